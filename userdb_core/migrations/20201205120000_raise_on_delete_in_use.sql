@@ -0,0 +1,87 @@
+------------------------------------------------------------
+-- Departments and titles which are still assigned to a   --
+-- person are no longer silently skipped when deleted.    --
+-- Instead the foreign key violation is raised, allowing  --
+-- the client to report why the delete failed. As before, --
+-- 0 is returned if nothing matches.                      --
+------------------------------------------------------------
+
+-----------------------------------
+--   DELETEDEPARTMENT
+-----------------------------------
+CREATE OR REPLACE FUNCTION deleteDepartment(
+    department TEXT
+) RETURNS INT AS
+$$
+DECLARE
+    dept_id INT;
+BEGIN
+    DELETE FROM
+        department
+    WHERE
+        department.name = deleteDepartment.department
+    RETURNING department.id INTO dept_id;
+    RETURN COALESCE(dept_id, 0);
+END;
+$$
+Language 'plpgsql';
+
+----------------------------------
+-- DELETEDEPARTMENTBYID
+----------------------------------
+CREATE OR REPLACE FUNCTION deleteDepartmentById(
+    id INT
+) RETURNS INT AS
+$$
+DECLARE
+    dept_id INT;
+BEGIN
+    DELETE FROM
+        department
+    WHERE
+        department.id = deleteDepartmentById.id
+    RETURNING department.id INTO dept_id;
+    RETURN COALESCE(dept_id, 0);
+END;
+$$
+Language 'plpgsql';
+
+-----------------------------------
+--   DELETETITLE
+-----------------------------------
+CREATE OR REPLACE FUNCTION deleteTitle(
+    title TEXT
+) RETURNS INT AS
+$$
+DECLARE
+    title_id INT;
+BEGIN
+    DELETE FROM
+        title
+    WHERE
+        title.name = deleteTitle.title
+    RETURNING title.id INTO title_id;
+    RETURN COALESCE(title_id, 0);
+END;
+$$
+Language 'plpgsql';
+
+----------------------------------
+-- DELETETITLEBYID
+----------------------------------
+CREATE OR REPLACE FUNCTION deleteTitleById(
+    id INT
+) RETURNS INT AS
+$$
+DECLARE
+    title_id INT;
+BEGIN
+    DELETE FROM
+        title
+    WHERE
+        title.id = deleteTitleById.id
+    RETURNING title.id INTO title_id;
+    RETURN COALESCE(title_id, 0);
+END;
+$$
+Language 'plpgsql';
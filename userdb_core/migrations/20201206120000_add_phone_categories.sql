@@ -0,0 +1,7 @@
+------------------------------------------------------------
+-- Pagers, faxes, and desk (direct line) phones get their --
+-- own categories, rather than being filed as Home/Cell.  --
+------------------------------------------------------------
+ALTER TYPE phonecategory ADD VALUE IF NOT EXISTS 'Pager';
+ALTER TYPE phonecategory ADD VALUE IF NOT EXISTS 'Fax';
+ALTER TYPE phonecategory ADD VALUE IF NOT EXISTS 'Desk';
@@ -0,0 +1,128 @@
+------------------------------------------------------------
+-- A person may have any number of phones of a category   --
+-- at a location. To tell them apart, a phone may carry a --
+-- label (eg 'work cell'), and each person may mark one   --
+-- phone per category as their primary.                   --
+------------------------------------------------------------
+ALTER TABLE phone ADD COLUMN IF NOT EXISTS label VARCHAR(256);
+
+ALTER TABLE people_phones ADD COLUMN IF NOT EXISTS is_primary BOOLEAN NOT NULL DEFAULT FALSE;
+
+-------------------------------------------
+-- ONEPRIMARYPERCATEGORY TRIGGER         --
+--                                       --
+-- Marking a phone as primary clears the --
+-- flag on the person's other phones of  --
+-- the same category.                    --
+-------------------------------------------
+CREATE OR REPLACE FUNCTION onePrimaryPerCategory() RETURNS TRIGGER AS
+$$
+BEGIN
+    UPDATE
+        people_phones
+    SET
+        is_primary = FALSE
+    WHERE
+        people_phones.person_id = NEW.person_id
+    AND
+        people_phones.phone_id <> NEW.phone_id
+    AND
+        people_phones.is_primary
+    AND
+        people_phones.phone_id IN (
+            SELECT
+                other.id
+            FROM
+                phone AS other
+                JOIN phone AS this ON this.category = other.category
+            WHERE
+                this.id = NEW.phone_id
+        );
+    RETURN NEW;
+END;
+$$
+Language 'plpgsql';
+
+DROP TRIGGER IF EXISTS one_primary_per_category ON people_phones;
+CREATE TRIGGER one_primary_per_category
+    AFTER INSERT OR UPDATE OF is_primary ON people_phones
+    FOR EACH ROW
+    WHEN (NEW.is_primary)
+    EXECUTE PROCEDURE onePrimaryPerCategory();
+
+---------------------
+-- PERSONVIEW VIEW --
+---------------------
+CREATE OR REPLACE VIEW personview AS
+WITH cte_pf AS (
+   SELECT pp.*,ph.number,ph.category,ph.location,ph.label
+   FROM
+      people_phones AS pp
+   JOIN phone as ph ON pp.phone_id = ph.id
+)
+SELECT 
+    p.id AS person_id, 
+    p.first, 
+    p.last, 
+    p.first || ' ' || p.last AS fullname, 
+    p.login,
+    dept.name AS department,
+    title.name AS title,
+    cte_pf.phone_id, 
+    cte_pf.number, 
+    cte_pf.category, 
+    cte_pf.location,
+    cte_pf.label,
+    cte_pf.is_primary
+FROM 
+    person p
+JOIN 
+    title 
+ON 
+    p.title_id = title.id
+JOIN 
+    department AS dept 
+ON 
+    p.department_id=dept.id
+LEFT JOIN 
+    cte_pf 
+ON 
+    p.id = cte_pf.person_id;
+
+-----------------------
+-- ADDPHONE FUNCTION --
+-----------------------
+-- As the original addPhone, but labeling the phone and optionally
+-- marking it as the person's primary phone of its category.
+CREATE OR REPLACE FUNCTION addPhone (
+    login text, 
+    number text, 
+    category phonecategory, 
+    site location,
+    label text,
+    is_primary boolean
+) RETURNS INT AS $$
+
+    WITH Y AS 
+        (INSERT 
+           INTO phone (number, category, location, label) 
+         VALUES (number, category, site, label)
+         ON CONFLICT DO NOTHING
+      RETURNING phone.id), 
+    X AS 
+        (SELECT id 
+           FROM person 
+          WHERE person.login=addPhone.login)
+    INSERT 
+      INTO people_phones (person_id, phone_id, is_primary)
+    SELECT 
+        X.id, Y.id, addPhone.is_primary
+    FROM 
+        X 
+    CROSS JOIN 
+        Y
+    ON CONFLICT DO NOTHING
+    RETURNING 
+        people_phones.phone_id;
+$$
+Language 'sql';
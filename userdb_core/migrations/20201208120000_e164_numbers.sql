@@ -0,0 +1,28 @@
+------------------------------------------------------------
+-- Numbers are stored in E.164 (eg +914012345678) so that --
+-- staff outside North America may be reached. Numbers    --
+-- may now start with a +. Extensions are internal, and   --
+-- remain as four digits.                                  --
+------------------------------------------------------------
+ALTER TABLE phone DROP CONSTRAINT IF EXISTS phone_number_check;
+ALTER TABLE phone ADD CONSTRAINT phone_number_check CHECK(number ~ '^\+?[0-9]+$');
+
+------------------------------------------------------------
+-- Convert existing national numbers using the country of --
+-- their location, with or without a trunk prefix.        --
+------------------------------------------------------------
+UPDATE phone SET number = '+1' || number
+ WHERE number ~ '^[0-9]{10}$'
+   AND location IN ('Portland', 'PlayaVista', 'Vancouver', 'Montreal');
+
+UPDATE phone SET number = '+' || number
+ WHERE number ~ '^1[0-9]{10}$'
+   AND location IN ('Portland', 'PlayaVista', 'Vancouver', 'Montreal');
+
+UPDATE phone SET number = '+91' || number
+ WHERE number ~ '^[0-9]{10}$'
+   AND location = 'Hyderabad';
+
+UPDATE phone SET number = '+91' || substr(number, 2)
+ WHERE number ~ '^0[0-9]{10}$'
+   AND location = 'Hyderabad';
@@ -0,0 +1,153 @@
+------------------------------------------------------------
+-- Each location has a dial plan: the main switchboard    --
+-- number, the ranges its extensions are drawn from, and  --
+-- the prefix which, followed by an extension, dials it   --
+-- directly from outside (its DID).                        --
+------------------------------------------------------------
+
+--------------------
+-- DIALPLAN TABLE --
+--------------------
+CREATE TABLE IF NOT EXISTS dialplan
+(
+    location    location    PRIMARY KEY,
+    switchboard VARCHAR(30) NOT NULL CHECK(switchboard ~ '^\+[0-9]+$'),
+    did_prefix  VARCHAR(30) NOT NULL CHECK(did_prefix ~ '^\+[0-9]+$')
+);
+
+---------------------------
+-- EXTENSION_RANGE TABLE --
+---------------------------
+CREATE TABLE IF NOT EXISTS extension_range
+(
+    id        SERIAL   PRIMARY KEY,
+    location  location NOT NULL,
+    first_ext INT      NOT NULL,
+    last_ext  INT      NOT NULL,
+    CHECK(first_ext <= last_ext),
+    FOREIGN KEY (location) REFERENCES dialplan(location) ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+INSERT INTO dialplan (location, switchboard, did_prefix) VALUES
+    ('Portland',   '+15035550100',  '+1503555'),
+    ('PlayaVista', '+13105550100',  '+1310555'),
+    ('Vancouver',  '+16045550100',  '+1604555'),
+    ('Montreal',   '+15145550100',  '+1514555'),
+    ('Hyderabad',  '+914066120000', '+91406612')
+ON CONFLICT DO NOTHING;
+
+INSERT INTO extension_range (location, first_ext, last_ext) VALUES
+    ('Portland',   5000, 5999),
+    ('PlayaVista', 3000, 3999),
+    ('Vancouver',  6000, 6999),
+    ('Montreal',   8000, 9999),
+    ('Hyderabad',  4000, 4999);
+
+-------------------------------------------
+-- CHECKEXTENSION TRIGGER                --
+--                                       --
+-- Extensions at a location with a dial  --
+-- plan must fall within one of its      --
+-- ranges. Existing extensions are only  --
+-- checked when they change.             --
+-------------------------------------------
+CREATE OR REPLACE FUNCTION checkExtension() RETURNS TRIGGER AS
+$$
+BEGIN
+    IF EXISTS (SELECT 1 FROM dialplan WHERE dialplan.location = NEW.location)
+    AND NOT EXISTS (
+        SELECT
+            1
+        FROM
+            extension_range AS r
+        WHERE
+            r.location = NEW.location
+        AND
+            NEW.number ~ '^[0-9]{1,9}$'
+        AND
+            NEW.number::int BETWEEN r.first_ext AND r.last_ext
+    ) THEN
+        RAISE EXCEPTION 'extension % is outside the dial plan for %', NEW.number, NEW.location
+            USING ERRCODE = 'check_violation',
+                  CONSTRAINT = 'phone_extension_check',
+                  DETAIL = format('Key (number, location)=(%s, %s) is outside the dial plan.', NEW.number, NEW.location);
+    END IF;
+    RETURN NEW;
+END;
+$$
+Language 'plpgsql';
+
+DROP TRIGGER IF EXISTS check_extension ON phone;
+CREATE TRIGGER check_extension
+    BEFORE INSERT OR UPDATE OF number, category, location ON phone
+    FOR EACH ROW
+    WHEN (NEW.category = 'Extension')
+    EXECUTE PROCEDURE checkExtension();
+
+---------------------------
+-- EXTENSIONDID FUNCTION --
+---------------------------
+-- The number which dials an extension directly from outside, or
+-- NULL for other categories and extensions outside the dial plan.
+CREATE OR REPLACE FUNCTION extensionDid (
+    number text,
+    category phonecategory,
+    site location
+) RETURNS TEXT AS $$
+    SELECT
+        dialplan.did_prefix || extensionDid.number
+    FROM
+        dialplan
+    JOIN
+        extension_range AS r ON r.location = dialplan.location
+    WHERE
+        extensionDid.category = 'Extension'
+    AND
+        dialplan.location = extensionDid.site
+    AND
+        extensionDid.number ~ '^[0-9]{1,9}$'
+    AND
+        extensionDid.number::int BETWEEN r.first_ext AND r.last_ext
+    LIMIT 1;
+$$
+Language 'sql' STABLE;
+
+---------------------
+-- PERSONVIEW VIEW --
+---------------------
+CREATE OR REPLACE VIEW personview AS
+WITH cte_pf AS (
+   SELECT pp.*,ph.number,ph.category,ph.location,ph.label
+   FROM
+      people_phones AS pp
+   JOIN phone as ph ON pp.phone_id = ph.id
+)
+SELECT
+    p.id AS person_id,
+    p.first,
+    p.last,
+    p.first || ' ' || p.last AS fullname,
+    p.login,
+    dept.name AS department,
+    title.name AS title,
+    cte_pf.phone_id,
+    cte_pf.number,
+    cte_pf.category,
+    cte_pf.location,
+    cte_pf.label,
+    cte_pf.is_primary,
+    extensionDid(cte_pf.number, cte_pf.category, cte_pf.location) AS did
+FROM
+    person p
+JOIN
+    title
+ON
+    p.title_id = title.id
+JOIN
+    department AS dept
+ON
+    p.department_id=dept.id
+LEFT JOIN
+    cte_pf
+ON
+    p.id = cte_pf.person_id;
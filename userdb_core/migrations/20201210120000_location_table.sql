@@ -0,0 +1,306 @@
+------------------------------------------------------------
+-- Locations move from the `location` enum into a table,  --
+-- so that a studio may be opened without a migration.    --
+-- Each location has a unique name and short code, along  --
+-- with any aliases it is known by, its address, timezone --
+-- and the ISO code of its country. Phones and dial plans --
+-- refer to their location by name.                       --
+------------------------------------------------------------
+
+-- the table takes the enum's name, so move the enum out of the way
+ALTER TYPE location RENAME TO location_enum;
+
+------------------------------------------
+-- ISTIMEZONE FUNCTION                  --
+--                                      --
+-- Is the name one postgres knows as a  --
+-- timezone (eg America/Los_Angeles)    --
+------------------------------------------
+CREATE OR REPLACE FUNCTION isTimezone(
+    name text
+) RETURNS BOOLEAN AS $$
+    SELECT EXISTS (SELECT 1 FROM pg_timezone_names WHERE pg_timezone_names.name = isTimezone.name);
+$$
+Language 'sql' STABLE;
+
+--------------------
+-- LOCATION TABLE --
+--------------------
+CREATE TABLE IF NOT EXISTS location
+(
+    id       SERIAL       PRIMARY KEY,
+    name     VARCHAR(256) NOT NULL UNIQUE,
+    code     VARCHAR(16)  NOT NULL UNIQUE,
+    aliases  TEXT[]       NOT NULL DEFAULT '{}',
+    address  TEXT,
+    timezone VARCHAR(64)  NOT NULL DEFAULT 'UTC',
+    country  VARCHAR(2)   NOT NULL,
+    CONSTRAINT location_timezone_check CHECK(isTimezone(timezone)),
+    CONSTRAINT location_country_check CHECK(country ~ '^[A-Z]{2}$')
+);
+
+INSERT INTO location (name, code, aliases, timezone, country) VALUES
+    ('Portland',   'pd', '{}',                                         'America/Los_Angeles', 'US'),
+    ('PlayaVista', 'pv', '{playa, "playa vista", playa-vista, venice}', 'America/Los_Angeles', 'US'),
+    ('Vancouver',  'bc', '{}',                                         'America/Vancouver',   'CA'),
+    ('Montreal',   'mt', '{}',                                         'America/Toronto',     'CA'),
+    ('Hyderabad',  'hb', '{}',                                         'Asia/Kolkata',        'IN')
+ON CONFLICT DO NOTHING;
+
+------------------------------------------------------------
+-- Point phones and dial plans at the table. The view and --
+-- trigger depending on the columns are dropped, and      --
+-- recreated below.                                       --
+------------------------------------------------------------
+DROP VIEW IF EXISTS personview;
+DROP TRIGGER IF EXISTS check_extension ON phone;
+ALTER TABLE extension_range DROP CONSTRAINT IF EXISTS extension_range_location_fkey;
+
+ALTER TABLE phone ALTER COLUMN location TYPE VARCHAR(256) USING location::text;
+ALTER TABLE dialplan ALTER COLUMN location TYPE VARCHAR(256) USING location::text;
+ALTER TABLE extension_range ALTER COLUMN location TYPE VARCHAR(256) USING location::text;
+
+ALTER TABLE phone ADD CONSTRAINT fk_phone_location
+    FOREIGN KEY (location) REFERENCES location(name) ON UPDATE CASCADE;
+ALTER TABLE dialplan ADD CONSTRAINT fk_dialplan_location
+    FOREIGN KEY (location) REFERENCES location(name) ON UPDATE CASCADE ON DELETE CASCADE;
+ALTER TABLE extension_range ADD CONSTRAINT extension_range_location_fkey
+    FOREIGN KEY (location) REFERENCES dialplan(location) ON UPDATE CASCADE ON DELETE CASCADE;
+
+-------------------------------------------
+-- CHECKEXTENSION TRIGGER                --
+--                                       --
+-- As before, but inserting a phone      --
+-- which already exists is left to its   --
+-- ON CONFLICT clause, so that restoring --
+-- a snapshot does not trip over         --
+-- existing extensions.                  --
+-------------------------------------------
+CREATE OR REPLACE FUNCTION checkExtension() RETURNS TRIGGER AS
+$$
+BEGIN
+    IF TG_OP = 'INSERT' AND EXISTS (
+        SELECT
+            1
+        FROM
+            phone
+        WHERE
+            phone.number = NEW.number
+        AND
+            phone.category = NEW.category
+        AND
+            phone.location = NEW.location
+    ) THEN
+        RETURN NEW;
+    END IF;
+    IF EXISTS (SELECT 1 FROM dialplan WHERE dialplan.location = NEW.location)
+    AND NOT EXISTS (
+        SELECT
+            1
+        FROM
+            extension_range AS r
+        WHERE
+            r.location = NEW.location
+        AND
+            NEW.number ~ '^[0-9]{1,9}$'
+        AND
+            NEW.number::int BETWEEN r.first_ext AND r.last_ext
+    ) THEN
+        RAISE EXCEPTION 'extension % is outside the dial plan for %', NEW.number, NEW.location
+            USING ERRCODE = 'check_violation',
+                  CONSTRAINT = 'phone_extension_check',
+                  DETAIL = format('Key (number, location)=(%s, %s) is outside the dial plan.', NEW.number, NEW.location);
+    END IF;
+    RETURN NEW;
+END;
+$$
+Language 'plpgsql';
+
+CREATE TRIGGER check_extension
+    BEFORE INSERT OR UPDATE OF number, category, location ON phone
+    FOR EACH ROW
+    WHEN (NEW.category = 'Extension')
+    EXECUTE PROCEDURE checkExtension();
+
+------------------------------------------------------------
+-- The functions taking a location enum are replaced by   --
+-- ones taking the location's name.                       --
+------------------------------------------------------------
+DROP FUNCTION IF EXISTS addPhone(text, text, phonecategory, location_enum);
+DROP FUNCTION IF EXISTS addPhone(text, text, phonecategory, location_enum, text, boolean);
+DROP FUNCTION IF EXISTS deletePhone(text, text, phonecategory, location_enum);
+DROP FUNCTION IF EXISTS extensionDid(text, phonecategory, location_enum);
+
+-----------------------
+-- ADDPHONE FUNCTION --
+-----------------------
+CREATE OR REPLACE FUNCTION addPhone (
+    login text,
+    number text,
+    category phonecategory,
+    site text,
+    label text,
+    is_primary boolean
+) RETURNS INT AS $$
+
+    WITH Y AS
+        (INSERT
+           INTO phone (number, category, location, label)
+         VALUES (number, category, site, label)
+         ON CONFLICT DO NOTHING
+      RETURNING phone.id),
+    X AS
+        (SELECT id
+           FROM person
+          WHERE person.login=addPhone.login)
+    INSERT
+      INTO people_phones (person_id, phone_id, is_primary)
+    SELECT
+        X.id, Y.id, addPhone.is_primary
+    FROM
+        X
+    CROSS JOIN
+        Y
+    ON CONFLICT DO NOTHING
+    RETURNING
+        people_phones.phone_id;
+$$
+Language 'sql';
+
+-- as above, neither labeled nor primary
+CREATE OR REPLACE FUNCTION addPhone (
+    login text,
+    number text,
+    category phonecategory,
+    site text
+) RETURNS INT AS $$
+    SELECT addPhone(login, number, category, site, NULL, FALSE);
+$$
+Language 'sql';
+
+---------------------------------------------------------
+-- DELETEPHONE                                         --
+--                                                     --
+-- Given a login, a number a category and a location   --
+-- (a) Remove the association between phone and person --
+-- (b) Delete the phone if it is not associated with   --
+--     additional people.                              --
+---------------------------------------------------------
+CREATE OR REPLACE FUNCTION deletePhone(
+    login text,
+    number text,
+    category phonecategory,
+    site text
+
+) RETURNS int AS $$
+DECLARE
+    target_person_id person.id%type;
+    target_phone_id phone.id%type;
+BEGIN
+    SELECT
+        id
+    INTO
+        target_person_id
+    FROM
+        person
+    WHERE
+        person.login = deletePhone.login;
+
+    IF NOT found THEN
+        RETURN 0;
+    END IF;
+
+    SELECT
+        id
+    INTO
+        target_phone_id
+    FROM
+        phone
+    WHERE
+        deletePhone.number = phone.number
+    AND
+        deletePhone.category = phone.category
+    AND
+        deletePhone.site = phone.location;
+
+    IF NOT found THEN
+        return 0;
+    END IF;
+
+    RETURN (
+        SELECT
+            *
+        FROM
+            deletePhoneFromIds(target_person_id, target_phone_id)
+    );
+END;
+$$
+Language 'plpgsql';
+
+---------------------------
+-- EXTENSIONDID FUNCTION --
+---------------------------
+CREATE OR REPLACE FUNCTION extensionDid (
+    number text,
+    category phonecategory,
+    site text
+) RETURNS TEXT AS $$
+    SELECT
+        dialplan.did_prefix || extensionDid.number
+    FROM
+        dialplan
+    JOIN
+        extension_range AS r ON r.location = dialplan.location
+    WHERE
+        extensionDid.category = 'Extension'
+    AND
+        dialplan.location = extensionDid.site
+    AND
+        extensionDid.number ~ '^[0-9]{1,9}$'
+    AND
+        extensionDid.number::int BETWEEN r.first_ext AND r.last_ext
+    LIMIT 1;
+$$
+Language 'sql' STABLE;
+
+---------------------
+-- PERSONVIEW VIEW --
+---------------------
+CREATE OR REPLACE VIEW personview AS
+WITH cte_pf AS (
+   SELECT pp.*,ph.number,ph.category,ph.location,ph.label
+   FROM
+      people_phones AS pp
+   JOIN phone as ph ON pp.phone_id = ph.id
+)
+SELECT
+    p.id AS person_id,
+    p.first,
+    p.last,
+    p.first || ' ' || p.last AS fullname,
+    p.login,
+    dept.name AS department,
+    title.name AS title,
+    cte_pf.phone_id,
+    cte_pf.number,
+    cte_pf.category,
+    cte_pf.location,
+    cte_pf.label,
+    cte_pf.is_primary,
+    extensionDid(cte_pf.number, cte_pf.category, cte_pf.location) AS did
+FROM
+    person p
+JOIN
+    title
+ON
+    p.title_id = title.id
+JOIN
+    department AS dept
+ON
+    p.department_id=dept.id
+LEFT JOIN
+    cte_pf
+ON
+    p.id = cte_pf.person_id;
+
+DROP TYPE location_enum;
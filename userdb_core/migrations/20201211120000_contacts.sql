@@ -0,0 +1,71 @@
+------------------------------------------------------------
+-- Besides phones, a person may be reached through other  --
+-- contact channels: their work email, a chat handle (eg  --
+-- on Slack or Teams), or a personal email. As with       --
+-- phones, a contact may carry a label, and each person   --
+-- may mark one contact per category as their primary.    --
+------------------------------------------------------------
+
+---------------------------
+-- CONTACTCATEGORY TYPE --
+---------------------------
+CREATE TYPE contactcategory AS ENUM
+(
+    'Email',
+    'Chat',
+    'PersonalEmail'
+);
+
+-------------------
+-- CONTACT TABLE --
+-------------------
+CREATE TABLE IF NOT EXISTS contact
+(
+    id         SERIAL          PRIMARY KEY,
+    person_id  INT             NOT NULL,
+    category   contactcategory NOT NULL,
+    value      VARCHAR(256)    NOT NULL,
+    label      VARCHAR(256),
+    is_primary BOOLEAN         NOT NULL DEFAULT FALSE,
+    UNIQUE(person_id, category, value),
+    CONSTRAINT fk_contact_person
+        FOREIGN KEY (person_id)
+            REFERENCES person(id) ON UPDATE CASCADE ON DELETE CASCADE,
+    CONSTRAINT contact_value_check CHECK(value <> ''),
+    CONSTRAINT contact_email_check
+        CHECK(category = 'Chat' OR value ~ '^[^@[:space:]]+@[^@[:space:]]+\.[^@[:space:]]+$')
+);
+
+---------------------------------------------
+-- ONEPRIMARYCONTACTPERCATEGORY TRIGGER    --
+--                                         --
+-- Marking a contact as primary clears the --
+-- flag on the person's other contacts of  --
+-- the same category.                      --
+---------------------------------------------
+CREATE OR REPLACE FUNCTION onePrimaryContactPerCategory() RETURNS TRIGGER AS
+$$
+BEGIN
+    UPDATE
+        contact
+    SET
+        is_primary = FALSE
+    WHERE
+        contact.person_id = NEW.person_id
+    AND
+        contact.category = NEW.category
+    AND
+        contact.id <> NEW.id
+    AND
+        contact.is_primary;
+    RETURN NEW;
+END;
+$$
+Language 'plpgsql';
+
+DROP TRIGGER IF EXISTS one_primary_contact_per_category ON contact;
+CREATE TRIGGER one_primary_contact_per_category
+    AFTER INSERT OR UPDATE OF is_primary, category ON contact
+    FOR EACH ROW
+    WHEN (NEW.is_primary)
+    EXECUTE PROCEDURE onePrimaryContactPerCategory();
@@ -0,0 +1,98 @@
+------------------------------------------------------------
+-- A person may report to a manager, who is another       --
+-- person. Reporting may not loop back on itself, so      --
+-- following managers upward always ends at someone       --
+-- without one. Deleting a manager leaves their reports   --
+-- without a manager.                                     --
+------------------------------------------------------------
+ALTER TABLE person ADD COLUMN IF NOT EXISTS manager_id INT;
+
+ALTER TABLE person ADD CONSTRAINT fk_manager
+    FOREIGN KEY (manager_id) REFERENCES person(id) ON UPDATE CASCADE ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS person_manager_id_idx ON person (manager_id);
+
+-------------------------------------------
+-- CHECKMANAGER TRIGGER                  --
+--                                       --
+-- Walk up from the new manager. Should  --
+-- we reach the person being updated,    --
+-- the change would create a cycle.      --
+-------------------------------------------
+CREATE OR REPLACE FUNCTION checkManager() RETURNS TRIGGER AS
+$$
+DECLARE
+    manager_login person.login%type;
+BEGIN
+    IF EXISTS (
+        WITH RECURSIVE chain(id, manager_id) AS (
+            SELECT id, manager_id FROM person WHERE id = NEW.manager_id
+        UNION
+            SELECT person.id, person.manager_id
+            FROM person JOIN chain ON person.id = chain.manager_id
+        )
+        SELECT 1 FROM chain WHERE chain.id = NEW.id
+    ) THEN
+        SELECT login INTO manager_login FROM person WHERE id = NEW.manager_id;
+        RAISE EXCEPTION '% may not report to %', NEW.login, manager_login
+            USING ERRCODE = 'check_violation',
+                  CONSTRAINT = 'person_manager_cycle_check',
+                  DETAIL = format('Key (login, manager)=(%s, %s) would create a cycle.', NEW.login, manager_login);
+    END IF;
+    RETURN NEW;
+END;
+$$
+Language 'plpgsql';
+
+DROP TRIGGER IF EXISTS check_manager ON person;
+CREATE TRIGGER check_manager
+    BEFORE INSERT OR UPDATE OF manager_id ON person
+    FOR EACH ROW
+    WHEN (NEW.manager_id IS NOT NULL)
+    EXECUTE PROCEDURE checkManager();
+
+---------------------
+-- PERSONVIEW VIEW --
+---------------------
+-- as before, with the login of the person's manager
+CREATE OR REPLACE VIEW personview AS
+WITH cte_pf AS (
+   SELECT pp.*,ph.number,ph.category,ph.location,ph.label
+   FROM
+      people_phones AS pp
+   JOIN phone as ph ON pp.phone_id = ph.id
+)
+SELECT
+    p.id AS person_id,
+    p.first,
+    p.last,
+    p.first || ' ' || p.last AS fullname,
+    p.login,
+    dept.name AS department,
+    title.name AS title,
+    cte_pf.phone_id,
+    cte_pf.number,
+    cte_pf.category,
+    cte_pf.location,
+    cte_pf.label,
+    cte_pf.is_primary,
+    extensionDid(cte_pf.number, cte_pf.category, cte_pf.location) AS did,
+    manager.login AS manager
+FROM
+    person p
+JOIN
+    title
+ON
+    p.title_id = title.id
+JOIN
+    department AS dept
+ON
+    p.department_id=dept.id
+LEFT JOIN
+    person AS manager
+ON
+    p.manager_id = manager.id
+LEFT JOIN
+    cte_pf
+ON
+    p.id = cte_pf.person_id;
@@ -0,0 +1,29 @@
+------------------------------------------------------------
+-- Where a person sits within a site: the building, floor --
+-- and room (or desk). People working from more than one  --
+-- location may have a desk at each of them, while a desk --
+-- without a location is simply the person's desk.        --
+------------------------------------------------------------
+
+----------------
+-- DESK TABLE --
+----------------
+CREATE TABLE IF NOT EXISTS desk
+(
+    id        SERIAL       PRIMARY KEY,
+    person_id INT          NOT NULL,
+    location  VARCHAR(256),
+    building  VARCHAR(256),
+    floor     VARCHAR(256),
+    room      VARCHAR(256),
+    CONSTRAINT fk_desk_person
+        FOREIGN KEY (person_id)
+            REFERENCES person(id) ON UPDATE CASCADE ON DELETE CASCADE,
+    CONSTRAINT fk_desk_location
+        FOREIGN KEY (location)
+            REFERENCES location(name) ON UPDATE CASCADE
+);
+
+-- one desk per person and location, counting the missing location as one
+CREATE UNIQUE INDEX IF NOT EXISTS desk_person_id_location_key
+    ON desk (person_id, (COALESCE(location, '')));
@@ -0,0 +1,74 @@
+------------------------------------------------------------
+-- Each person has a status, and the dates they started   --
+-- and finished. Rather than deleting people when they    --
+-- leave, they are marked as terminated, keeping their    --
+-- record until it is purged after a retention period.    --
+------------------------------------------------------------
+
+------------------------
+-- PERSONSTATUS TYPE --
+------------------------
+CREATE TYPE personstatus AS ENUM
+(
+    'Active',
+    'OnLeave',
+    'Contractor',
+    'Terminated'
+);
+
+ALTER TABLE person ADD COLUMN IF NOT EXISTS status personstatus NOT NULL DEFAULT 'Active';
+ALTER TABLE person ADD COLUMN IF NOT EXISTS start_date DATE;
+ALTER TABLE person ADD COLUMN IF NOT EXISTS end_date DATE;
+
+ALTER TABLE person ADD CONSTRAINT person_dates_check CHECK(end_date >= start_date);
+
+CREATE INDEX IF NOT EXISTS person_status_idx ON person (status);
+
+---------------------
+-- PERSONVIEW VIEW --
+---------------------
+-- as before, with the person's status and dates
+CREATE OR REPLACE VIEW personview AS
+WITH cte_pf AS (
+   SELECT pp.*,ph.number,ph.category,ph.location,ph.label
+   FROM
+      people_phones AS pp
+   JOIN phone as ph ON pp.phone_id = ph.id
+)
+SELECT
+    p.id AS person_id,
+    p.first,
+    p.last,
+    p.first || ' ' || p.last AS fullname,
+    p.login,
+    dept.name AS department,
+    title.name AS title,
+    cte_pf.phone_id,
+    cte_pf.number,
+    cte_pf.category,
+    cte_pf.location,
+    cte_pf.label,
+    cte_pf.is_primary,
+    extensionDid(cte_pf.number, cte_pf.category, cte_pf.location) AS did,
+    manager.login AS manager,
+    p.status,
+    p.start_date,
+    p.end_date
+FROM
+    person p
+JOIN
+    title
+ON
+    p.title_id = title.id
+JOIN
+    department AS dept
+ON
+    p.department_id=dept.id
+LEFT JOIN
+    person AS manager
+ON
+    p.manager_id = manager.id
+LEFT JOIN
+    cte_pf
+ON
+    p.id = cte_pf.person_id;
@@ -0,0 +1,113 @@
+------------------------------------------------------------
+-- An audit trail of every change made to the userdb.     --
+-- Each insert, update and delete is recorded by trigger, --
+-- along with who made it and the row before and after.   --
+-- The actor is read from the userdb.actor setting, which --
+-- clients set as they connect, falling back on the       --
+-- database user.                                         --
+------------------------------------------------------------
+
+-----------------------
+-- AUDITACTION TYPE --
+-----------------------
+CREATE TYPE auditaction AS ENUM
+(
+    'Create',
+    'Update',
+    'Delete'
+);
+
+-----------------
+-- AUDIT TABLE --
+-----------------
+CREATE TABLE IF NOT EXISTS audit
+(
+    id        BIGSERIAL    PRIMARY KEY,
+    at        TIMESTAMPTZ  NOT NULL DEFAULT now(),
+    actor     VARCHAR(256) NOT NULL,
+    action    auditaction  NOT NULL,
+    entity    VARCHAR(64)  NOT NULL,
+    entity_id INT,
+    login     VARCHAR(256),
+    before    JSONB,
+    after     JSONB
+);
+
+CREATE INDEX IF NOT EXISTS audit_at_idx ON audit (at);
+CREATE INDEX IF NOT EXISTS audit_entity_idx ON audit (entity, entity_id);
+CREATE INDEX IF NOT EXISTS audit_login_idx ON audit (login);
+CREATE INDEX IF NOT EXISTS audit_actor_idx ON audit (actor);
+
+-----------------------------
+-- AUDITCHANGE() FUNCTION --
+-----------------------------
+-- Record the change to the row in the audit table. The entity is the
+-- name of the table, and the login is that of the person the row
+-- belongs to, if any. When the person has already been deleted, as with
+-- rows removed along with them, their login is taken from their own
+-- audit trail. Updates which change nothing are not recorded.
+CREATE OR REPLACE FUNCTION auditChange()
+RETURNS TRIGGER AS $$
+DECLARE
+    old_row JSONB;
+    new_row JSONB;
+    cur_row JSONB;
+    row_login VARCHAR(256);
+BEGIN
+    IF TG_OP <> 'INSERT' THEN
+        old_row := to_jsonb(OLD);
+    END IF;
+    IF TG_OP <> 'DELETE' THEN
+        new_row := to_jsonb(NEW);
+    END IF;
+    IF TG_OP = 'UPDATE' AND old_row = new_row THEN
+        RETURN NULL;
+    END IF;
+    cur_row := COALESCE(new_row, old_row);
+    IF TG_TABLE_NAME = 'person' THEN
+        row_login := cur_row->>'login';
+    ELSIF cur_row ? 'person_id' THEN
+        SELECT login INTO row_login FROM person WHERE id = (cur_row->>'person_id')::int;
+        IF row_login IS NULL THEN
+            SELECT login INTO row_login FROM audit
+            WHERE entity = 'person' AND entity_id = (cur_row->>'person_id')::int
+            ORDER BY id DESC LIMIT 1;
+        END IF;
+    END IF;
+    INSERT INTO audit (actor, action, entity, entity_id, login, before, after)
+    VALUES (
+        COALESCE(NULLIF(current_setting('userdb.actor', true), ''), session_user),
+        CASE TG_OP WHEN 'INSERT' THEN 'Create' WHEN 'UPDATE' THEN 'Update' ELSE 'Delete' END::auditaction,
+        TG_TABLE_NAME,
+        (cur_row->>'id')::int,
+        row_login,
+        old_row,
+        new_row
+    );
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+--------------------
+-- AUDIT TRIGGERS --
+--------------------
+CREATE TRIGGER audit_person AFTER INSERT OR UPDATE OR DELETE ON person
+    FOR EACH ROW EXECUTE PROCEDURE auditChange();
+CREATE TRIGGER audit_phone AFTER INSERT OR UPDATE OR DELETE ON phone
+    FOR EACH ROW EXECUTE PROCEDURE auditChange();
+CREATE TRIGGER audit_people_phones AFTER INSERT OR UPDATE OR DELETE ON people_phones
+    FOR EACH ROW EXECUTE PROCEDURE auditChange();
+CREATE TRIGGER audit_contact AFTER INSERT OR UPDATE OR DELETE ON contact
+    FOR EACH ROW EXECUTE PROCEDURE auditChange();
+CREATE TRIGGER audit_desk AFTER INSERT OR UPDATE OR DELETE ON desk
+    FOR EACH ROW EXECUTE PROCEDURE auditChange();
+CREATE TRIGGER audit_title AFTER INSERT OR UPDATE OR DELETE ON title
+    FOR EACH ROW EXECUTE PROCEDURE auditChange();
+CREATE TRIGGER audit_department AFTER INSERT OR UPDATE OR DELETE ON department
+    FOR EACH ROW EXECUTE PROCEDURE auditChange();
+CREATE TRIGGER audit_location AFTER INSERT OR UPDATE OR DELETE ON location
+    FOR EACH ROW EXECUTE PROCEDURE auditChange();
+CREATE TRIGGER audit_dialplan AFTER INSERT OR UPDATE OR DELETE ON dialplan
+    FOR EACH ROW EXECUTE PROCEDURE auditChange();
+CREATE TRIGGER audit_extension_range AFTER INSERT OR UPDATE OR DELETE ON extension_range
+    FOR EACH ROW EXECUTE PROCEDURE auditChange();
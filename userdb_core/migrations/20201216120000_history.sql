@@ -0,0 +1,196 @@
+------------------------------------------------------------
+-- Point in time history of people, phones and the phones --
+-- assigned to people. Every version of a row is kept in  --
+-- a history table, valid from the moment it was written  --
+-- until the moment it was changed or deleted. The        --
+-- current version has no valid_to.                       --
+--                                                        --
+-- History begins when this migration is applied, so the  --
+-- rows which exist at that point are taken to have       --
+-- always existed. Columns later added to person, phone   --
+-- or people_phones must be added to their history tables --
+-- as well.                                               --
+------------------------------------------------------------
+
+---------------------------
+-- PERSON_HISTORY TABLE --
+---------------------------
+CREATE TABLE IF NOT EXISTS person_history
+(
+    LIKE person,
+    valid_from TIMESTAMPTZ NOT NULL,
+    valid_to   TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS person_history_id_idx ON person_history (id, valid_from);
+
+--------------------------
+-- PHONE_HISTORY TABLE --
+--------------------------
+CREATE TABLE IF NOT EXISTS phone_history
+(
+    LIKE phone,
+    valid_from TIMESTAMPTZ NOT NULL,
+    valid_to   TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS phone_history_id_idx ON phone_history (id, valid_from);
+
+----------------------------------
+-- PEOPLE_PHONES_HISTORY TABLE --
+----------------------------------
+CREATE TABLE IF NOT EXISTS people_phones_history
+(
+    LIKE people_phones,
+    valid_from TIMESTAMPTZ NOT NULL,
+    valid_to   TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS people_phones_history_id_idx ON people_phones_history (person_id, phone_id, valid_from);
+
+INSERT INTO person_history SELECT *, '-infinity', NULL FROM person;
+INSERT INTO phone_history SELECT *, '-infinity', NULL FROM phone;
+INSERT INTO people_phones_history SELECT *, '-infinity', NULL FROM people_phones;
+
+--------------------------------
+-- RECORD HISTORY FUNCTIONS --
+--------------------------------
+-- Close the current version of the row, if there is one, and open a
+-- new one, unless the row was deleted. Updates which change nothing
+-- leave the history alone. Versions written and replaced within the
+-- same transaction are valid for no time at all.
+CREATE OR REPLACE FUNCTION recordPersonHistory()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'UPDATE' AND OLD IS NOT DISTINCT FROM NEW THEN
+        RETURN NULL;
+    END IF;
+    IF TG_OP <> 'INSERT' THEN
+        UPDATE person_history SET valid_to = now()
+        WHERE id = OLD.id AND valid_to IS NULL;
+    END IF;
+    IF TG_OP <> 'DELETE' THEN
+        INSERT INTO person_history SELECT NEW.*, now(), NULL;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION recordPhoneHistory()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'UPDATE' AND OLD IS NOT DISTINCT FROM NEW THEN
+        RETURN NULL;
+    END IF;
+    IF TG_OP <> 'INSERT' THEN
+        UPDATE phone_history SET valid_to = now()
+        WHERE id = OLD.id AND valid_to IS NULL;
+    END IF;
+    IF TG_OP <> 'DELETE' THEN
+        INSERT INTO phone_history SELECT NEW.*, now(), NULL;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION recordPeoplePhonesHistory()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'UPDATE' AND OLD IS NOT DISTINCT FROM NEW THEN
+        RETURN NULL;
+    END IF;
+    IF TG_OP <> 'INSERT' THEN
+        UPDATE people_phones_history SET valid_to = now()
+        WHERE person_id = OLD.person_id AND phone_id = OLD.phone_id AND valid_to IS NULL;
+    END IF;
+    IF TG_OP <> 'DELETE' THEN
+        INSERT INTO people_phones_history SELECT NEW.*, now(), NULL;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER record_person_history AFTER INSERT OR UPDATE OR DELETE ON person
+    FOR EACH ROW EXECUTE PROCEDURE recordPersonHistory();
+CREATE TRIGGER record_phone_history AFTER INSERT OR UPDATE OR DELETE ON phone
+    FOR EACH ROW EXECUTE PROCEDURE recordPhoneHistory();
+CREATE TRIGGER record_people_phones_history AFTER INSERT OR UPDATE OR DELETE ON people_phones
+    FOR EACH ROW EXECUTE PROCEDURE recordPeoplePhonesHistory();
+
+------------------------
+-- AS OF FUNCTIONS --
+------------------------
+-- The versions of the rows as they stood at the end of the supplied day
+CREATE OR REPLACE FUNCTION person_as_of(day DATE)
+RETURNS SETOF person_history AS $$
+    SELECT * FROM person_history
+    WHERE valid_from < day + 1 AND (valid_to IS NULL OR valid_to >= day + 1);
+$$ LANGUAGE sql STABLE;
+
+CREATE OR REPLACE FUNCTION phone_as_of(day DATE)
+RETURNS SETOF phone_history AS $$
+    SELECT * FROM phone_history
+    WHERE valid_from < day + 1 AND (valid_to IS NULL OR valid_to >= day + 1);
+$$ LANGUAGE sql STABLE;
+
+CREATE OR REPLACE FUNCTION people_phones_as_of(day DATE)
+RETURNS SETOF people_phones_history AS $$
+    SELECT * FROM people_phones_history
+    WHERE valid_from < day + 1 AND (valid_to IS NULL OR valid_to >= day + 1);
+$$ LANGUAGE sql STABLE;
+
+-----------------------------------
+-- PERSONVIEW_AS_OF() FUNCTION --
+-----------------------------------
+-- personview, as it stood at the end of the supplied day. Titles,
+-- departments and the dial plan are as they are now. It returns rows
+-- of personview, so must be replaced whenever personview is.
+CREATE OR REPLACE FUNCTION personview_as_of(day DATE)
+RETURNS SETOF personview AS $$
+WITH p AS (
+   SELECT * FROM person_as_of(day)
+),
+cte_pf AS (
+   SELECT pp.person_id, pp.phone_id, pp.is_primary, ph.number, ph.category, ph.location, ph.label
+   FROM
+      people_phones_as_of(day) AS pp
+   JOIN phone_as_of(day) AS ph ON pp.phone_id = ph.id
+)
+SELECT
+    p.id AS person_id,
+    p.first,
+    p.last,
+    p.first || ' ' || p.last AS fullname,
+    p.login,
+    dept.name AS department,
+    title.name AS title,
+    cte_pf.phone_id,
+    cte_pf.number,
+    cte_pf.category,
+    cte_pf.location,
+    cte_pf.label,
+    cte_pf.is_primary,
+    extensionDid(cte_pf.number, cte_pf.category, cte_pf.location) AS did,
+    manager.login AS manager,
+    p.status,
+    p.start_date,
+    p.end_date
+FROM
+    p
+JOIN
+    title
+ON
+    p.title_id = title.id
+JOIN
+    department AS dept
+ON
+    p.department_id=dept.id
+LEFT JOIN
+    p AS manager
+ON
+    p.manager_id = manager.id
+LEFT JOIN
+    cte_pf
+ON
+    p.id = cte_pf.person_id;
+$$ LANGUAGE sql STABLE;
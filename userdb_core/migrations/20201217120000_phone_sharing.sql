@@ -0,0 +1,201 @@
+------------------------------------------------------------
+-- Manage who a phone is assigned to directly: share a    --
+-- phone with another person, stop sharing it, or move a  --
+-- person's phones to someone else. Departments may also  --
+-- be merged, moving everyone in one into the other.      --
+------------------------------------------------------------
+
+-------------------------------------------------------
+-- SHAREPHONE                                        --
+--                                                   --
+-- Assign an existing phone to the person with the   --
+-- supplied login as well as to those who already    --
+-- have it. Returns the id of the phone.             --
+-------------------------------------------------------
+CREATE OR REPLACE FUNCTION sharePhone(
+    login text,
+    phone_id int,
+    is_primary boolean
+) RETURNS int AS $$
+DECLARE
+    target_person_id person.id%type;
+BEGIN
+    SELECT id INTO target_person_id FROM person WHERE person.login = sharePhone.login;
+    IF NOT found THEN
+        RAISE EXCEPTION 'unknown person %', sharePhone.login
+            USING ERRCODE = 'foreign_key_violation',
+                  CONSTRAINT = 'people_phones_person_id_fkey',
+                  DETAIL = format('Key (login)=(%s) is not present in table "person".', sharePhone.login);
+    END IF;
+    IF NOT EXISTS (SELECT 1 FROM phone WHERE id = sharePhone.phone_id) THEN
+        RAISE EXCEPTION 'unknown phone %', sharePhone.phone_id
+            USING ERRCODE = 'foreign_key_violation',
+                  CONSTRAINT = 'people_phones_phone_id_fkey',
+                  DETAIL = format('Key (phone_id)=(%s) is not present in table "phone".', sharePhone.phone_id);
+    END IF;
+    IF EXISTS (
+        SELECT 1 FROM people_phones
+        WHERE people_phones.person_id = target_person_id AND people_phones.phone_id = sharePhone.phone_id
+    ) THEN
+        RAISE EXCEPTION 'phone % is already assigned to %', sharePhone.phone_id, sharePhone.login
+            USING ERRCODE = 'unique_violation',
+                  CONSTRAINT = 'people_phones_pkey',
+                  DETAIL = format('Key (login, phone_id)=(%s, %s) already exists.', sharePhone.login, sharePhone.phone_id);
+    END IF;
+    INSERT INTO people_phones (person_id, phone_id, is_primary)
+    VALUES (target_person_id, sharePhone.phone_id, sharePhone.is_primary);
+    RETURN sharePhone.phone_id;
+END;
+$$
+Language 'plpgsql';
+
+-------------------------------------------------------
+-- UNSHAREPHONE                                      --
+--                                                   --
+-- Stop sharing a phone with the person with the     --
+-- supplied login. The phone must remain assigned to --
+-- someone else; a phone which belongs to one person --
+-- alone is deleted rather than unshared. Returns    --
+-- the id of the phone, or 0 if the person does not  --
+-- have it.                                          --
+-------------------------------------------------------
+CREATE OR REPLACE FUNCTION unsharePhone(
+    login text,
+    phone_id int
+) RETURNS int AS $$
+DECLARE
+    target_person_id person.id%type;
+BEGIN
+    SELECT id INTO target_person_id FROM person WHERE person.login = unsharePhone.login;
+    IF NOT found OR NOT EXISTS (
+        SELECT 1 FROM people_phones
+        WHERE people_phones.person_id = target_person_id AND people_phones.phone_id = unsharePhone.phone_id
+    ) THEN
+        RETURN 0;
+    END IF;
+    IF NOT EXISTS (
+        SELECT 1 FROM people_phones
+        WHERE people_phones.person_id <> target_person_id AND people_phones.phone_id = unsharePhone.phone_id
+    ) THEN
+        RAISE EXCEPTION 'phone % is only assigned to %', unsharePhone.phone_id, unsharePhone.login
+            USING ERRCODE = 'check_violation',
+                  CONSTRAINT = 'people_phones_shared_check',
+                  DETAIL = format('Key (phone_id, login)=(%s, %s) is not shared.', unsharePhone.phone_id, unsharePhone.login);
+    END IF;
+    DELETE FROM people_phones
+    WHERE people_phones.person_id = target_person_id AND people_phones.phone_id = unsharePhone.phone_id;
+    RETURN unsharePhone.phone_id;
+END;
+$$
+Language 'plpgsql';
+
+-------------------------------------------------------
+-- TRANSFERPHONES                                    --
+--                                                   --
+-- Move the phones of one person to another, or just --
+-- the phone with the supplied id. A phone remains   --
+-- primary unless the recipient already has a        --
+-- primary phone of its category. Phones which the   --
+-- recipient already shares are simply taken away    --
+-- from the first person. Returns the ids of the     --
+-- phones moved.                                     --
+-------------------------------------------------------
+CREATE OR REPLACE FUNCTION transferPhones(
+    from_login text,
+    to_login text,
+    phone_id int
+) RETURNS SETOF int AS $$
+DECLARE
+    from_person_id person.id%type;
+    to_person_id person.id%type;
+    moving RECORD;
+BEGIN
+    SELECT id INTO from_person_id FROM person WHERE person.login = from_login;
+    IF NOT found THEN
+        RAISE EXCEPTION 'unknown person %', from_login
+            USING ERRCODE = 'foreign_key_violation',
+                  CONSTRAINT = 'people_phones_person_id_fkey',
+                  DETAIL = format('Key (login)=(%s) is not present in table "person".', from_login);
+    END IF;
+    SELECT id INTO to_person_id FROM person WHERE person.login = to_login;
+    IF NOT found THEN
+        RAISE EXCEPTION 'unknown person %', to_login
+            USING ERRCODE = 'foreign_key_violation',
+                  CONSTRAINT = 'people_phones_person_id_fkey',
+                  DETAIL = format('Key (login)=(%s) is not present in table "person".', to_login);
+    END IF;
+    IF from_person_id = to_person_id THEN
+        RETURN;
+    END IF;
+    FOR moving IN
+        SELECT pp.phone_id, pp.is_primary, ph.category
+        FROM people_phones AS pp JOIN phone AS ph ON ph.id = pp.phone_id
+        WHERE pp.person_id = from_person_id
+        AND (transferPhones.phone_id IS NULL OR pp.phone_id = transferPhones.phone_id)
+        ORDER BY pp.phone_id
+    LOOP
+        DELETE FROM people_phones
+        WHERE people_phones.person_id = from_person_id AND people_phones.phone_id = moving.phone_id;
+        INSERT INTO people_phones (person_id, phone_id, is_primary)
+        SELECT
+            to_person_id,
+            moving.phone_id,
+            moving.is_primary AND NOT EXISTS (
+                SELECT 1
+                FROM people_phones AS pp JOIN phone AS ph ON ph.id = pp.phone_id
+                WHERE pp.person_id = to_person_id AND pp.is_primary AND ph.category = moving.category
+            )
+        ON CONFLICT DO NOTHING;
+        RETURN NEXT moving.phone_id;
+    END LOOP;
+    RETURN;
+END;
+$$
+Language 'plpgsql';
+
+-------------------------------------------------------
+-- MERGEDEPARTMENT                                   --
+--                                                   --
+-- Move everyone in the source department into the   --
+-- destination, and delete the source. History is    --
+-- repointed as well, so that people are shown in    --
+-- the department which took over theirs. Returns    --
+-- the number of people moved.                       --
+-------------------------------------------------------
+CREATE OR REPLACE FUNCTION mergeDepartment(
+    source text,
+    destination text
+) RETURNS int AS $$
+DECLARE
+    source_id department.id%type;
+    destination_id department.id%type;
+    moved int;
+BEGIN
+    SELECT id INTO source_id FROM department WHERE name = source;
+    IF NOT found THEN
+        RAISE EXCEPTION 'unknown department %', source
+            USING ERRCODE = 'foreign_key_violation',
+                  CONSTRAINT = 'fk_department',
+                  DETAIL = format('Key (name)=(%s) is not present in table "department".', source);
+    END IF;
+    SELECT id INTO destination_id FROM department WHERE name = destination;
+    IF NOT found THEN
+        RAISE EXCEPTION 'unknown department %', destination
+            USING ERRCODE = 'foreign_key_violation',
+                  CONSTRAINT = 'fk_department',
+                  DETAIL = format('Key (name)=(%s) is not present in table "department".', destination);
+    END IF;
+    IF source_id = destination_id THEN
+        RAISE EXCEPTION 'department % may not be merged into itself', source
+            USING ERRCODE = 'check_violation',
+                  CONSTRAINT = 'department_merge_check',
+                  DETAIL = format('Key (name)=(%s) may not be merged into itself.', source);
+    END IF;
+    UPDATE person SET department_id = destination_id WHERE department_id = source_id;
+    GET DIAGNOSTICS moved = ROW_COUNT;
+    UPDATE person_history SET department_id = destination_id WHERE department_id = source_id;
+    DELETE FROM department WHERE id = source_id;
+    RETURN moved;
+END;
+$$
+Language 'plpgsql';
@@ -0,0 +1,54 @@
+------------------------------------------------------------
+-- Rank the people found by the phone command, and find   --
+-- them despite typos. Trigram similarity is provided by  --
+-- the pg_trgm extension.                                 --
+------------------------------------------------------------
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+-------------------------------------------------------
+-- SEARCHSCORE                                       --
+--                                                   --
+-- How well a person matches the value searched for. --
+-- The whole part of the score is the kind of match: --
+--   4 the login, exactly                            --
+--   3 the start of the full name                    --
+--   2 part of the full name or login                --
+--   1 part of the title or department               --
+--   0 anything else, eg a contact or desk           --
+-- The fraction is the trigram similarity of the     --
+-- value to the full name or login, so that closer   --
+-- matches of the same kind come first.              --
+-------------------------------------------------------
+CREATE OR REPLACE FUNCTION searchScore(
+    value text,
+    login text,
+    fullname text,
+    title text,
+    department text
+) RETURNS real AS $$
+    SELECT (
+        CASE
+            WHEN lower(login) = lower(value) THEN 4
+            WHEN fullname ILIKE value || '%' THEN 3
+            WHEN fullname ILIKE '%' || value || '%' OR login ILIKE '%' || value || '%' THEN 2
+            WHEN title ILIKE '%' || value || '%' OR department ILIKE '%' || value || '%' THEN 1
+            ELSE 0
+        END
+        + 0.99 * greatest(similarity(value, login), word_similarity(value, fullname))
+    )::real;
+$$ LANGUAGE sql IMMUTABLE;
+
+-------------------------------------------------------
+-- FUZZYMATCH                                        --
+--                                                   --
+-- Whether the value is close enough to a word of    --
+-- the full name, or to the login, to be a misspelt  --
+-- search for the person, eg Nietszche for Nietzsche --
+-------------------------------------------------------
+CREATE OR REPLACE FUNCTION fuzzyMatch(
+    value text,
+    login text,
+    fullname text
+) RETURNS boolean AS $$
+    SELECT word_similarity(value, fullname) >= 0.4 OR similarity(value, login) >= 0.4;
+$$ LANGUAGE sql IMMUTABLE;
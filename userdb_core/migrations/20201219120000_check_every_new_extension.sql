@@ -0,0 +1,37 @@
+-------------------------------------------
+-- CHECKEXTENSION TRIGGER                --
+--                                       --
+-- Extensions at a location with a dial  --
+-- plan must fall within one of its      --
+-- ranges, whether or not the phone      --
+-- already exists. Restoring a snapshot  --
+-- skips existing phones itself, rather  --
+-- than inserting them and relying on    --
+-- ON CONFLICT. Existing extensions are  --
+-- only checked when they change.        --
+-------------------------------------------
+CREATE OR REPLACE FUNCTION checkExtension() RETURNS TRIGGER AS
+$$
+BEGIN
+    IF EXISTS (SELECT 1 FROM dialplan WHERE dialplan.location = NEW.location)
+    AND NOT EXISTS (
+        SELECT
+            1
+        FROM
+            extension_range AS r
+        WHERE
+            r.location = NEW.location
+        AND
+            NEW.number ~ '^[0-9]{1,9}$'
+        AND
+            NEW.number::int BETWEEN r.first_ext AND r.last_ext
+    ) THEN
+        RAISE EXCEPTION 'extension % is outside the dial plan for %', NEW.number, NEW.location
+            USING ERRCODE = 'check_violation',
+                  CONSTRAINT = 'phone_extension_check',
+                  DETAIL = format('Key (number, location)=(%s, %s) is outside the dial plan.', NEW.number, NEW.location);
+    END IF;
+    RETURN NEW;
+END;
+$$
+Language 'plpgsql';
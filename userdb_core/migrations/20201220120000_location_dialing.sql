@@ -0,0 +1,23 @@
+------------------------------------------------------------
+-- Each location records how numbers are dialed in its    --
+-- country: the international calling code (eg 44) and    --
+-- the trunk prefix dialed before national numbers (eg 0).--
+-- Locations without a calling code fall back on the      --
+-- countries userdb knows (US, CA and IN), so a studio in --
+-- any other country needs one to take national numbers.  --
+------------------------------------------------------------
+ALTER TABLE location ADD COLUMN IF NOT EXISTS calling_code VARCHAR(3);
+ALTER TABLE location ADD COLUMN IF NOT EXISTS trunk_prefix VARCHAR(2);
+
+ALTER TABLE location DROP CONSTRAINT IF EXISTS location_calling_code_check;
+ALTER TABLE location ADD CONSTRAINT location_calling_code_check
+    CHECK(calling_code ~ '^[1-9][0-9]{0,2}$');
+
+ALTER TABLE location DROP CONSTRAINT IF EXISTS location_trunk_prefix_check;
+ALTER TABLE location ADD CONSTRAINT location_trunk_prefix_check
+    CHECK(trunk_prefix ~ '^[0-9]{0,2}$');
+
+UPDATE location SET calling_code = '1', trunk_prefix = '1'
+    WHERE calling_code IS NULL AND country IN ('US', 'CA');
+UPDATE location SET calling_code = '91', trunk_prefix = '0'
+    WHERE calling_code IS NULL AND country = 'IN';
@@ -0,0 +1,116 @@
+------------------------------------------------------------
+-- The names of the title and department of a person are  --
+-- kept with each version in person_history, so that they --
+-- are shown as they were even once renamed, merged or    --
+-- gone. Versions written before this migration take the  --
+-- names the title and department have now.               --
+------------------------------------------------------------
+ALTER TABLE person_history ADD COLUMN IF NOT EXISTS title_name VARCHAR(256);
+ALTER TABLE person_history ADD COLUMN IF NOT EXISTS department_name VARCHAR(256);
+
+UPDATE person_history SET title_name = title.name
+FROM title
+WHERE person_history.title_id = title.id AND person_history.title_name IS NULL;
+
+UPDATE person_history SET department_name = department.name
+FROM department
+WHERE person_history.department_id = department.id AND person_history.department_name IS NULL;
+
+-- the names follow the columns of person, valid_from and valid_to
+CREATE OR REPLACE FUNCTION recordPersonHistory()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'UPDATE' AND OLD IS NOT DISTINCT FROM NEW THEN
+        RETURN NULL;
+    END IF;
+    IF TG_OP <> 'INSERT' THEN
+        UPDATE person_history SET valid_to = now()
+        WHERE id = OLD.id AND valid_to IS NULL;
+    END IF;
+    IF TG_OP <> 'DELETE' THEN
+        INSERT INTO person_history
+        SELECT NEW.*, now(), NULL,
+            (SELECT name FROM title WHERE id = NEW.title_id),
+            (SELECT name FROM department WHERE id = NEW.department_id);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+-----------------------------------
+-- PERSONVIEW_AS_OF() FUNCTION --
+-----------------------------------
+-- personview, as it stood at the end of the supplied day. Titles and
+-- departments are named as they were then, falling back on their
+-- current names, and the dial plan is as it is now. The columns are
+-- listed rather than taken from personview, so that changing the view
+-- does not silently change, or break, the function.
+DROP FUNCTION IF EXISTS personview_as_of(DATE);
+CREATE FUNCTION personview_as_of(day DATE)
+RETURNS TABLE (
+    person_id  INTEGER,
+    first      VARCHAR(256),
+    last       VARCHAR(256),
+    fullname   TEXT,
+    login      VARCHAR(256),
+    department VARCHAR(256),
+    title      VARCHAR(256),
+    phone_id   INTEGER,
+    number     VARCHAR(30),
+    category   phonecategory,
+    location   VARCHAR(256),
+    label      VARCHAR(256),
+    is_primary BOOLEAN,
+    did        TEXT,
+    manager    VARCHAR(256),
+    status     personstatus,
+    start_date DATE,
+    end_date   DATE
+) AS $$
+WITH p AS (
+   SELECT * FROM person_as_of(day)
+),
+cte_pf AS (
+   SELECT pp.person_id, pp.phone_id, pp.is_primary, ph.number, ph.category, ph.location, ph.label
+   FROM
+      people_phones_as_of(day) AS pp
+   JOIN phone_as_of(day) AS ph ON pp.phone_id = ph.id
+)
+SELECT
+    p.id AS person_id,
+    p.first,
+    p.last,
+    p.first || ' ' || p.last AS fullname,
+    p.login,
+    coalesce(p.department_name, dept.name) AS department,
+    coalesce(p.title_name, title.name) AS title,
+    cte_pf.phone_id,
+    cte_pf.number,
+    cte_pf.category,
+    cte_pf.location,
+    cte_pf.label,
+    cte_pf.is_primary,
+    extensionDid(cte_pf.number, cte_pf.category, cte_pf.location) AS did,
+    manager.login AS manager,
+    p.status,
+    p.start_date,
+    p.end_date
+FROM
+    p
+LEFT JOIN
+    title
+ON
+    p.title_id = title.id
+LEFT JOIN
+    department AS dept
+ON
+    p.department_id=dept.id
+LEFT JOIN
+    p AS manager
+ON
+    p.manager_id = manager.id
+LEFT JOIN
+    cte_pf
+ON
+    p.id = cte_pf.person_id;
+$$ LANGUAGE sql STABLE;
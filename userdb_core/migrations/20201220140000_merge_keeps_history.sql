@@ -0,0 +1,44 @@
+-------------------------------------------------------
+-- MERGEDEPARTMENT                                   --
+--                                                   --
+-- Move everyone in the source department into the   --
+-- destination, and delete the source. History keeps --
+-- the name of the source, as people were in it at   --
+-- the time. Returns the number of people moved.     --
+-------------------------------------------------------
+CREATE OR REPLACE FUNCTION mergeDepartment(
+    source text,
+    destination text
+) RETURNS int AS $$
+DECLARE
+    source_id department.id%type;
+    destination_id department.id%type;
+    moved int;
+BEGIN
+    SELECT id INTO source_id FROM department WHERE name = source;
+    IF NOT found THEN
+        RAISE EXCEPTION 'unknown department %', source
+            USING ERRCODE = 'foreign_key_violation',
+                  CONSTRAINT = 'fk_department',
+                  DETAIL = format('Key (name)=(%s) is not present in table "department".', source);
+    END IF;
+    SELECT id INTO destination_id FROM department WHERE name = destination;
+    IF NOT found THEN
+        RAISE EXCEPTION 'unknown department %', destination
+            USING ERRCODE = 'foreign_key_violation',
+                  CONSTRAINT = 'fk_department',
+                  DETAIL = format('Key (name)=(%s) is not present in table "department".', destination);
+    END IF;
+    IF source_id = destination_id THEN
+        RAISE EXCEPTION 'department % may not be merged into itself', source
+            USING ERRCODE = 'check_violation',
+                  CONSTRAINT = 'department_merge_check',
+                  DETAIL = format('Key (name)=(%s) may not be merged into itself.', source);
+    END IF;
+    UPDATE person SET department_id = destination_id WHERE department_id = source_id;
+    GET DIAGNOSTICS moved = ROW_COUNT;
+    DELETE FROM department WHERE id = source_id;
+    RETURN moved;
+END;
+$$
+Language 'plpgsql';
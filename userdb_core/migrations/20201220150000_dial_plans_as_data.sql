@@ -0,0 +1,16 @@
+------------------------------------------------------------
+-- Dial plans are data, defined per location with         --
+-- `phoneutil create dial-plan`, rather than rows seeded  --
+-- by a migration. The placeholder plans seeded by the    --
+-- dial plan migration are removed, along with their      --
+-- ranges, so that no location is held to a plan nobody  --
+-- defined. Plans which have since been changed are kept. --
+------------------------------------------------------------
+DELETE FROM dialplan
+WHERE (location, switchboard, did_prefix) IN (
+    ('Portland',   '+15035550100',  '+1503555'),
+    ('PlayaVista', '+13105550100',  '+1310555'),
+    ('Vancouver',  '+16045550100',  '+1604555'),
+    ('Montreal',   '+15145550100',  '+1514555'),
+    ('Hyderabad',  '+914066120000', '+91406612')
+);
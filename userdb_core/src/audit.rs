@@ -0,0 +1,166 @@
+use std::str::FromStr;
+use std::fmt;
+use serde::{Serialize, Deserialize};
+use crate::PhoneError;
+use crate::traits::ToStaticStr;
+
+/// The kind of change recorded in the audit log
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone, Copy)]
+pub enum AuditAction {
+    Create,
+    Update,
+    Delete
+}
+
+impl ToStaticStr for AuditAction {
+    fn to_static_str(&self) -> &'static str {
+        match self {
+            Self::Create => "Create",
+            Self::Update => "Update",
+            Self::Delete => "Delete"
+        }
+    }
+}
+
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_static_str())
+    }
+}
+
+/// The kinds of entity whose changes are audited. Each corresponds to a
+/// table in the database.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditEntity {
+    Person,
+    Phone,
+    /// The assignment of a phone to a person
+    PeoplePhones,
+    Contact,
+    Desk,
+    Title,
+    Department,
+    Location,
+    #[serde(rename = "dialplan")]
+    DialPlan,
+    ExtensionRange
+}
+
+impl FromStr for AuditEntity {
+    type Err = PhoneError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().replace('-', "_").as_str() {
+            "person" | "people" => Ok(Self::Person),
+            "phone" => Ok(Self::Phone),
+            "people_phones" | "assignment" => Ok(Self::PeoplePhones),
+            "contact" => Ok(Self::Contact),
+            "desk" => Ok(Self::Desk),
+            "title" => Ok(Self::Title),
+            "department" | "dept" => Ok(Self::Department),
+            "location" => Ok(Self::Location),
+            "dialplan" | "dial_plan" => Ok(Self::DialPlan),
+            "extension_range" | "extensions" => Ok(Self::ExtensionRange),
+            _ => Err(Self::Err::InvalidAuditEntity(s.to_string()))
+        }
+    }
+}
+
+impl ToStaticStr for AuditEntity {
+    fn to_static_str(&self) -> &'static str {
+        match self {
+            Self::Person => "person",
+            Self::Phone => "phone",
+            Self::PeoplePhones => "people_phones",
+            Self::Contact => "contact",
+            Self::Desk => "desk",
+            Self::Title => "title",
+            Self::Department => "department",
+            Self::Location => "location",
+            Self::DialPlan => "dialplan",
+            Self::ExtensionRange => "extension_range"
+        }
+    }
+}
+
+impl fmt::Display for AuditEntity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_static_str())
+    }
+}
+
+/// A single change recorded in the audit log: who made it, when, and
+/// the row before and after the change
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone)]
+pub struct AuditEntry {
+    pub audit_id: i64,
+    /// When the change was made, as YYYY-MM-DD HH:MM:SS+TZ
+    pub at: String,
+    pub actor: String,
+    pub action: AuditAction,
+    pub entity: AuditEntity,
+    /// The id of the row, for those entities which have one
+    #[serde(default)]
+    pub entity_id: Option<i32>,
+    /// The login of the person the row belongs to, if any
+    #[serde(default)]
+    pub login: Option<String>,
+    /// The row before the change. Absent for creates.
+    #[serde(default)]
+    pub before: Option<serde_json::Value>,
+    /// The row after the change. Absent for deletes.
+    #[serde(default)]
+    pub after: Option<serde_json::Value>,
+}
+
+impl AuditEntry {
+    /// The names of the fields which differ between before and after,
+    /// or every field of the row for creates and deletes
+    pub fn changed(&self) -> Vec<String> {
+        let empty = serde_json::Map::new();
+        let before = self.before.as_ref().and_then(|row| row.as_object()).unwrap_or(&empty);
+        let after = self.after.as_ref().and_then(|row| row.as_object()).unwrap_or(&empty);
+        let mut keys = before.keys().chain(after.keys())
+            .filter(|key| before.get(*key) != after.get(*key))
+            .cloned()
+            .collect::<Vec<_>>();
+        keys.sort_unstable();
+        keys.dedup();
+        keys
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PhoneErrorKind;
+    use serde_json::json;
+
+    #[test]
+    fn from_str_given_aliases_constructs_entity() {
+        assert_eq!(AuditEntity::from_str("People").unwrap(), AuditEntity::Person);
+        assert_eq!(AuditEntity::from_str("dial-plan").unwrap(), AuditEntity::DialPlan);
+        assert_eq!(AuditEntity::from_str("assignment").unwrap(), AuditEntity::PeoplePhones);
+        let result = AuditEntity::from_str("audit").unwrap_err().kind();
+        assert_eq!(result, PhoneErrorKind::InvalidAuditEntity);
+    }
+
+    #[test]
+    fn entry_deserializes_from_database_json() {
+        let entry: AuditEntry = serde_json::from_value(json!({
+            "audit_id": 3,
+            "at": "2020-12-15 10:00:00+00",
+            "actor": "jdoe",
+            "action": "Update",
+            "entity": "extension_range",
+            "entity_id": 7,
+            "login": null,
+            "before": {"id": 7, "first_ext": 1000},
+            "after": {"id": 7, "first_ext": 2000}
+        })).unwrap();
+        assert_eq!(entry.entity, AuditEntity::ExtensionRange);
+        assert_eq!(entry.action, AuditAction::Update);
+        assert_eq!(entry.changed(), vec!["first_ext"]);
+    }
+}
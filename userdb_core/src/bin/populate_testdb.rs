@@ -1,7 +1,9 @@
-//use sqlx::postgres::PgPoolOptions;
+use std::str::FromStr;
 use sqlx::ConnectOptions;
 use sqlx::postgres::PgConnectOptions;
 
+use userdb_core::Config;
+
 static CREATE: &str = r"
 INSERT INTO 
     department(name) 
@@ -114,7 +116,7 @@ pub async fn setup(
 
     for create in CREATE.split(";") {
 
-        let   rows = sqlx::query(&create);
+        let   rows = sqlx::query(create);
         let _ = rows.execute(&mut pool).await?;
     }
                    
@@ -125,17 +127,11 @@ pub async fn setup(
 #[async_std::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
-    // let  pool = PgPoolOptions::new()
-    //     .max_connections(1)
-    //     .connect(DB_URL).await?;
-    let conn = PgConnectOptions::new()
-        .host("localhost")
-        .port(5432)
-        .database("test")
-        .username("postgres")
-        .password("example")
+    // the database is selected via USERDB_PROFILE / USERDB_DATABASE_URL
+    let profile = Config::load_profile(None, None)?;
+    let conn = PgConnectOptions::from_str(profile.url())?
         .connect().await?;
     
-    let _ = setup(conn).await?; 
+    setup(conn).await?;
     Ok(())
 }
\ No newline at end of file
@@ -1,44 +1,74 @@
-//use sqlx::postgres::PgPoolOptions;
+use std::env;
+use std::str::FromStr;
 use sqlx::ConnectOptions;
 use sqlx::postgres::PgConnectOptions;
+use url::Url;
+
+use userdb_core::DB_URL;
 
 static DROP: &str = r"
 DROP DATABASE
-    test;
+    {};
 ";
 static NEW: &str = r"
-CREATE DATABASE test;
+CREATE DATABASE {};
 ";
 pub async fn setup(
-    mut pool: sqlx::PgConnection, 
+    mut pool: sqlx::PgConnection,
+    database: &str,
 ) -> Result<(), sqlx::Error> {
-    let   drop = sqlx::query(&DROP);
-    
+    let database = quote_ident(database);
+    let drop = DROP.replace("{}", &database);
+    let   drop = sqlx::query(&drop);
+
     // uncomment to print out query for debugging purposes
     // use sqlx::Execute;
     //println!("sql {}", rows.sql());
     let _ = drop.execute(&mut pool).await?;
-    
-    let newdb = sqlx::query(&NEW);
+
+    let newdb = NEW.replace("{}", &database);
+    let newdb = sqlx::query(&newdb);
     let _ = newdb.execute(&mut pool).await?;
-    
+
     Ok(())
 }
 
+// quote a postgres identifier, doubling any embedded quotes
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+// only databases named test, test_* or *_test may be torn down
+fn is_test_database(name: &str) -> bool {
+    name == "test" || name.starts_with("test_") || name.ends_with("_test")
+}
+
+// the url supplied via --database-url, or the testdb url. The profiles and
+// USERDB_DATABASE_URL are deliberately ignored, so that a default profile
+// pointing elsewhere is never dropped by accident.
+fn database_url() -> Result<String, String> {
+    let mut args = env::args().skip(1);
+    match (args.next().as_deref(), args.next(), args.next()) {
+        (None, ..) => Ok(DB_URL.to_string()),
+        (Some("--database-url"), Some(url), None) => Ok(url),
+        _ => Err("usage: teardown-testdb [--database-url <url>]".to_string()),
+    }
+}
+
 #[async_std::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
-    // let  pool = PgPoolOptions::new()
-    //     .max_connections(1)
-    //     .connect(DB_URL).await?;
-    let conn = PgConnectOptions::new()
-        .host("localhost")
-        .port(5432)
-        .database("postgres")
-        .username("postgres")
-        .password("example")
+    let mut url = Url::parse(&database_url()?)?;
+    let database = url.path().trim_start_matches('/').to_string();
+    if !is_test_database(&database) {
+        return Err(format!("refusing to drop '{}', which is not a test database", database).into());
+    }
+    // We cannot drop the database we are connected to, so connect to the
+    // postgres maintenance database on the same server instead.
+    url.set_path("/postgres");
+    let conn = PgConnectOptions::from_str(url.as_str())?
         .connect().await?;
-    
-    let _ = setup(conn).await?; 
+
+    setup(conn, &database).await?;
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,30 @@
+// external crates
+use serde::{Serialize, Deserialize};
+use sqlx::FromRow;
+
+// internal crate
+use crate::ContactCategory;
+
+/// Struct which represents a contact channel other than a phone (eg an
+/// email address or a chat handle) in the backing database. It is also
+/// capable of serializing and deserializing via serde
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct Contact {
+    pub contact_id: i32,
+    pub category: ContactCategory,
+    /// The address or handle
+    pub value: String,
+    /// An optional description, to tell apart contacts of the same category
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Whether this is the person's primary contact of its category
+    #[serde(rename = "is_primary", default)]
+    #[sqlx(rename = "is_primary")]
+    pub primary: bool,
+    /// The login of the person the contact belongs to. This is only
+    /// known when the contact is read on its own, rather than along
+    /// with its person.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[sqlx(default)]
+    pub login: Option<String>,
+}
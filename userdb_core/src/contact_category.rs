@@ -0,0 +1,87 @@
+use std::str::FromStr;
+use crate::PhoneError;
+use serde::{Serialize,Deserialize};
+use crate::traits::ToStaticStr;
+use std::fmt;
+
+/// The category of a contact channel other than a phone - a work
+/// `Email` address, a `Chat` handle (eg on Slack or Teams), or a
+/// `PersonalEmail` address.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone, sqlx::Type)]
+#[sqlx(rename="contactcategory")]
+pub enum ContactCategory {
+    Email,
+    Chat,
+    PersonalEmail
+}
+
+impl ContactCategory {
+    /// Is the contact an email address
+    pub fn is_email(&self) -> bool {
+        matches!(self, Self::Email | Self::PersonalEmail)
+    }
+}
+
+impl FromStr for ContactCategory {
+    type Err = PhoneError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "email" | "e" | "work" => Ok(Self::Email),
+            "chat" | "c" | "slack" | "teams" => Ok(Self::Chat),
+            "personalemail" | "personal" | "p" => Ok(Self::PersonalEmail),
+            _ => Err(Self::Err::InvalidContactCategory(s.to_string()))
+        }
+    }
+}
+
+impl ToStaticStr for ContactCategory {
+    fn to_static_str(&self) -> &'static str {
+        match self {
+            Self::Email => "Email",
+            Self::Chat => "Chat",
+            Self::PersonalEmail => "PersonalEmail"
+        }
+    }
+}
+
+impl fmt::Display for ContactCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_static_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PhoneErrorKind;
+
+    #[test]
+    fn from_str_given_valid_strs_constructs_category() {
+        let tests = &[
+            (&vec!["email", "e", "Work"], ContactCategory::Email),
+            (&vec!["chat", "c", "slack", "Teams"], ContactCategory::Chat),
+            (&vec!["personalemail", "PersonalEmail", "personal", "p"], ContactCategory::PersonalEmail),
+        ];
+
+        for test in tests {
+            for item in test.0 {
+                let result = ContactCategory::from_str(item).unwrap();
+                assert_eq!(result, test.1);
+            }
+        }
+    }
+
+    #[test]
+    fn from_str_given_bad_data_constructs_err() {
+        let result = ContactCategory::from_str("fax").unwrap_err().kind();
+        assert_eq!(result, PhoneErrorKind::InvalidContactCategory);
+    }
+
+    #[test]
+    fn is_email_given_each_category() {
+        assert!(ContactCategory::Email.is_email());
+        assert!(ContactCategory::PersonalEmail.is_email());
+        assert!(!ContactCategory::Chat.is_email());
+    }
+}
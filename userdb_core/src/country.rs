@@ -0,0 +1,240 @@
+use std::str::FromStr;
+use crate::PhoneError;
+use std::fmt;
+use serde::{Serialize, Deserialize};
+
+/// The country of a location. Each country knows how to turn a national
+/// number into E.164, and how its numbers are written. The dialing rules
+/// come from the location, falling back on those of the countries we know
+/// (US, CA and IN) for locations which do not record them.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone, Hash)]
+pub struct Country {
+    code: String,
+    calling_code: String,
+    trunk_prefix: String,
+}
+
+// the length of a national significant number in NANP and in India
+pub(crate) const NATIONAL_LENGTH: usize = 10;
+// the longest number E.164 allows, excluding the +
+const MAX_E164_DIGITS: usize = 15;
+// the shortest number we accept with a country code, excluding the +
+const MIN_E164_DIGITS: usize = 8;
+// indian area codes which are two digits long. The rest are three or four
+const INDIA_METRO_CODES: &[&str] = &["11", "20", "22", "33", "40", "44", "79", "80"];
+// the area codes of Canada, which shares +1 with the US
+const CANADA_AREA_CODES: &[&str] = &[
+    "204", "226", "236", "249", "250", "263", "289", "306", "343", "354",
+    "365", "367", "368", "382", "403", "416", "418", "428", "431", "437",
+    "438", "450", "468", "474", "506", "514", "519", "548", "579", "581",
+    "584", "587", "600", "604", "613", "639", "647", "672", "683", "705",
+    "709", "742", "753", "778", "780", "782", "807", "819", "825", "867",
+    "873", "879", "902", "905",
+];
+// the countries whose dialing rules we know, as their ISO code, other
+// names, calling code and trunk prefix
+const KNOWN: &[(&str, &[&str], &str, &str)] = &[
+    ("US", &["usa", "unitedstates", "united states"], "1", "1"),
+    ("CA", &["canada"], "1", "1"),
+    ("IN", &["india"], "91", "0"),
+];
+
+impl Country {
+    /// Construct a country from its two letter ISO code, its calling code
+    /// and the trunk prefix dialed before national numbers, which may be
+    /// empty.
+    pub fn new(code: &str, calling_code: &str, trunk_prefix: &str) -> Self {
+        Self {
+            code: code.to_uppercase(),
+            calling_code: calling_code.to_string(),
+            trunk_prefix: trunk_prefix.to_string(),
+        }
+    }
+
+    /// The two letter ISO code of the country
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    /// The international calling code, without the leading +
+    pub fn calling_code(&self) -> &str {
+        &self.calling_code
+    }
+
+    /// Look up the country of an E.164 number from its calling code, among
+    /// the countries we know. The US and Canada share +1, so +1 numbers
+    /// are told apart by their area code.
+    pub fn from_e164(number: &str) -> Option<Self> {
+        let digits = number.strip_prefix('+')?;
+        let code = if let Some(national) = digits.strip_prefix('1') {
+            match national.get(0..3) {
+                Some(area) if CANADA_AREA_CODES.contains(&area) => "CA",
+                _ => "US",
+            }
+        } else if digits.starts_with("91") {
+            "IN"
+        } else {
+            return None;
+        };
+        Self::from_str(code).ok()
+    }
+
+    /// Convert a national number, written with or without its trunk
+    /// prefix, to E.164. Returns None if the number has the wrong
+    /// number of digits for the country.
+    pub fn to_e164(&self, national: &str) -> Option<String> {
+        let trunk = self.trunk_prefix.as_str();
+        let national = match national.strip_prefix(trunk) {
+            Some(rest) if !trunk.is_empty() && self.is_national_length(rest) => rest,
+            _ if self.is_national_length(national) => national,
+            _ => return None,
+        };
+        Some(format!("+{}{}", self.calling_code, national))
+    }
+
+    /// Whether `number`, in E.164, is a valid number for the country
+    pub fn is_valid_e164(&self, number: &str) -> bool {
+        self.national(number).map(|national| self.is_national_length(national)).unwrap_or(false)
+    }
+
+    /// Write an E.164 number the way it is written in the country.
+    /// North American numbers are written as 310-386-4321, and indian
+    /// numbers as +91 40 1234 5678 or, for mobiles, +91 98765 43210.
+    /// Numbers in other countries, and numbers which are not valid for
+    /// the country, are returned as is.
+    pub fn format(&self, number: &str) -> String {
+        if !self.is_valid_e164(number) {
+            return number.to_string();
+        }
+        let national = self.national(number).unwrap();
+        match self.code.as_str() {
+            "US" | "CA" => {
+                format!("{}-{}-{}", &national[0..3], &national[3..6], &national[6..])
+            }
+            "IN" if national.starts_with(|c| ('6'..='9').contains(&c)) => {
+                format!("+91 {} {}", &national[0..5], &national[5..])
+            }
+            "IN" if INDIA_METRO_CODES.contains(&&national[0..2]) => {
+                format!("+91 {} {} {}", &national[0..2], &national[2..6], &national[6..])
+            }
+            "IN" => format!("+91 {} {} {}", &national[0..3], &national[3..6], &national[6..]),
+            _ => number.to_string(),
+        }
+    }
+
+    // the national significant number of an E.164 number in this country
+    fn national<'a>(&self, number: &'a str) -> Option<&'a str> {
+        number.strip_prefix('+')?.strip_prefix(self.calling_code.as_str())
+    }
+
+    // whether a national significant number has as many digits as the
+    // country's numbers do. NANP and indian numbers have ten, while
+    // elsewhere any number E.164 allows is accepted
+    fn is_national_length(&self, national: &str) -> bool {
+        match self.code.as_str() {
+            "US" | "CA" | "IN" => national.len() == NATIONAL_LENGTH,
+            _ => is_e164_length(&format!("{}{}", self.calling_code, national)),
+        }
+    }
+}
+
+/// Whether `digits`, the part of a number following the +, is
+/// of a length E.164 allows
+pub(crate) fn is_e164_length(digits: &str) -> bool {
+    (MIN_E164_DIGITS..=MAX_E164_DIGITS).contains(&digits.len())
+}
+
+impl FromStr for Country {
+    type Err = PhoneError;
+    /// Convert from a str to one of the countries we know. The input is
+    /// case insensitive, and accepts the name or two letter ISO code of
+    /// the country.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.to_lowercase();
+        KNOWN.iter()
+            .find(|(code, names, ..)| code.to_lowercase() == lower || names.contains(&lower.as_str()))
+            .map(|(code, _, calling_code, trunk_prefix)| Self::new(code, calling_code, trunk_prefix))
+            .ok_or_else(|| Self::Err::InvalidCountry(s.to_string()))
+    }
+}
+
+impl fmt::Display for Country {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PhoneErrorKind;
+
+    fn known(code: &str) -> Country {
+        Country::from_str(code).unwrap()
+    }
+
+    #[test]
+    fn to_e164_given_national_numbers_succeeds() {
+        let tests = &[
+            (known("US"), "3103864321", "+13103864321"),
+            (known("CA"), "16045551212", "+16045551212"),
+            (known("IN"), "4012345678", "+914012345678"),
+            (known("IN"), "04012345678", "+914012345678"),
+        ];
+        for (country, national, expect) in tests {
+            assert_eq!(country.to_e164(national).as_deref(), Some(*expect));
+        }
+    }
+
+    #[test]
+    fn to_e164_given_wrong_length_fails() {
+        assert_eq!(known("US").to_e164("23103864321"), None);
+        assert_eq!(known("IN").to_e164("401234567"), None);
+    }
+
+    #[test]
+    fn format_writes_numbers_per_country() {
+        let tests = &[
+            (known("US"), "+13103864321", "310-386-4321"),
+            (known("IN"), "+914012345678", "+91 40 1234 5678"),
+            (known("IN"), "+919876543210", "+91 98765 43210"),
+            (known("IN"), "+915412345678", "+91 541 234 5678"),
+            (known("IN"), "+91401234", "+91401234"),
+        ];
+        for (country, number, expect) in tests {
+            assert_eq!(&country.format(number), expect);
+        }
+    }
+
+    #[test]
+    fn to_e164_given_country_from_location_uses_its_dialing() {
+        let uk = Country::new("gb", "44", "0");
+        assert_eq!(uk.to_e164("02079460000").as_deref(), Some("+442079460000"));
+        assert_eq!(uk.to_e164("2079460000").as_deref(), Some("+442079460000"));
+        assert_eq!(uk.format("+442079460000"), "+442079460000");
+        assert!(uk.is_valid_e164("+442079460000"));
+        let singapore = Country::new("SG", "65", "");
+        assert_eq!(singapore.to_e164("61234567").as_deref(), Some("+6561234567"));
+        assert_eq!(singapore.to_e164("123").as_deref(), None);
+    }
+
+    #[test]
+    fn from_e164_tells_canada_from_the_us_by_area_code() {
+        let tests = &[
+            ("+16045551212", Some("CA")),
+            ("+15145551212", Some("CA")),
+            ("+15035551212", Some("US")),
+            ("+914012345678", Some("IN")),
+            ("+442079460000", None),
+        ];
+        for (number, expect) in tests {
+            assert_eq!(Country::from_e164(number).as_ref().map(Country::code), *expect);
+        }
+    }
+
+    #[test]
+    fn from_str_given_bad_input_fails() {
+        let result = Country::from_str("mars").unwrap_err().kind();
+        assert_eq!(result, PhoneErrorKind::InvalidCountry);
+    }
+}
@@ -1,4 +1,8 @@
 pub mod person;
 pub mod phone;
 pub mod title;
-pub mod department;
\ No newline at end of file
+pub mod department;
+pub mod location;
+pub mod contact;
+pub mod person_phone;
+pub mod dial_plan;
@@ -0,0 +1,52 @@
+//! Create a contact channel (eg an email address or chat handle) for a person
+use sqlx::prelude::*;
+use sqlx::{Executor, Postgres};
+use crate::prelude::*;
+use crate::ContactCategory;
+use crate::UserDbError;
+
+const CREATE_CONTACT: &str = r"
+INSERT INTO contact
+    (person_id, category, value, label, is_primary)
+SELECT
+    person.id, $2::contactcategory, $3, $4, $5
+FROM
+    person
+WHERE
+    person.login = $1
+RETURNING id;
+";
+
+#[derive(FromRow)]
+struct Rval {
+    id: i32
+}
+
+/// Create a contact for the person with `login`, optionally labeling it
+/// and marking it as their primary contact of its category. Returns the
+/// id of the new contact.
+pub async fn create<'c, E>(
+    executor: E,
+    login: &str,
+    category: &ContactCategory,
+    value: &str,
+    label: Option<&str>,
+    primary: bool,
+) -> Result<i32, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let value = value.trim();
+    let rval: Option<Rval> = sqlx::query_as(CREATE_CONTACT)
+    .bind(login)
+    .bind(category.to_static_str())
+    .bind(value)
+    .bind(label)
+    .bind(primary)
+    .fetch_optional(executor).await
+    .map_err(|e| match UserDbError::from(e) {
+        UserDbError::InvalidEmail(_) => UserDbError::InvalidEmail(value.to_string()),
+        e => e,
+    })?;
+    rval.map(|Rval{id}| id).ok_or_else(|| UserDbError::NotFound(format!("Person with login '{}'", login)))
+}
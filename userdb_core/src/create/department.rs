@@ -1,5 +1,7 @@
 use std::convert::AsRef;
 use sqlx::prelude::*;
+use sqlx::{Executor, Postgres};
+use crate::UserDbError;
 
 const CREATE_DEPT: &str = r"
 INSERT INTO department 
@@ -12,15 +14,17 @@ RETURNING id;
 
 #[derive(FromRow)]
 struct Rval {
-    id: Option<i32>
+    id: i32
 }
-pub async fn create<I>(pool: &sqlx::PgPool, name: I) 
--> Result<Option<i32>, sqlx::Error>
+/// Create a department, returning its id, or None if it already exists.
+pub async fn create<'c, E, I>(executor: E, name: I) 
+-> Result<Option<i32>, UserDbError>
 where
+    E: Executor<'c, Database = Postgres>,
     I: AsRef<str>
 {
-    let Rval{id} = sqlx::query_as(&CREATE_DEPT)
+    let rval: Option<Rval> = sqlx::query_as(CREATE_DEPT)
     .bind(name.as_ref())
-    .fetch_one(pool).await?;
-    Ok(id)
+    .fetch_optional(executor).await?;
+    Ok(rval.map(|Rval{id}| id))
 }
\ No newline at end of file
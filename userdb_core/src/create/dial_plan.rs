@@ -0,0 +1,84 @@
+//! Create the dial plan of a location, and the ranges its extensions
+//! are drawn from
+use sqlx::prelude::*;
+use sqlx::{Executor, Postgres};
+use crate::ExtensionRange;
+use crate::Location;
+use crate::NumberString;
+use crate::UserDbError;
+
+const CREATE_DIAL_PLAN: &str = r"
+INSERT INTO dialplan
+    (location, switchboard, did_prefix)
+VALUES
+    ($1, $2, $3)
+ON CONFLICT DO NOTHING
+RETURNING location;
+";
+
+const CREATE_EXTENSION_RANGE: &str = r"
+INSERT INTO extension_range
+    (location, first_ext, last_ext)
+VALUES
+    ($1, $2, $3)
+RETURNING id;
+";
+
+#[derive(FromRow)]
+struct Plan {
+    location: String
+}
+
+#[derive(FromRow)]
+struct Rval {
+    id: i32
+}
+
+/// Create the dial plan of `location`, returning the name of the location,
+/// or None if it already has one. The `switchboard` is stored in E.164,
+/// reading a national number as belonging to the country of `location`.
+/// The `did_prefix` is only the start of a number, so must be written with
+/// its country code (eg +1604555). The plan has no extensions until ranges
+/// are added with `add_range`.
+pub async fn create<'c, E>(
+    executor: E,
+    location: &Location,
+    switchboard: &NumberString,
+    did_prefix: &NumberString,
+) -> Result<Option<String>, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let switchboard = switchboard.to_e164(location.country().as_ref())?.to_string();
+    let did_prefix = did_prefix.to_string();
+    let rval: Option<Plan> = sqlx::query_as(CREATE_DIAL_PLAN)
+    .bind(location.name.as_str())
+    .bind(&switchboard)
+    .bind(&did_prefix)
+    .fetch_optional(executor).await
+    .map_err(|e| match UserDbError::from(e) {
+        // only the did prefix may be invalid, as the switchboard is already E.164
+        UserDbError::InvalidNumber(_) => UserDbError::InvalidNumber(did_prefix.clone()),
+        e => e,
+    })?;
+    Ok(rval.map(|Plan{location}| location))
+}
+
+/// Add `range` to the extensions of the dial plan of `location`, returning
+/// the id of the range. Extensions already at the location are only checked
+/// against the plan when they change.
+pub async fn add_range<'c, E>(
+    executor: E,
+    location: &Location,
+    range: &ExtensionRange,
+) -> Result<i32, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let Rval{id} = sqlx::query_as(CREATE_EXTENSION_RANGE)
+    .bind(location.name.as_str())
+    .bind(range.first as i32)
+    .bind(range.last as i32)
+    .fetch_one(executor).await?;
+    Ok(id)
+}
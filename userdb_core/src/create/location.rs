@@ -0,0 +1,58 @@
+use sqlx::prelude::*;
+use sqlx::{Executor, Postgres};
+use crate::UserDbError;
+
+const CREATE_LOCATION: &str = r"
+INSERT INTO location 
+    (name, code, aliases, address, timezone, country, calling_code, trunk_prefix)
+VALUES
+    ($1, $2, $3, $4, $5, UPPER($6), $7, $8)
+ON CONFLICT DO NOTHING
+RETURNING id;
+";
+
+#[derive(FromRow)]
+struct Rval {
+    id: i32
+}
+
+/// Create a location, returning its id, or None if a location with
+/// the same name or code already exists. The `timezone` is named as in
+/// the tz database (eg America/Vancouver), and the `country` by its two
+/// letter ISO code. The `calling_code` (eg 44) and `trunk_prefix` (eg 0)
+/// say how numbers are dialed in the country, and may be left out for
+/// the US, Canada and India, whose dialing we know.
+#[allow(clippy::too_many_arguments)]
+pub async fn create<'c, E>(
+    executor: E,
+    name: &str,
+    code: &str,
+    aliases: &[String],
+    address: Option<&str>,
+    timezone: &str,
+    country: &str,
+    calling_code: Option<&str>,
+    trunk_prefix: Option<&str>,
+) -> Result<Option<i32>, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let rval: Option<Rval> = sqlx::query_as(CREATE_LOCATION)
+    .bind(name)
+    .bind(code)
+    .bind(aliases)
+    .bind(address)
+    .bind(timezone)
+    .bind(country)
+    .bind(calling_code)
+    .bind(trunk_prefix)
+    .fetch_optional(executor).await
+    .map_err(|e| match UserDbError::from(e) {
+        UserDbError::InvalidTimezone(_) => UserDbError::InvalidTimezone(timezone.to_string()),
+        UserDbError::InvalidCountryCode(_) => UserDbError::InvalidCountryCode(country.to_string()),
+        UserDbError::InvalidCallingCode(_) => UserDbError::InvalidCallingCode(calling_code.unwrap_or_default().to_string()),
+        UserDbError::InvalidTrunkPrefix(_) => UserDbError::InvalidTrunkPrefix(trunk_prefix.unwrap_or_default().to_string()),
+        e => e,
+    })?;
+    Ok(rval.map(|Rval{id}| id))
+}
@@ -4,6 +4,8 @@
 //use crate::PersonView;
 use std::convert::AsRef;
 use sqlx::prelude::*;
+use sqlx::{Executor, Postgres};
+use crate::UserDbError;
 
 const CREATE_PERSON: &str = r"
 WITH cte_department AS (
@@ -35,22 +37,29 @@ RETURNING id;
 struct Rval {
     id: i32
 }
-pub async fn create<I, J, K, L, M>(pool: &sqlx::PgPool, first: I, last: J, login: K, department: L, title: M) 
--> Result<i32, sqlx::Error>
+pub async fn create<'c, E, I, J, K, L, M>(executor: E, first: I, last: J, login: K, department: L, title: M) 
+-> Result<i32, UserDbError>
 where
+    E: Executor<'c, Database = Postgres>,
     I: AsRef<str>,
     J: AsRef<str>,
     K: AsRef<str>,
     L: AsRef<str>,
     M: AsRef<str>,
 {
-    let Rval{id} = sqlx::query_as(&CREATE_PERSON)
+    let Rval{id} = sqlx::query_as(CREATE_PERSON)
     .bind(department.as_ref())
     .bind(title.as_ref())
     .bind(first.as_ref())
     .bind(last.as_ref())
     .bind(login.as_ref())
-    .fetch_one(pool).await?;
+    .fetch_one(executor).await
+    // postgres can only tell us which id was missing
+    .map_err(|e| match UserDbError::from(e) {
+        UserDbError::UnknownDepartment(_) => UserDbError::UnknownDepartment(department.as_ref().into()),
+        UserDbError::UnknownTitle(_) => UserDbError::UnknownTitle(title.as_ref().into()),
+        e => e,
+    })?;
     //let Rval{id} = Rval::from_row(&row).unwrap();
     Ok(id)
 }
\ No newline at end of file
@@ -0,0 +1,39 @@
+//! Share an existing phone with another person. Phones such as
+//! extensions may be assigned to any number of people.
+use std::convert::AsRef;
+use sqlx::prelude::*;
+use sqlx::{Executor, Postgres};
+use crate::UserDbError;
+
+const SHARE_PHONE: &str = r"
+SELECT
+    *
+FROM
+    sharePhone($1, $2, $3) AS phone_id;
+";
+
+#[derive(FromRow)]
+struct Rval {
+    phone_id: i32
+}
+
+/// Assign the phone with `phone_id` to the person with `login`, as well as
+/// to those who already have it, optionally marking it as their primary
+/// phone of its category. Returns the id of the phone.
+pub async fn share<'c, E, I>(
+    executor: E,
+    login: I,
+    phone_id: u32,
+    primary: bool,
+) -> Result<i32, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+    I: AsRef<str>,
+{
+    let Rval{phone_id} = sqlx::query_as(SHARE_PHONE)
+    .bind(login.as_ref())
+    .bind(phone_id as i32)
+    .bind(primary)
+    .fetch_one(executor).await?;
+    Ok(phone_id)
+}
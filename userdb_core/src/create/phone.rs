@@ -4,13 +4,15 @@
 //use crate::PersonView;
 use std::convert::AsRef;
 use sqlx::prelude::*;
+use sqlx::{Executor, Postgres};
 use crate::prelude::*;
+use crate::UserDbError;
 
 const CREATE_PHONE: &str = r"
 SELECT 
     *
 FROM
-    addPhone($1, $2, $3::phonecategory, $4::location);
+    addPhone($1, $2, $3::phonecategory, $4, $5, $6);
 ";
 
 #[derive(FromRow)]
@@ -18,23 +20,37 @@ struct Rval {
     addphone: Option<i32>
 }
 
-pub async fn create<I>(
-    pool: &sqlx::PgPool, 
+/// Create a phone and assign it to the person with `login`, optionally
+/// labeling it and marking it as their primary phone of its category.
+/// The number is stored in E.164, reading national numbers as belonging
+/// to the country of `location`.
+/// Returns None if the phone already exists.
+pub async fn create<'c, E, I>(
+    executor: E, 
     login: I, 
     number: &crate::NumberString, 
     category: &crate::PhoneCategory, 
-    location: &crate::Location
-) -> Result<Option<i32>, sqlx::Error>
+    location: &crate::Location,
+    label: Option<&str>,
+    primary: bool,
+) -> Result<Option<i32>, UserDbError>
 where
+    E: Executor<'c, Database = Postgres>,
     I: AsRef<str>,
 {
-    let number = number.to_string();
-    let Rval{addphone} = sqlx::query_as(&CREATE_PHONE)
+    let number = number.normalize(category, location.country().as_ref())?.to_string();
+    let Rval{addphone} = sqlx::query_as(CREATE_PHONE)
     .bind(login.as_ref())
-    .bind(number)
+    .bind(&number)
     .bind(category.to_static_str())
-    .bind(location.to_static_str())
-    .fetch_one(pool).await?;
+    .bind(location.name.as_str())
+    .bind(label)
+    .bind(primary)
+    .fetch_one(executor).await
+    .map_err(|e| match UserDbError::from(e) {
+        UserDbError::InvalidNumber(_) => UserDbError::InvalidNumber(number.clone()),
+        e => e,
+    })?;
     //let Rval{addphone} = Rval::from_row(&row).unwrap();
     Ok(addphone)
 }
\ No newline at end of file
@@ -0,0 +1,69 @@
+use serde::{Serialize, Deserialize};
+use std::fmt;
+use std::str::FromStr;
+use crate::PhoneError;
+
+/// A calendar date, written as YYYY-MM-DD, as postgres expects it
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Clone)]
+#[serde(transparent)]
+pub struct Date {
+    inner: String
+}
+
+impl FromStr for Date {
+    type Err = PhoneError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || PhoneError::InvalidDate(s.to_string());
+        let value = s.trim();
+        let parts = value.split('-').collect::<Vec<_>>();
+        if parts.len() != 3 || parts[0].len() != 4 || parts[1].len() != 2 || parts[2].len() != 2 {
+            return Err(invalid());
+        }
+        let fields = parts.iter()
+            .map(|part| if part.chars().all(|c| c.is_ascii_digit()) { part.parse::<u32>().ok() } else { None })
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(invalid)?;
+        let (year, month, day) = (fields[0], fields[1], fields[2]);
+        if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+            return Err(invalid());
+        }
+        Ok(Self{inner: value.to_string()})
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.inner)
+    }
+}
+
+fn days_in_month(year: u32, month: u32) -> u32 {
+    match month {
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PhoneErrorKind;
+
+    #[test]
+    fn from_str_given_valid_dates_succeeds() {
+        for value in &["2020-12-14", "2020-02-29", "2000-02-29", " 1999-01-31 "] {
+            assert_eq!(Date::from_str(value).unwrap().to_string(), value.trim());
+        }
+    }
+
+    #[test]
+    fn from_str_given_bad_dates_fails() {
+        for value in &["", "2020-12", "12/14/2020", "2020-13-01", "2019-02-29", "1900-02-29", "2020-04-31", "2020-1-01", "+020-01-01"] {
+            let result = Date::from_str(value).unwrap_err().kind();
+            assert_eq!(result, PhoneErrorKind::InvalidDate);
+        }
+    }
+}
@@ -2,4 +2,8 @@ pub mod phone;
 pub mod person_phone;
 pub mod department;
 pub mod title;
-pub mod person;
\ No newline at end of file
+pub mod person;
+pub mod location;
+pub mod contact;
+pub mod desk;
+pub mod dial_plan;
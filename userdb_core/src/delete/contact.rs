@@ -0,0 +1,67 @@
+//! Delete a contact channel from a person
+use sqlx::prelude::*;
+use sqlx::{Executor, Postgres};
+use crate::prelude::*;
+use crate::ContactCategory;
+use crate::UserDbError;
+
+const DELETE_CONTACT: &str = r"
+DELETE FROM
+    contact
+USING
+    person
+WHERE
+    contact.person_id = person.id
+AND
+    person.login = $1
+AND
+    contact.category = $2::contactcategory
+AND
+    contact.value = $3
+RETURNING contact.id;
+";
+
+const DELETE_CONTACT_BY_ID: &str = r"
+DELETE FROM
+    contact
+WHERE
+    id = $1
+RETURNING id;
+";
+
+#[derive(FromRow)]
+struct Rval {
+    id: i32
+}
+
+/// Delete the contact of the person with `login` matching `category` and
+/// `value`, returning its id.
+pub async fn delete<'c, E>(
+    executor: E,
+    login: &str,
+    category: &ContactCategory,
+    value: &str,
+) -> Result<i32, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let value = value.trim();
+    let rval: Option<Rval> = sqlx::query_as(DELETE_CONTACT)
+    .bind(login)
+    .bind(category.to_static_str())
+    .bind(value)
+    .fetch_optional(executor).await?;
+    rval.map(|Rval{id}| id)
+        .ok_or_else(|| UserDbError::NotFound(format!("{} contact '{}' of '{}'", category, value, login)))
+}
+
+/// Delete the contact with `id`, returning its id.
+pub async fn delete_by_id<'c, E>(executor: E, id: u32) -> Result<i32, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let rval: Option<Rval> = sqlx::query_as(DELETE_CONTACT_BY_ID)
+    .bind(id as i32)
+    .fetch_optional(executor).await?;
+    rval.map(|Rval{id}| id).ok_or_else(|| UserDbError::NotFound(format!("Contact with id {}", id)))
+}
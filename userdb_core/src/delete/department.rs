@@ -2,6 +2,8 @@
 //! a phone number and a person, since numbers may be shared (like extensions)
 use std::convert::AsRef;
 use sqlx::prelude::*;
+use sqlx::{Executor, Postgres};
+use crate::UserDbError;
 //use crate::prelude::*;
 
 const DELETE_DEPARTMENT: &str = r"
@@ -26,40 +28,43 @@ struct Rval {
 /// Deletes the association between phone number and person. 
 /// If there are no other persons associated with the phone number
 /// delete the underlying phone number record as well.
-pub async fn delete<I>(
-    pool: &sqlx::PgPool, 
+pub async fn delete<'c, E, I>(
+    executor: E, 
     department: I, 
-) -> Result<Option<i32>, sqlx::Error>
+) -> Result<i32, UserDbError>
 where
+    E: Executor<'c, Database = Postgres>,
     I: AsRef<str>,
 {
-    let Rval{dept_id} = sqlx::query_as(&DELETE_DEPARTMENT)
+    let Rval{dept_id} = sqlx::query_as(DELETE_DEPARTMENT)
     .bind(department.as_ref())
-    .fetch_one(pool).await?;
-    if let Some(value) = dept_id {
-        if value == 0 {
-            return Ok(None);
-        }
-    } 
-    Ok(dept_id)
+    .fetch_one(executor).await
+    .map_err(|e| match UserDbError::from(e) {
+        UserDbError::DepartmentInUse(_) => UserDbError::DepartmentInUse(format!("Department '{}'", department.as_ref())),
+        e => e,
+    })?;
+    match dept_id {
+        Some(value) if value != 0 => Ok(value),
+        _ => Err(UserDbError::NotFound(format!("Department '{}'", department.as_ref()))),
+    }
 }
 
 /// Deletes the association between phone number and person. 
 /// If there are no other persons associated with the phone number
 /// delete the underlying phone number record as well.
-pub async fn delete_by_id(
-    pool: &sqlx::PgPool, 
-    dept_id: u32,
-) -> Result<Option<i32>, sqlx::Error>
+pub async fn delete_by_id<'c, E>(
+    executor: E, 
+    id: u32,
+) -> Result<i32, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
 {
-    let Rval{dept_id} = sqlx::query_as(&DELETE_DEPT_FROM_IDS)
-    .bind(dept_id)
-    .fetch_one(pool).await?;
-    if let Some(value) = dept_id {
-        if value == 0 {
-            return Ok(None);
-        }
-    } 
-    Ok(dept_id)
+    let Rval{dept_id} = sqlx::query_as(DELETE_DEPT_FROM_IDS)
+    .bind(id)
+    .fetch_one(executor).await?;
+    match dept_id {
+        Some(value) if value != 0 => Ok(value),
+        _ => Err(UserDbError::NotFound(format!("Department with id {}", id))),
+    }
 }
 
@@ -0,0 +1,41 @@
+//! Delete the desks of a person
+use sqlx::{Executor, Postgres};
+use crate::{DeskOwner, Location, UserDbError};
+
+const DELETE_DESKS: &str = r"
+DELETE FROM
+    desk
+USING
+    person
+WHERE
+    desk.person_id = person.id
+AND
+    person.{person} = $1
+AND
+    ($2::text IS NULL OR desk.location = $2)
+RETURNING desk.id;
+";
+
+/// Delete the desk of the person at `location`, or all of their desks
+/// when no location is supplied, returning the number deleted.
+pub async fn delete<'c, E>(
+    executor: E,
+    owner: &DeskOwner,
+    location: Option<&Location>,
+) -> Result<usize, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let location = location.map(|location| location.name.clone());
+    let rval: Vec<(i32,)> = match owner {
+        DeskOwner::Id(id) => sqlx::query_as(&DELETE_DESKS.replace("{person}", "id"))
+            .bind(*id)
+            .bind(location)
+            .fetch_all(executor).await?,
+        DeskOwner::Login(login) => sqlx::query_as(&DELETE_DESKS.replace("{person}", "login"))
+            .bind(login.clone())
+            .bind(location)
+            .fetch_all(executor).await?,
+    };
+    Ok(rval.len())
+}
@@ -0,0 +1,30 @@
+//! Delete the dial plan of a location, along with its extension ranges.
+//! Extensions at the location are no longer checked against a plan.
+use sqlx::prelude::*;
+use sqlx::{Executor, Postgres};
+use crate::Location;
+use crate::UserDbError;
+
+const DELETE_DIAL_PLAN: &str = r"
+DELETE FROM
+    dialplan
+WHERE
+    location = $1
+RETURNING location;
+";
+
+#[derive(FromRow)]
+struct Rval {
+    location: String
+}
+
+/// Delete the dial plan of `location`, returning the name of the location.
+pub async fn delete<'c, E>(executor: E, location: &Location) -> Result<String, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let rval: Option<Rval> = sqlx::query_as(DELETE_DIAL_PLAN)
+    .bind(location.name.as_str())
+    .fetch_optional(executor).await?;
+    rval.map(|Rval{location}| location).ok_or_else(|| UserDbError::NotFound(format!("Dial plan of '{}'", location)))
+}
@@ -0,0 +1,58 @@
+//! Delete a location. Locations with phones may not be deleted, while
+//! the location's dial plan is deleted along with it.
+use sqlx::prelude::*;
+use sqlx::{Executor, Postgres};
+use crate::UserDbError;
+
+const DELETE_LOCATION: &str = r"
+DELETE FROM 
+    location 
+WHERE 
+    name = $1 
+RETURNING id;
+";
+
+const DELETE_LOCATION_BY_ID: &str = r"
+DELETE FROM 
+    location 
+WHERE 
+    id = $1 
+RETURNING id;
+";
+
+#[derive(FromRow)]
+struct Rval {
+    id: i32
+}
+
+/// Delete the location named `name`, returning its id.
+pub async fn delete<'c, E>(executor: E, name: &str) -> Result<i32, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let described = format!("Location '{}'", name);
+    let rval: Option<Rval> = sqlx::query_as(DELETE_LOCATION)
+    .bind(name)
+    .fetch_optional(executor).await
+    .map_err(|e| match UserDbError::from(e) {
+        UserDbError::LocationInUse(_) => UserDbError::LocationInUse(described.clone()),
+        e => e,
+    })?;
+    rval.map(|Rval{id}| id).ok_or(UserDbError::NotFound(described))
+}
+
+/// Delete the location with `id`, returning its id.
+pub async fn delete_by_id<'c, E>(executor: E, id: i32) -> Result<i32, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let described = format!("Location with id {}", id);
+    let rval: Option<Rval> = sqlx::query_as(DELETE_LOCATION_BY_ID)
+    .bind(id)
+    .fetch_optional(executor).await
+    .map_err(|e| match UserDbError::from(e) {
+        UserDbError::LocationInUse(_) => UserDbError::LocationInUse(described.clone()),
+        e => e,
+    })?;
+    rval.map(|Rval{id}| id).ok_or(UserDbError::NotFound(described))
+}
@@ -1,59 +1,99 @@
-//! Delete a person given a login or  id. 
+//! Delete a person given a login or  id.
+//!
+//! People are not removed when deleted. Rather, they are marked as
+//! terminated, ending today unless they already have an end date, and
+//! are hidden from searches by default. `purge` removes them for good
+//! once their retention period has passed.
 use std::convert::AsRef;
 use sqlx::prelude::*;
+use sqlx::{Executor, Postgres};
+use crate::UserDbError;
 
 const DELETE: &str = r"
-SELECT 
-    * 
-FROM
-    deletePerson($1) as id;
+UPDATE
+    person
+SET
+    status = 'Terminated',
+    end_date = COALESCE(end_date, GREATEST(current_date, start_date))
+WHERE
+    login = $1
+RETURNING id;
 ";
 
 const DELETE_BY_ID: &str = r"
-SELECT 
-    *
-FROM
-    deletePersonById($1) as id;
+UPDATE
+    person
+SET
+    status = 'Terminated',
+    end_date = COALESCE(end_date, GREATEST(current_date, start_date))
+WHERE
+    id = $1
+RETURNING id;
+";
+
+// phones, contacts and desks go along with the person, and their
+// reports are left without a manager
+const PURGE: &str = r"
+DELETE FROM
+    person
+WHERE
+    status = 'Terminated'
+AND
+    COALESCE(end_date, current_date) <= current_date - $1::int
+RETURNING login;
 ";
 
 #[derive(FromRow)]
 struct Rval {
-    id: Option<i32>
+    id: i32
+}
+
+#[derive(FromRow)]
+struct Login {
+    login: String
 }
 
-/// Delete a person record which matches the supplied arguments. 
-pub async fn delete<I>(
-    pool: &sqlx::PgPool, 
-    login: I, 
-) -> Result<Option<i32>, sqlx::Error>
+/// Mark the person with the supplied login as terminated, returning their id.
+pub async fn delete<'c, E, I>(
+    executor: E,
+    login: I,
+) -> Result<i32, UserDbError>
 where
+    E: Executor<'c, Database = Postgres>,
     I: AsRef<str>,
 {
-    let Rval{id} = sqlx::query_as(&DELETE)
+    let rval: Option<Rval> = sqlx::query_as(DELETE)
     .bind(login.as_ref())
-    .fetch_one(pool).await?;
-    if let Some(value) = id {
-        if value == 0 {
-            return Ok(None);
-        }
-    } 
-    Ok(id)
+    .fetch_optional(executor).await?;
+    rval.map(|Rval{id}| id).ok_or_else(|| UserDbError::NotFound(format!("Person '{}'", login.as_ref())))
+}
+
+/// Mark the person with the supplied id as terminated, returning their id.
+pub async fn delete_by_id<'c, E>(
+    executor: E,
+    id: u32,
+) -> Result<i32, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let rval: Option<Rval> = sqlx::query_as(DELETE_BY_ID)
+    .bind(id as i32)
+    .fetch_optional(executor).await?;
+    rval.map(|Rval{id}| id).ok_or_else(|| UserDbError::NotFound(format!("Person with id {}", id)))
 }
 
-/// delete phone given its id. This is a more direct method which 
-/// simply deletes the phone by its id.
-pub async fn delete_by_id(
-    pool: &sqlx::PgPool, 
-    id: u32, 
-) -> Result<Option<i32>, sqlx::Error> {
-
-    let Rval{id} = sqlx::query_as(&DELETE_BY_ID)
-    .bind(id)
-    .fetch_one(pool).await?;
-    if let Some(value) = id {
-        if value == 0 {
-            return Ok(None);
-        }
-    } 
-    Ok(id)
-}
\ No newline at end of file
+/// Remove everyone who was terminated at least `retention_days` ago,
+/// along with their phone assignments, contacts and desks, returning
+/// their logins.
+pub async fn purge<'c, E>(
+    executor: E,
+    retention_days: u32,
+) -> Result<Vec<String>, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let logins: Vec<Login> = sqlx::query_as(PURGE)
+    .bind(retention_days as i32)
+    .fetch_all(executor).await?;
+    Ok(logins.into_iter().map(|Login{login}| login).collect())
+}
@@ -2,13 +2,15 @@
 //! a phone number and a person, since numbers may be shared (like extensions)
 use std::convert::AsRef;
 use sqlx::prelude::*;
+use sqlx::{Executor, Postgres};
 use crate::prelude::*;
+use crate::UserDbError;
 
 const DELETE_PHONE: &str = r"
 SELECT 
     * 
 FROM 
-    deletePhone($1, $2, $3::phonecategory, $4::location) AS phone_id;
+    deletePhone($1, $2, $3::phonecategory, $4) AS phone_id;
 ";
 
 const DELETE_PHONE_FROM_IDS: &str = r"
@@ -17,6 +19,13 @@ SELECT
 FROM
     deletePhoneFromIds($1, $2) AS phone_id;
 ";
+const UNSHARE_PHONE: &str = r"
+SELECT
+    *
+FROM
+    unsharePhone($1, $2) AS phone_id;
+";
+
 #[derive(FromRow)]
 struct Rval {
     phone_id: Option<i32>
@@ -25,39 +34,68 @@ struct Rval {
 /// Deletes the association between phone number and person. 
 /// If there are no other persons associated with the phone number
 /// delete the underlying phone number record as well.
-pub async fn delete<I>(
-    pool: &sqlx::PgPool, 
+pub async fn delete<'c, E, I>(
+    executor: E, 
     login: I, 
     number: &crate::NumberString, 
     category: &crate::PhoneCategory, 
     location: &crate::Location
-) -> Result<Option<i32>, sqlx::Error>
+) -> Result<i32, UserDbError>
 where
+    E: Executor<'c, Database = Postgres>,
     I: AsRef<str>,
 {
-    let number = number.to_string();
-    let Rval{phone_id} = sqlx::query_as(&DELETE_PHONE)
+    let number = number.normalize(category, location.country().as_ref())?.to_string();
+    let missing = format!("{} phone {} at {} for '{}'", category, number, location, login.as_ref());
+    let Rval{phone_id} = sqlx::query_as(DELETE_PHONE)
     .bind(login.as_ref())
     .bind(number)
     .bind(category.to_static_str())
-    .bind(location.to_static_str())
-    .fetch_one(pool).await?;
-    Ok(phone_id)
+    .bind(location.name.as_str())
+    .fetch_one(executor).await?;
+    match phone_id {
+        Some(value) if value != 0 => Ok(value),
+        _ => Err(UserDbError::NotFound(missing)),
+    }
 }
 
 /// Deletes the association between phone number and person. 
 /// If there are no other persons associated with the phone number
 /// delete the underlying phone number record as well.
-pub async fn delete_by_id(
-    pool: &sqlx::PgPool, 
+pub async fn delete_by_id<'c, E>(
+    executor: E, 
     person_id: u32,
     phone_id: u32,
-) -> Result<Option<i32>, sqlx::Error>
+) -> Result<i32, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
 {
-    let Rval{phone_id} = sqlx::query_as(&DELETE_PHONE_FROM_IDS)
+    let Rval{phone_id: deleted} = sqlx::query_as(DELETE_PHONE_FROM_IDS)
     .bind(person_id)
     .bind(phone_id)
-    .fetch_one(pool).await?;
-    Ok(phone_id)
+    .fetch_one(executor).await?;
+    deleted.ok_or_else(|| UserDbError::NotFound(format!("Phone with id {} for person with id {}", phone_id, person_id)))
 }
 
+
+/// Stop sharing the phone with `phone_id` with the person with `login`.
+/// The phone must still be assigned to someone else afterwards; a phone
+/// belonging to `login` alone should be deleted instead.
+pub async fn unshare<'c, E, I>(
+    executor: E,
+    login: I,
+    phone_id: u32,
+) -> Result<i32, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+    I: AsRef<str>,
+{
+    let Rval{phone_id: unshared} = sqlx::query_as(UNSHARE_PHONE)
+    .bind(login.as_ref())
+    .bind(phone_id as i32)
+    .fetch_one(executor).await?;
+    match unshared {
+        Some(value) if value != 0 => Ok(value),
+        _ => Err(UserDbError::NotFound(format!("Phone with id {} for '{}'", phone_id, login.as_ref()))),
+    }
+}
@@ -3,7 +3,9 @@
 //! under normal circumstances...
 use std::convert::AsRef;
 use sqlx::prelude::*;
+use sqlx::{Executor, Postgres};
 use crate::prelude::*;
+use crate::UserDbError;
 
 const DELETE_PHONE: &str = r"
 WITH phone_cte AS (
@@ -18,7 +20,7 @@ WITH phone_cte AS (
     AND
         category = $3::phonecategory
     AND
-        location = $4::location
+        location = $4
 
 )
 DELETE FROM 
@@ -44,51 +46,51 @@ RETURNING id
 
 #[derive(FromRow)]
 struct Rval {
-    id: Option<i32>
+    id: i32
 }
 
 /// Delete a phone record which matches the supplied arguments. 
 /// This method is most likely NOT what you want. Please
 /// use ```person_phone::delete``` instead.
-pub async fn delete<I>(
-    pool: &sqlx::PgPool, 
+pub async fn delete<'c, E, I>(
+    executor: E, 
     login: I, 
     number: &crate::NumberString, 
     category: &crate::PhoneCategory, 
     location: &crate::Location
-) -> Result<Option<i32>, sqlx::Error>
+) -> Result<i32, UserDbError>
 where
+    E: Executor<'c, Database = Postgres>,
     I: AsRef<str>,
 {
-    let number = number.to_string();
-    let Rval{id} = sqlx::query_as(&DELETE_PHONE)
+    let number = number.normalize(category, location.country().as_ref())?.to_string();
+    let missing = format!("{} phone {} at {} for '{}'", category, number, location, login.as_ref());
+    let rval: Option<Rval> = sqlx::query_as(DELETE_PHONE)
     .bind(login.as_ref())
     .bind(number)
     .bind(category.to_static_str())
-    .bind(location.to_static_str())
-    .fetch_one(pool).await?;
-    if let Some(value) = id {
-        if value == 0 {
-            return Ok(None);
-        }
-    } 
-    Ok(id)
+    .bind(location.name.as_str())
+    .fetch_optional(executor).await?;
+    match rval {
+        Some(Rval{id}) => Ok(id),
+        None => Err(UserDbError::NotFound(missing)),
+    }
 }
 
 /// delete phone given its id. This is a more direct method which 
 /// simply deletes the phone by its id.
-pub async fn delete_by_id(
-    pool: &sqlx::PgPool, 
+pub async fn delete_by_id<'c, E>(
+    executor: E, 
     id: u32, 
-) -> Result<Option<i32>, sqlx::Error>
+) -> Result<i32, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
 {
-    let Rval{id} = sqlx::query_as(&DELETE_PHONE_ID)
+    let rval: Option<Rval> = sqlx::query_as(DELETE_PHONE_ID)
     .bind(id)
-    .fetch_one(pool).await?;
-    if let Some(value) = id {
-        if value == 0 {
-            return Ok(None);
-        }
-    } 
-    Ok(id)
+    .fetch_optional(executor).await?;
+    match rval {
+        Some(Rval{id}) => Ok(id),
+        None => Err(UserDbError::NotFound(format!("Phone with id {}", id))),
+    }
 }
\ No newline at end of file
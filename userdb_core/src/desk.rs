@@ -0,0 +1,79 @@
+// external crates
+use serde::{Serialize, Deserialize};
+use sqlx::FromRow;
+use std::fmt;
+
+/// Struct which represents where a person sits within a site, in the
+/// backing database. It is also capable of serializing and deserializing
+/// via serde
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct Desk {
+    pub desk_id: i32,
+    /// The location the desk is at. A person working from a single
+    /// location need not supply it
+    #[serde(default)]
+    pub location: Option<String>,
+    #[serde(default)]
+    pub building: Option<String>,
+    #[serde(default)]
+    pub floor: Option<String>,
+    /// The room or desk
+    #[serde(default)]
+    pub room: Option<String>,
+}
+
+/// Identifies the person whose desk is updated or deleted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeskOwner {
+    Id(i32),
+    Login(String),
+}
+
+impl fmt::Display for DeskOwner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Id(id) => write!(f, "Person with id {}", id),
+            Self::Login(login) => write!(f, "Person '{}'", login),
+        }
+    }
+}
+
+impl fmt::Display for Desk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts = [("Building", &self.building), ("Floor", &self.floor), ("Room", &self.room)]
+            .iter()
+            .filter_map(|(name, value)| value.as_ref().map(|value| format!("{} {}", name, value)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        match &self.location {
+            Some(location) => write!(f, "{} ({})", parts, location),
+            None => write!(f, "{}", parts),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn desk() -> Desk {
+        Desk {
+            desk_id: 1,
+            location: Some("PlayaVista".into()),
+            building: Some("2".into()),
+            floor: Some("3".into()),
+            room: Some("3-114".into()),
+        }
+    }
+
+    #[test]
+    fn display_given_every_field_succeeds() {
+        assert_eq!(desk().to_string(), "Building 2, Floor 3, Room 3-114 (PlayaVista)");
+    }
+
+    #[test]
+    fn display_given_missing_fields_skips_them() {
+        let desk = Desk { location: None, floor: None, ..desk() };
+        assert_eq!(desk.to_string(), "Building 2, Room 3-114");
+    }
+}
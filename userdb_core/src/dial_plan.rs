@@ -0,0 +1,121 @@
+use serde::{Serialize, Deserialize};
+use std::fmt;
+use std::str::FromStr;
+use crate::PhoneError;
+
+/// A range of extensions, inclusive of both ends
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ExtensionRange {
+    pub first: u32,
+    pub last: u32,
+}
+
+impl ExtensionRange {
+    /// Does the range include `ext`
+    pub fn contains(&self, ext: u32) -> bool {
+        (self.first..=self.last).contains(&ext)
+    }
+}
+
+impl FromStr for ExtensionRange {
+    type Err = PhoneError;
+    /// Convert from a str written as first-last (eg 6000-6999), or as a
+    /// single extension
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || PhoneError::InvalidExtensionRange(s.to_string());
+        let (first, last) = s.split_once('-').unwrap_or((s, s));
+        let first = first.trim().parse::<u32>().map_err(|_| invalid())?;
+        let last = last.trim().parse::<u32>().map_err(|_| invalid())?;
+        if first > last {
+            return Err(invalid());
+        }
+        Ok(Self{first, last})
+    }
+}
+
+impl fmt::Display for ExtensionRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.first, self.last)
+    }
+}
+
+/// The dial plan of a location: its main switchboard number, the ranges
+/// its extensions are drawn from, and the prefix which, followed by an
+/// extension, dials it directly from outside (its DID). Numbers are in E.164.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct DialPlan {
+    pub location: String,
+    pub switchboard: String,
+    pub did_prefix: String,
+    pub extensions: Vec<ExtensionRange>,
+}
+
+impl DialPlan {
+    /// Is `ext` an extension within the plan
+    pub fn contains(&self, ext: &str) -> bool {
+        match ext.parse::<u32>() {
+            Ok(value) if ext.chars().all(|c| c.is_ascii_digit()) => {
+                self.extensions.iter().any(|range| range.contains(value))
+            }
+            _ => false,
+        }
+    }
+
+    /// The DID of `ext`, or None if it is outside the plan
+    pub fn did(&self, ext: &str) -> Option<String> {
+        if self.contains(ext) {
+            Some(format!("{}{}", self.did_prefix, ext))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vancouver() -> DialPlan {
+        DialPlan {
+            location: "Vancouver".into(),
+            switchboard: "+16045550100".into(),
+            did_prefix: "+1604555".into(),
+            extensions: vec![ExtensionRange{first: 6000, last: 6999}],
+        }
+    }
+
+    #[test]
+    fn contains_given_extension_in_range_succeeds() {
+        assert!(vancouver().contains("6432"));
+    }
+
+    #[test]
+    fn contains_given_extension_out_of_range_fails() {
+        let plan = vancouver();
+        for ext in &["9878", "+6432", "64a2", ""] {
+            assert!(!plan.contains(ext));
+        }
+    }
+
+    #[test]
+    fn extension_range_from_str_given_range_or_single_extension_succeeds() {
+        let tests = &[("6000-6999", (6000, 6999)), (" 8000 - 9999 ", (8000, 9999)), ("4100", (4100, 4100))];
+        for (range, (first, last)) in tests {
+            assert_eq!(ExtensionRange::from_str(range).unwrap(), ExtensionRange{first: *first, last: *last});
+        }
+    }
+
+    #[test]
+    fn extension_range_from_str_given_bad_input_fails() {
+        for range in &["6999-6000", "6000-", "six"] {
+            let result = ExtensionRange::from_str(range).unwrap_err().kind();
+            assert_eq!(result, crate::PhoneErrorKind::InvalidExtensionRange);
+        }
+    }
+
+    #[test]
+    fn did_prefixes_extension() {
+        assert_eq!(vancouver().did("6432").as_deref(), Some("+16045556432"));
+        assert_eq!(vancouver().did("3412"), None);
+    }
+}
@@ -1,18 +1,43 @@
 use thiserror::Error;
 use enum_kinds::EnumKind;
+use sqlx::postgres::PgDatabaseError;
 
 /// Custom error enum for the phone project.
 #[derive(Error, Debug, EnumKind)]
 #[enum_kind(PhoneErrorKind)]
 pub enum PhoneError {
-    #[error("Invalid category supplied '{0}'. Should be one of: home, cell, ext")]
+    #[error("Invalid category supplied '{0}'. Should be one of: home, cell, ext, pager, fax, desk")]
     InvalidCategory(String),
 
+    #[error("Invalid contact category supplied '{0}'. Should be one of: email, chat, personal")]
+    InvalidContactCategory(String),
+
     #[error("Invalid location supplied '{0}'")]
     InvalidLocation(String),
 
     #[error("Invalid number supplied '{0}'")]
-    InvalidNumber(String)
+    InvalidNumber(String),
+
+    #[error("Invalid country supplied '{0}'. Should be one of: us, ca, in")]
+    InvalidCountry(String),
+
+    #[error("Unable to tell which country '{0}' is in. Supply a number starting with +, or a location with a calling code")]
+    MissingCountry(String),
+
+    #[error("Invalid extension range supplied '{0}'. Should be written as first-last, eg 6000-6999")]
+    InvalidExtensionRange(String),
+
+    #[error("Invalid status supplied '{0}'. Should be one of: active, onleave, contractor, terminated")]
+    InvalidPersonStatus(String),
+
+    #[error("Invalid date supplied '{0}'. Should be written as YYYY-MM-DD")]
+    InvalidDate(String),
+
+    #[error("Invalid entity supplied '{0}'. Should be one of: person, phone, assignment, contact, desk, title, department, location, dialplan, extension_range")]
+    InvalidAuditEntity(String),
+
+    #[error("Invalid search '{0}': {1}")]
+    InvalidSearch(String, String),
 }
 
 impl PhoneError {
@@ -22,4 +47,386 @@ impl PhoneError {
     pub fn kind(&self) -> PhoneErrorKind {
         PhoneErrorKind::from(self)
     }
-}
\ No newline at end of file
+}
+
+/// Errors encountered while loading the userdb configuration.
+#[derive(Error, Debug, EnumKind)]
+#[enum_kind(ConfigErrorKind)]
+pub enum ConfigError {
+    #[error("Unable to read config file '{0}': {1}")]
+    Io(String, std::io::Error),
+
+    #[error("Invalid config {0}: {1}")]
+    Parse(String, String),
+
+    #[error("Unknown profile '{0}'")]
+    UnknownProfile(String),
+}
+
+impl ConfigError {
+    /// Construct a ConfigErrorKind from a ConfigError. See `PhoneError::kind`
+    pub fn kind(&self) -> ConfigErrorKind {
+        ConfigErrorKind::from(self)
+    }
+}
+
+/// Errors returned by the userdb library calls. Failures reported by
+/// postgres are classified into the domain errors below where possible,
+/// falling back on `Sqlx` otherwise.
+#[derive(Error, Debug, EnumKind)]
+#[enum_kind(UserDbErrorKind)]
+pub enum UserDbError {
+    #[error("{0} not found")]
+    NotFound(String),
+
+    #[error("Login '{0}' already exists")]
+    DuplicateLogin(String),
+
+    #[error("A person named '{0}' already exists")]
+    DuplicateFullName(String),
+
+    #[error("Department '{0}' already exists")]
+    DuplicateDepartment(String),
+
+    #[error("Title '{0}' already exists")]
+    DuplicateTitle(String),
+
+    #[error("Phone '{0}' already exists")]
+    DuplicatePhone(String),
+
+    #[error("Location '{0}' already exists")]
+    DuplicateLocation(String),
+
+    #[error("Contact '{0}' already exists")]
+    DuplicateContact(String),
+
+    #[error("Unknown department '{0}'")]
+    UnknownDepartment(String),
+
+    #[error("Unknown title '{0}'")]
+    UnknownTitle(String),
+
+    #[error("Unknown location '{0}'")]
+    UnknownLocation(String),
+
+    #[error("Unknown manager '{0}'")]
+    UnknownManager(String),
+
+    #[error("{0} would create a reporting cycle")]
+    ManagerCycle(String),
+
+    #[error("{0} is still assigned to one or more people")]
+    DepartmentInUse(String),
+
+    #[error("Department '{0}' may not be merged into itself")]
+    SelfMerge(String),
+
+    #[error("'{0}' already has the phone with id {1}")]
+    PhoneAlreadyAssigned(String, String),
+
+    #[error("The phone with id {0} is only assigned to '{1}'. Delete it instead")]
+    PhoneNotShared(String, String),
+
+    #[error("{0} is still assigned to one or more people")]
+    TitleInUse(String),
+
+    #[error("{0} still has one or more phones or desks")]
+    LocationInUse(String),
+
+    #[error("Unknown timezone '{0}'. Should be named as in the tz database, eg America/Los_Angeles")]
+    InvalidTimezone(String),
+
+    #[error("Invalid country '{0}'. Should be a two letter ISO code, eg US")]
+    InvalidCountryCode(String),
+
+    #[error("Invalid calling code '{0}'. Should be one to three digits, without the +, eg 44")]
+    InvalidCallingCode(String),
+
+    #[error("Invalid trunk prefix '{0}'. Should be at most two digits, eg 0")]
+    InvalidTrunkPrefix(String),
+
+    #[error("Invalid phone number '{0}'. Numbers may only contain digits, after an optional leading +")]
+    InvalidNumber(String),
+
+    #[error("Invalid email address '{0}'")]
+    InvalidEmail(String),
+
+    #[error("A contact may not be empty")]
+    EmptyContact,
+
+    #[error("A person's end date may not precede their start date")]
+    EndBeforeStart,
+
+    #[error("Extension {0} is outside the dial plan")]
+    ExtensionOutsideDialPlan(String),
+
+    #[error(transparent)]
+    Phone(#[from] PhoneError),
+
+    #[error("Unknown export format '{0}'. Should be one of: csv, jsonl, vcard, org")]
+    InvalidFormat(String),
+
+    #[error("Unknown conflict policy '{0}'. Should be one of: skip, update, fail")]
+    InvalidConflictPolicy(String),
+
+    #[error("Invalid snapshot: {0}")]
+    InvalidSnapshot(String),
+
+    #[error("Unable to decode query results: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Unable to write output: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Database error: {0}")]
+    Sqlx(sqlx::Error),
+}
+
+impl UserDbError {
+    /// Construct a UserDbErrorKind from a UserDbError. See `PhoneError::kind`
+    pub fn kind(&self) -> UserDbErrorKind {
+        UserDbErrorKind::from(self)
+    }
+}
+
+/// Errors encountered while importing people and phones. Each error
+/// raised by a row of the input carries the line it was read from.
+#[derive(Error, Debug, EnumKind)]
+#[enum_kind(ImportErrorKind)]
+pub enum ImportError {
+    #[error("Unable to read '{0}': {1}")]
+    Io(String, std::io::Error),
+
+    #[error("line {0}: {1}")]
+    Malformed(u64, String),
+
+    #[error("line {0}: missing {1}")]
+    MissingField(u64, &'static str),
+
+    #[error("line {0}: {1}")]
+    Invalid(u64, PhoneError),
+
+    #[error("line {0}: invalid {1} '{2}'. Should be true or false")]
+    InvalidFlag(u64, &'static str, String),
+
+    #[error("line {0}: {1}")]
+    Rejected(u64, UserDbError),
+
+    #[error(transparent)]
+    UserDb(#[from] UserDbError),
+}
+
+impl ImportError {
+    /// Construct an ImportErrorKind from an ImportError. See `PhoneError::kind`
+    pub fn kind(&self) -> ImportErrorKind {
+        ImportErrorKind::from(self)
+    }
+}
+
+impl From<sqlx::Error> for UserDbError {
+    fn from(error: sqlx::Error) -> Self {
+        if let sqlx::Error::RowNotFound = error {
+            return UserDbError::NotFound("Record".into());
+        }
+        let classified = error
+            .as_database_error()
+            .and_then(|e| e.try_downcast_ref::<PgDatabaseError>())
+            .and_then(|e| classify(e.code(), e.constraint(), e.column(), e.detail()));
+        classified.unwrap_or(UserDbError::Sqlx(error))
+    }
+}
+
+// Map the sqlstate code and constraint reported by postgres onto a UserDbError,
+// returning None if the failure is not one we know how to describe.
+fn classify(code: &str, constraint: Option<&str>, column: Option<&str>, detail: Option<&str>) -> Option<UserDbError> {
+    let value = detail.and_then(key_value).unwrap_or_default();
+    let referenced = detail.map(|d| d.contains("still referenced")).unwrap_or(false);
+    let error = match (code, constraint, column) {
+        // unique_violation
+        ("23505", Some("person_login_key"), _) => UserDbError::DuplicateLogin(value),
+        ("23505", Some("person_first_last_key"), _) => UserDbError::DuplicateFullName(value.replace(", ", " ")),
+        ("23505", Some("department_name_key"), _) => UserDbError::DuplicateDepartment(value),
+        ("23505", Some("title_name_key"), _) => UserDbError::DuplicateTitle(value),
+        ("23505", Some("phone_number_category_location_key"), _) => UserDbError::DuplicatePhone(value.replace(", ", " ")),
+        ("23505", Some("location_name_key"), _) | ("23505", Some("location_code_key"), _) => UserDbError::DuplicateLocation(value),
+        // the person's id is dropped, leaving the category and value
+        ("23505", Some("contact_person_id_category_value_key"), _) => UserDbError::DuplicateContact(
+            value.split_once(", ").map(|(_, rest)| rest.replace(", ", " ")).unwrap_or_default()),
+        ("23505", Some("people_phones_pkey"), _) => {
+            let (login, phone_id) = value.split_once(", ").unwrap_or((value.as_str(), ""));
+            UserDbError::PhoneAlreadyAssigned(login.to_string(), phone_id.to_string())
+        },
+        // not_null_violation. The department and title are looked up by name
+        // when creating a person, resulting in a null id if they do not exist
+        ("23502", _, Some("department_id")) => UserDbError::UnknownDepartment(value),
+        ("23502", _, Some("title_id")) => UserDbError::UnknownTitle(value),
+        // foreign_key_violation
+        ("23503", Some("fk_department"), _) if referenced => UserDbError::DepartmentInUse(format!("Department with id {}", value)),
+        ("23503", Some("fk_department"), _) => UserDbError::UnknownDepartment(value),
+        ("23503", Some("fk_title"), _) if referenced => UserDbError::TitleInUse(format!("Title with id {}", value)),
+        ("23503", Some("fk_title"), _) => UserDbError::UnknownTitle(value),
+        ("23503", Some("fk_manager"), _) => UserDbError::UnknownManager(value),
+        ("23503", Some("people_phones_person_id_fkey"), _) => UserDbError::NotFound(format!("Person '{}'", value)),
+        ("23503", Some("people_phones_phone_id_fkey"), _) => UserDbError::NotFound(format!("Phone with id {}", value)),
+        ("23503", Some("extension_range_location_fkey"), _) => UserDbError::NotFound(format!("Dial plan of '{}'", value)),
+        ("23503", Some("fk_phone_location"), _) | ("23503", Some("fk_desk_location"), _) if referenced => UserDbError::LocationInUse(format!("Location '{}'", value)),
+        ("23503", Some("fk_phone_location"), _) | ("23503", Some("fk_dialplan_location"), _) | ("23503", Some("fk_desk_location"), _) => UserDbError::UnknownLocation(value),
+        // check_violation
+        ("23514", Some("phone_number_check"), _) => UserDbError::InvalidNumber(value),
+        ("23514", Some("dialplan_switchboard_check"), _) | ("23514", Some("dialplan_did_prefix_check"), _) => UserDbError::InvalidNumber(value),
+        ("23514", Some("phone_extension_check"), _) => UserDbError::ExtensionOutsideDialPlan(value.replace(", ", " at ")),
+        ("23514", Some("location_timezone_check"), _) => UserDbError::InvalidTimezone(value),
+        ("23514", Some("location_country_check"), _) => UserDbError::InvalidCountryCode(value),
+        ("23514", Some("location_calling_code_check"), _) => UserDbError::InvalidCallingCode(value),
+        ("23514", Some("location_trunk_prefix_check"), _) => UserDbError::InvalidTrunkPrefix(value),
+        ("23514", Some("person_manager_cycle_check"), _) => UserDbError::ManagerCycle(value.replace(", ", " reporting to ")),
+        ("23514", Some("contact_email_check"), _) => UserDbError::InvalidEmail(value),
+        ("23514", Some("contact_value_check"), _) => UserDbError::EmptyContact,
+        ("23514", Some("person_dates_check"), _) => UserDbError::EndBeforeStart,
+        ("23514", Some("department_merge_check"), _) => UserDbError::SelfMerge(value),
+        ("23514", Some("people_phones_shared_check"), _) => {
+            let (phone_id, login) = value.split_once(", ").unwrap_or((value.as_str(), ""));
+            UserDbError::PhoneNotShared(phone_id.to_string(), login.to_string())
+        },
+        _ => return None,
+    };
+    Some(error)
+}
+
+// Extract the value from a detail message of the form
+// `Key (login)=(jdoe) already exists.` The value may itself hold
+// parentheses, eg a department named `Software (Playa)`, so it runs to
+// the last `) ` of the message, which starts the description following it
+fn key_value(detail: &str) -> Option<String> {
+    let start = detail.find(")=(")? + 3;
+    let rest = &detail[start..];
+    let end = rest.rfind(") ").or_else(|| rest.rfind(')'))?;
+    Some(rest[..end].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_given_duplicate_login_succeeds() {
+        let result = classify("23505", Some("person_login_key"), None, Some("Key (login)=(jdoe) already exists."));
+        match result {
+            Some(UserDbError::DuplicateLogin(login)) => assert_eq!(login, "jdoe"),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn classify_given_duplicate_fullname_succeeds() {
+        let result = classify("23505", Some("person_first_last_key"), None, Some("Key (first, last)=(Sam, Slade) already exists."));
+        match result {
+            Some(UserDbError::DuplicateFullName(name)) => assert_eq!(name, "Sam Slade"),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn classify_given_value_with_parentheses_keeps_it_whole() {
+        let tests = &[
+            ("Key (name)=(Software (Playa)) already exists.", "Software (Playa)"),
+            ("Key (name)=(A) B) is not present in table \"department\".", "A) B"),
+            ("Key (name)=(jdoe)", "jdoe"),
+        ];
+        for (detail, expect) in tests {
+            assert_eq!(key_value(detail).as_deref(), Some(*expect));
+        }
+    }
+
+    #[test]
+    fn classify_given_duplicate_contact_succeeds() {
+        let result = classify("23505", Some("contact_person_id_category_value_key"), None, Some("Key (person_id, category, value)=(3, Email, jdoe@example.com) already exists."));
+        match result {
+            Some(UserDbError::DuplicateContact(contact)) => assert_eq!(contact, "Email jdoe@example.com"),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn classify_given_shared_phone_succeeds() {
+        let result = classify("23505", Some("people_phones_pkey"), None, Some("Key (login, phone_id)=(jdoe, 42) already exists."));
+        match result {
+            Some(UserDbError::PhoneAlreadyAssigned(login, phone_id)) => assert_eq!((login.as_str(), phone_id.as_str()), ("jdoe", "42")),
+            other => panic!("unexpected {:?}", other),
+        }
+        let result = classify("23514", Some("people_phones_shared_check"), None, Some("Key (phone_id, login)=(42, jdoe) is not shared."));
+        match result {
+            Some(UserDbError::PhoneNotShared(phone_id, login)) => assert_eq!((phone_id.as_str(), login.as_str()), ("42", "jdoe")),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn classify_given_manager_cycle_succeeds() {
+        let result = classify("23514", Some("person_manager_cycle_check"), None, Some("Key (login, manager)=(jdoe, sslade) would create a cycle."));
+        match result {
+            Some(UserDbError::ManagerCycle(cycle)) => assert_eq!(cycle, "jdoe reporting to sslade"),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn classify_given_duplicate_phone_succeeds() {
+        let result = classify("23505", Some("phone_number_category_location_key"), None, Some("Key (number, category, location)=(1234, Extension, Portland) already exists."));
+        match result {
+            Some(UserDbError::DuplicatePhone(phone)) => assert_eq!(phone, "1234 Extension Portland"),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn classify_given_null_department_succeeds() {
+        let result = classify("23502", None, Some("department_id"), Some("Failing row contains (...)."));
+        assert_eq!(result.map(|e| e.kind()), Some(UserDbErrorKind::UnknownDepartment));
+    }
+
+    #[test]
+    fn classify_given_department_in_use_succeeds() {
+        let result = classify("23503", Some("fk_department"), None, Some("Key (id)=(3) is still referenced from table \"person\"."));
+        match result {
+            Some(UserDbError::DepartmentInUse(dept)) => assert_eq!(dept, "Department with id 3"),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn classify_given_missing_title_succeeds() {
+        let result = classify("23503", Some("fk_title"), None, Some("Key (title_id)=(99) is not present in table \"title\"."));
+        match result {
+            Some(UserDbError::UnknownTitle(id)) => assert_eq!(id, "99"),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn classify_given_number_check_succeeds() {
+        let result = classify("23514", Some("phone_number_check"), None, None);
+        assert_eq!(result.map(|e| e.kind()), Some(UserDbErrorKind::InvalidNumber));
+    }
+
+    #[test]
+    fn classify_given_extension_check_succeeds() {
+        let detail = "Key (number, location)=(1234, Vancouver) is outside the dial plan.";
+        match classify("23514", Some("phone_extension_check"), None, Some(detail)) {
+            Some(UserDbError::ExtensionOutsideDialPlan(value)) => assert_eq!(value, "1234 at Vancouver"),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn classify_given_location_in_use_succeeds() {
+        let detail = "Key (name)=(Portland) is still referenced from table \"phone\".";
+        let result = classify("23503", Some("fk_phone_location"), None, Some(detail));
+        assert_eq!(result.map(|e| e.kind()), Some(UserDbErrorKind::LocationInUse));
+    }
+
+    #[test]
+    fn classify_given_unknown_code_fails() {
+        assert!(classify("40001", None, None, None).is_none());
+    }
+}
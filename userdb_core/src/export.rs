@@ -0,0 +1,375 @@
+//! Export of the directory for use by other tools.
+//!
+//! Every person in `personview` is streamed from the database, along with
+//! all of their phones, and written out as they arrive in one of the
+//! following formats:
+//! - `csv`: one row per phone, with the same columns read by `import`,
+//!   so an export may be imported again
+//! - `jsonl`: one json object per person, as produced by `phone --json`
+//! - `vcard`: one vCard 3.0 card per person, for contacts apps. Email
+//!   addresses are included; chat handles have no vCard 3.0 property
+//! - `org`: the reporting structure as a json array with a tree per
+//!   person without a manager. Written once every person has arrived
+use futures::TryStreamExt;
+use sqlx::FromRow;
+use sqlx::{Executor, Postgres};
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+
+use crate::prelude::*;
+use crate::read::person::PersonQuery;
+use crate::{ContactCategory, JsonAdapter, OrgChart, OrgPerson, PersonView, PhoneCategory, PhoneNumber, QueryMode, UserDbError};
+
+/// The formats which the directory may be exported as
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ExportFormat {
+    Csv,
+    Jsonl,
+    Vcard,
+    Org,
+}
+
+impl FromStr for ExportFormat {
+    type Err = UserDbError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "jsonl" | "json" => Ok(Self::Jsonl),
+            "vcard" | "vcf" => Ok(Self::Vcard),
+            "org" => Ok(Self::Org),
+            _ => Err(UserDbError::InvalidFormat(s.to_string())),
+        }
+    }
+}
+
+impl ToStaticStr for ExportFormat {
+    fn to_static_str(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Jsonl => "jsonl",
+            Self::Vcard => "vcard",
+            Self::Org => "org",
+        }
+    }
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_static_str())
+    }
+}
+
+/// Stream every person in the database to `writer` in the requested
+/// `format`, returning the number of people written.
+pub async fn export<'c, E, W>(executor: E, format: ExportFormat, writer: W) -> Result<usize, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+    W: Write,
+{
+    let query = PersonQuery::new().query(&QueryMode::Exact);
+    let mut rows = sqlx::query(&query).fetch(executor);
+    let mut output = Output::new(format, writer)?;
+    let mut count = 0;
+    while let Some(row) = rows.try_next().await? {
+        let JsonAdapter{inner} = JsonAdapter::from_row(&row)?;
+        output.write(inner)?;
+        count += 1;
+    }
+    output.finish()?;
+    Ok(count)
+}
+
+// wraps the writer in whatever the format needs
+enum Output<W: Write> {
+    Csv(Box<csv::Writer<W>>),
+    Jsonl(W),
+    Vcard(W),
+    // the people, and the login of their manager, gathered until finished
+    Org(W, Vec<(OrgPerson, Option<String>)>),
+}
+
+impl<W: Write> Output<W> {
+    fn new(format: ExportFormat, writer: W) -> Result<Self, UserDbError> {
+        match format {
+            ExportFormat::Csv => {
+                let mut writer = csv::Writer::from_writer(writer);
+                writer.write_record(CSV_HEADER).map_err(csv_error)?;
+                Ok(Self::Csv(Box::new(writer)))
+            }
+            ExportFormat::Jsonl => Ok(Self::Jsonl(writer)),
+            ExportFormat::Vcard => Ok(Self::Vcard(writer)),
+            ExportFormat::Org => Ok(Self::Org(writer, Vec::new())),
+        }
+    }
+
+    fn write(&mut self, value: serde_json::Value) -> Result<(), UserDbError> {
+        match self {
+            Self::Csv(writer) => {
+                let person: PersonView = serde_json::from_value(value)?;
+                for record in csv_records(&person) {
+                    writer.write_record(&record).map_err(csv_error)?;
+                }
+            }
+            Self::Jsonl(writer) => {
+                serde_json::to_writer(&mut *writer, &value)?;
+                writeln!(writer)?;
+            }
+            Self::Vcard(writer) => {
+                let person: PersonView = serde_json::from_value(value)?;
+                writer.write_all(vcard(&person).as_bytes())?;
+            }
+            Self::Org(_, people) => {
+                let person: PersonView = serde_json::from_value(value)?;
+                let PersonView{person_id, login, fullname, title, department, manager, ..} = person;
+                people.push((OrgPerson{person_id, login, fullname, title, department, manager_id: None}, manager));
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(), UserDbError> {
+        match self {
+            Self::Csv(mut writer) => writer.flush()?,
+            Self::Jsonl(mut writer) | Self::Vcard(mut writer) => writer.flush()?,
+            Self::Org(mut writer, people) => {
+                serde_json::to_writer_pretty(&mut writer, &org_chart(people).forest())?;
+                writeln!(writer)?;
+                writer.flush()?
+            }
+        }
+        Ok(())
+    }
+}
+
+// resolve each manager's login to their id
+fn org_chart(people: Vec<(OrgPerson, Option<String>)>) -> OrgChart {
+    let ids = people.iter()
+        .map(|(person, _)| (person.login.clone(), person.person_id))
+        .collect::<std::collections::HashMap<_, _>>();
+    OrgChart::new(people.into_iter().map(|(mut person, manager)| {
+        person.manager_id = manager.and_then(|login| ids.get(&login).copied());
+        person
+    }).collect())
+}
+
+const CSV_HEADER: [&str; 10] = ["first", "last", "login", "department", "title", "number", "category", "location", "label", "primary"];
+
+// csv only fails to write if the writer does
+fn csv_error(error: csv::Error) -> UserDbError {
+    UserDbError::Io(error.into())
+}
+
+// one record per phone, or a single record with empty phone columns
+// for people without one
+fn csv_records(person: &PersonView) -> Vec<[String; 10]> {
+    let person_fields = |phone_fields: [String; 5]| {
+        let [number, category, location, label, primary] = phone_fields;
+        [
+            person.first.clone(),
+            person.last.clone(),
+            person.login.clone(),
+            person.department.clone(),
+            person.title.clone(),
+            number,
+            category,
+            location,
+            label,
+            primary,
+        ]
+    };
+    match person.phones.as_deref() {
+        Some(phones) if !phones.is_empty() => phones.iter()
+            .map(|phone| person_fields([
+                phone.number.inner(),
+                phone.category.to_static_str().to_string(),
+                phone.location.clone(),
+                phone.label.clone().unwrap_or_default(),
+                phone.primary.to_string(),
+            ]))
+            .collect(),
+        _ => vec![person_fields(Default::default())],
+    }
+}
+
+// the vCard TEL type for each category
+fn tel_type(category: &PhoneCategory) -> &'static str {
+    match category {
+        PhoneCategory::Home => "HOME,VOICE",
+        PhoneCategory::Cell => "CELL,VOICE",
+        PhoneCategory::Extension | PhoneCategory::Desk => "WORK,VOICE",
+        PhoneCategory::Pager => "PAGER",
+        PhoneCategory::Fax => "WORK,FAX",
+    }
+}
+
+// escape a vCard text value
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\")
+         .replace(',', "\\,")
+         .replace(';', "\\;")
+         .replace('\n', "\\n")
+}
+
+// fold a content line so no line is longer than 75 octets, continuing
+// each on the next after a CRLF and a space, per RFC 6350. Lines are only
+// broken between characters, so a multi-byte one is never split
+fn fold(line: &str) -> String {
+    const MAX: usize = 75;
+    let mut folded = String::with_capacity(line.len());
+    // the octets on the current line, counting the space continuing it
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > MAX {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+    folded
+}
+
+// render a person as a vCard 3.0 card. Lines end in CRLF per RFC 2426,
+// and are folded once longer than 75 octets
+fn vcard(person: &PersonView) -> String {
+    let mut lines = vec![
+        "BEGIN:VCARD".to_string(),
+        "VERSION:3.0".to_string(),
+        format!("N:{};{};;;", escape(&person.last), escape(&person.first)),
+        format!("FN:{}", escape(&person.fullname)),
+        format!("NICKNAME:{}", escape(&person.login)),
+        format!("ORG:{}", escape(&person.department)),
+        format!("TITLE:{}", escape(&person.title)),
+    ];
+    for phone in person.phones.iter().flatten() {
+        let pref = if phone.primary { ",PREF" } else { "" };
+        // international numbers keep their country code, whoever reads the card
+        let number = match &phone.number {
+            PhoneNumber::International(number) => number.clone(),
+            number => number.to_string(),
+        };
+        lines.push(format!("TEL;TYPE={}{}:{}", tel_type(&phone.category), pref, number));
+    }
+    for contact in person.contacts.iter().flatten() {
+        let pref = if contact.primary { ",PREF" } else { "" };
+        match contact.category {
+            ContactCategory::Email => lines.push(format!("EMAIL;TYPE=INTERNET,WORK{}:{}", pref, escape(&contact.value))),
+            ContactCategory::PersonalEmail => lines.push(format!("EMAIL;TYPE=INTERNET,HOME{}:{}", pref, escape(&contact.value))),
+            ContactCategory::Chat => (),
+        }
+    }
+    lines.push("END:VCARD".to_string());
+    let mut card = lines.iter().map(|line| fold(line)).collect::<Vec<_>>().join("\r\n");
+    card.push_str("\r\n");
+    card
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::UserDbErrorKind;
+
+    fn person() -> PersonView {
+        serde_json::from_value(serde_json::json!({
+            "person_id": 1,
+            "first": "Jane",
+            "last": "Doe",
+            "fullname": "Jane Doe",
+            "login": "jdoe",
+            "department": "Supervisors",
+            "title": "Supervisor, Pipeline",
+            "phones": [
+                {"phone_id": 1, "number": "1234", "category": "Extension", "location": "Portland"},
+                {"phone_id": 2, "number": "5035551212", "category": "Cell", "location": "Portland", "label": "work cell", "is_primary": true},
+                {"phone_id": 3, "number": "+914012345678", "category": "Desk", "location": "Hyderabad"}
+            ],
+            "contacts": [
+                {"contact_id": 1, "category": "Email", "value": "jdoe@example.com", "is_primary": true},
+                {"contact_id": 2, "category": "Chat", "value": "@jdoe", "label": "slack"},
+                {"contact_id": 3, "category": "PersonalEmail", "value": "jane@example.org"}
+            ]
+        })).unwrap()
+    }
+
+    #[test]
+    fn from_str_given_valid_strs_succeeds() {
+        assert_eq!(ExportFormat::from_str("CSV").unwrap(), ExportFormat::Csv);
+        assert_eq!(ExportFormat::from_str("jsonl").unwrap(), ExportFormat::Jsonl);
+        assert_eq!(ExportFormat::from_str("vcf").unwrap(), ExportFormat::Vcard);
+        assert_eq!(ExportFormat::from_str("org").unwrap(), ExportFormat::Org);
+    }
+
+    #[test]
+    fn from_str_given_bad_input_fails() {
+        let result = ExportFormat::from_str("xml").unwrap_err().kind();
+        assert_eq!(result, UserDbErrorKind::InvalidFormat);
+    }
+
+    #[test]
+    fn org_chart_resolves_managers_by_login() {
+        let person = |person_id, login: &str| OrgPerson {
+            person_id,
+            login: login.into(),
+            fullname: "Jane Doe".into(),
+            title: "Supervisor".into(),
+            department: "Supervisors".into(),
+            manager_id: None,
+        };
+        let chart = org_chart(vec![
+            (person(1, "jdoe"), Some("sslade".into())),
+            (person(2, "sslade"), None),
+            (person(3, "rkennedy"), Some("nobody".into())),
+        ]);
+        let forest = chart.forest();
+        assert_eq!(forest.iter().map(|n| n.login.as_str()).collect::<Vec<_>>(), vec!["rkennedy", "sslade"]);
+        assert_eq!(forest[1].reports[0].login, "jdoe");
+    }
+
+    #[test]
+    fn csv_records_writes_a_record_per_phone() {
+        let records = csv_records(&person());
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[1][5..], ["5035551212", "Cell", "Portland", "work cell", "true"]);
+    }
+
+    #[test]
+    fn csv_records_given_no_phones_writes_one_record() {
+        let mut person = person();
+        person.phones = None;
+        let records = csv_records(&person);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0][5..], ["", "", "", "", ""]);
+    }
+
+    #[test]
+    fn vcard_maps_categories_and_escapes_text() {
+        let card = vcard(&person());
+        assert!(card.starts_with("BEGIN:VCARD\r\nVERSION:3.0\r\nN:Doe;Jane;;;\r\n"));
+        assert!(card.contains("\r\nTITLE:Supervisor\\, Pipeline\r\n"));
+        assert!(card.contains("\r\nTEL;TYPE=WORK,VOICE:1234\r\n"));
+        assert!(card.contains("\r\nTEL;TYPE=CELL,VOICE,PREF:503-555-1212\r\n"));
+        assert!(card.contains("\r\nTEL;TYPE=WORK,VOICE:+914012345678\r\n"));
+        assert!(card.contains("\r\nEMAIL;TYPE=INTERNET,WORK,PREF:jdoe@example.com\r\n"));
+        assert!(card.contains("\r\nEMAIL;TYPE=INTERNET,HOME:jane@example.org\r\n"));
+        assert!(!card.contains("@jdoe"));
+        assert!(card.ends_with("END:VCARD\r\n"));
+    }
+
+    #[test]
+    fn vcard_folds_long_lines() {
+        let mut person = person();
+        person.title = "Supervisor of Lighting, Compositing and Final Delivery for Feature Animation".into();
+        person.department = "Ünïcödé ".repeat(12);
+        let card = vcard(&person);
+        for line in card.split("\r\n") {
+            assert!(line.len() <= 75, "{:?} is {} octets", line, line.len());
+        }
+        // unfolding restores the original lines
+        let unfolded = card.replace("\r\n ", "");
+        assert!(unfolded.contains("\r\nTITLE:Supervisor of Lighting\\, Compositing and Final Delivery for Feature Animation\r\n"));
+        assert!(unfolded.contains(&format!("\r\nORG:{}\r\n", person.department)));
+        assert!(card.contains("\r\n "));
+    }
+}
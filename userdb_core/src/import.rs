@@ -0,0 +1,464 @@
+//! Bulk import of people and their phones.
+//!
+//! The input is a csv file whose header row names the columns `first`,
+//! `last`, `login`, `department`, `title`, `number`, `category` and
+//! `location`, and optionally `label` and `primary`. Header names are case
+//! insensitive. The phone columns may be left empty for people without a
+//! phone, and a person with several phones may appear on several rows.
+//!
+//! ```csv
+//! first,last,login,department,title,number,category,location,label,primary
+//! Jane,Doe,jdoe,Supervisors,Animator,1234,ext,portland,,
+//! Jane,Doe,jdoe,Supervisors,Animator,503 555 1212,cell,portland,work cell,true
+//! John,Smith,jsmith,Features,Compositor,,,,,
+//! ```
+//!
+//! Locations may be given by name, code or alias, and are looked up in
+//! the `Locations` passed to `parse`.
+//!
+//! Importing happens in two steps. `parse` validates every row, and
+//! `import` upserts the validated rows in a single transaction. Either step
+//! reports all of the rows which failed rather than stopping at the first.
+use serde::Deserialize;
+use sqlx::postgres::PgConnection;
+use sqlx::prelude::*;
+use sqlx::{Acquire, Postgres};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::prelude::*;
+use crate::{ImportError, Location, Locations, NumberString, PhoneCategory, UserDbError};
+
+const UPSERT_PERSON: &str = r"
+INSERT INTO person
+    (first, last, login, department_id, title_id)
+VALUES
+    ($1, $2, $3,
+        (SELECT id FROM department WHERE name = $4),
+        (SELECT id FROM title WHERE name = $5))
+ON CONFLICT (login) DO UPDATE SET
+    first = EXCLUDED.first,
+    last = EXCLUDED.last,
+    department_id = EXCLUDED.department_id,
+    title_id = EXCLUDED.title_id
+RETURNING id, (xmax = 0) AS inserted;
+";
+
+// an empty label or primary column leaves the existing value alone
+const UPSERT_PHONE: &str = r"
+WITH cte_phone AS (
+    INSERT INTO phone
+        (number, category, location, label)
+    VALUES
+        ($2, $3::phonecategory, $4, $5)
+    ON CONFLICT (number, category, location) DO UPDATE SET
+        label = COALESCE(EXCLUDED.label, phone.label)
+    RETURNING id
+)
+INSERT INTO people_phones
+    (person_id, phone_id, is_primary)
+SELECT
+    $1, id, COALESCE($6, FALSE)
+FROM
+    cte_phone
+ON CONFLICT (person_id, phone_id) DO UPDATE SET
+    is_primary = COALESCE($6, people_phones.is_primary)
+RETURNING (xmax = 0) AS added;
+";
+
+/// A validated phone from a row of the input
+#[derive(Debug, PartialEq, Eq)]
+pub struct ImportPhone {
+    pub number: NumberString,
+    pub category: PhoneCategory,
+    pub location: Location,
+    pub label: Option<String>,
+    /// None leaves an existing phone's flag unchanged
+    pub primary: Option<bool>,
+}
+
+/// A validated row of the input
+#[derive(Debug, PartialEq, Eq)]
+pub struct ImportRow {
+    /// The line of the input the row was read from
+    pub line: u64,
+    pub first: String,
+    pub last: String,
+    pub login: String,
+    pub department: String,
+    pub title: String,
+    pub phone: Option<ImportPhone>,
+}
+
+/// Tally of the changes made by an import
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct ImportSummary {
+    pub people_created: usize,
+    pub people_updated: usize,
+    pub phones_added: usize,
+}
+
+// a row as it appears in the csv, prior to validation
+#[derive(Debug, Deserialize)]
+struct Record {
+    first: Option<String>,
+    last: Option<String>,
+    login: Option<String>,
+    department: Option<String>,
+    title: Option<String>,
+    #[serde(alias = "phone")]
+    number: Option<String>,
+    category: Option<String>,
+    location: Option<String>,
+    label: Option<String>,
+    primary: Option<String>,
+}
+
+/// Read and validate the csv file at `path`.
+pub fn parse_file<P: AsRef<Path>>(path: P, locations: &Locations) -> Result<Vec<ImportRow>, Vec<ImportError>> {
+    let path = path.as_ref();
+    let file = File::open(path)
+        .map_err(|e| vec![ImportError::Io(path.display().to_string(), e)])?;
+    parse(file, locations)
+}
+
+/// Read and validate csv from `reader`, returning the rows if every one
+/// of them is valid, and an error per problem otherwise.
+pub fn parse<R: io::Read>(reader: R, locations: &Locations) -> Result<Vec<ImportRow>, Vec<ImportError>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(reader);
+    let headers = reader.headers()
+        .map_err(|e| vec![malformed(&e)])?
+        .iter()
+        .map(|header| header.to_lowercase())
+        .collect::<csv::StringRecord>();
+
+    let mut rows = Vec::new();
+    let mut errors = Vec::new();
+    for result in reader.records() {
+        let record = match result {
+            Ok(record) => record,
+            Err(e) => {
+                errors.push(malformed(&e));
+                continue;
+            }
+        };
+        let line = record.position().map(|p| p.line()).unwrap_or(0);
+        match record.deserialize::<Record>(Some(&headers)) {
+            Ok(record) => match validate(line, record, locations) {
+                Ok(row) => rows.push(row),
+                Err(mut errs) => errors.append(&mut errs),
+            },
+            Err(e) => errors.push(malformed(&e)),
+        }
+    }
+    if errors.is_empty() {
+        Ok(rows)
+    } else {
+        Err(errors)
+    }
+}
+
+// report a csv error against the line it occurred on
+fn malformed(error: &csv::Error) -> ImportError {
+    let line = error.position().map(|p| p.line()).unwrap_or(1);
+    let message = match error.kind() {
+        csv::ErrorKind::Deserialize { err, .. } => err.to_string(),
+        csv::ErrorKind::UnequalLengths { expected_len, len, .. } => {
+            format!("expected {} fields but found {}", expected_len, len)
+        }
+        _ => error.to_string(),
+    };
+    ImportError::Malformed(line, message)
+}
+
+// convert a record into a row, collecting every problem with it
+fn validate(line: u64, record: Record, locations: &Locations) -> Result<ImportRow, Vec<ImportError>> {
+    let mut errors = Vec::new();
+    let mut required = |value: Option<String>, name: &'static str| {
+        value.unwrap_or_else(|| {
+            errors.push(ImportError::MissingField(line, name));
+            String::new()
+        })
+    };
+    let first = required(record.first, "first");
+    let last = required(record.last, "last");
+    let login = required(record.login, "login");
+    let department = required(record.department, "department");
+    let title = required(record.title, "title");
+
+    let Record{number, category, location, label, primary, ..} = record;
+    let phone = match (number, category, location) {
+        (None, None, None) if label.is_none() && primary.is_none() => None,
+        (number, category, location) => {
+            let number = required(number, "number");
+            let category = required(category, "category");
+            let location = required(location, "location");
+            let number = parse_field(line, &number, NumberString::from_str, &mut errors);
+            let category = parse_field(line, &category, PhoneCategory::from_str, &mut errors);
+            let location = parse_field(line, &location, |v| locations.parse(v).cloned(), &mut errors);
+            // numbers are stored in E.164, read in the country of their
+            // location, unless they are extensions
+            let number = match (number, &category, &location) {
+                (Some(number), Some(category), Some(location)) => number.normalize(category, location.country().as_ref())
+                    .map_err(|e| errors.push(ImportError::Invalid(line, e)))
+                    .ok(),
+                _ => None,
+            };
+            let primary = match primary.as_deref().map(parse_flag) {
+                Some(Some(flag)) => Some(flag),
+                Some(None) => {
+                    errors.push(ImportError::InvalidFlag(line, "primary", primary.unwrap_or_default()));
+                    None
+                }
+                None => None,
+            };
+            match (number, category, location) {
+                (Some(number), Some(category), Some(location)) => Some(ImportPhone{number, category, location, label, primary}),
+                _ => None,
+            }
+        }
+    };
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    Ok(ImportRow{line, first, last, login, department, title, phone})
+}
+
+// parse a phone field, skipping those already reported as missing
+fn parse_field<T>(
+    line: u64,
+    value: &str,
+    parser: impl Fn(&str) -> Result<T, crate::PhoneError>,
+    errors: &mut Vec<ImportError>,
+) -> Option<T> {
+    if value.is_empty() {
+        return None;
+    }
+    parser(value)
+        .map_err(|e| errors.push(ImportError::Invalid(line, e)))
+        .ok()
+}
+
+// parse a yes/no column
+fn parse_flag(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "true" | "t" | "yes" | "y" | "1" => Some(true),
+        "false" | "f" | "no" | "n" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// Upsert `rows` into the database in a single transaction. People are
+/// matched by login and updated in place; phones are added to, but never
+/// removed from, a person. If any row is rejected by the database, nothing
+/// is imported. When `dry_run` is set, the transaction is always rolled
+/// back, so the rows are checked against the database without changing it.
+///
+/// As each row is imported within a savepoint, `conn` must be able to
+/// begin a transaction, so it may be a `UserDb`, a connection, or an open
+/// `Transaction`, within which the import is itself a savepoint.
+pub async fn import<'c, A>(conn: A, rows: &[ImportRow], dry_run: bool) -> Result<ImportSummary, Vec<ImportError>>
+where
+    A: Acquire<'c, Database = Postgres>,
+{
+    let mut tx = conn.begin().await.map_err(fatal)?;
+    let mut summary = ImportSummary::default();
+    // a person spanning several rows is only counted once
+    let mut seen = HashSet::new();
+    let mut errors = Vec::new();
+    for row in rows {
+        // a savepoint per row lets us carry on and report later rows
+        // after postgres has rejected one
+        let mut savepoint = Acquire::begin(&mut tx).await.map_err(fatal)?;
+        match upsert(&mut savepoint, row).await {
+            Ok((inserted, phone_added)) => {
+                savepoint.commit().await.map_err(fatal)?;
+                if inserted {
+                    summary.people_created += 1;
+                } else if !seen.contains(row.login.as_str()) {
+                    summary.people_updated += 1;
+                }
+                seen.insert(row.login.as_str());
+                if phone_added {
+                    summary.phones_added += 1;
+                }
+            }
+            Err(e) => {
+                savepoint.rollback().await.map_err(fatal)?;
+                errors.push(ImportError::Rejected(row.line, e));
+            }
+        }
+    }
+    if !errors.is_empty() {
+        tx.rollback().await.map_err(fatal)?;
+        return Err(errors);
+    }
+    if dry_run {
+        tx.rollback().await.map_err(fatal)?;
+    } else {
+        tx.commit().await.map_err(fatal)?;
+    }
+    Ok(summary)
+}
+
+fn fatal<E: Into<UserDbError>>(error: E) -> Vec<ImportError> {
+    vec![ImportError::UserDb(error.into())]
+}
+
+#[derive(FromRow)]
+struct PhoneRval {
+    added: bool,
+}
+
+#[derive(FromRow)]
+struct PersonRval {
+    id: i32,
+    inserted: bool,
+}
+
+// upsert a single row, returning whether the person was created and
+// whether a phone was added to them
+async fn upsert(conn: &mut PgConnection, row: &ImportRow) -> Result<(bool, bool), UserDbError> {
+    let PersonRval{id, inserted} = sqlx::query_as(UPSERT_PERSON)
+    .bind(&row.first)
+    .bind(&row.last)
+    .bind(&row.login)
+    .bind(&row.department)
+    .bind(&row.title)
+    .fetch_one(&mut *conn).await
+    // postgres can only tell us which id was missing
+    .map_err(|e| match UserDbError::from(e) {
+        UserDbError::UnknownDepartment(_) => UserDbError::UnknownDepartment(row.department.clone()),
+        UserDbError::UnknownTitle(_) => UserDbError::UnknownTitle(row.title.clone()),
+        e => e,
+    })?;
+
+    let phone_added = match &row.phone {
+        Some(phone) => {
+            let PhoneRval{added} = sqlx::query_as(UPSERT_PHONE)
+            .bind(id)
+            .bind(phone.number.to_string())
+            .bind(phone.category.to_static_str())
+            .bind(&phone.location.name)
+            .bind(&phone.label)
+            .bind(phone.primary)
+            .fetch_one(&mut *conn).await?;
+            added
+        }
+        None => false,
+    };
+    Ok((inserted, phone_added))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ImportErrorKind;
+
+    const HEADER: &str = "first,last,login,department,title,number,category,location\n";
+
+    fn location(id: i32, name: &str, code: &str) -> Location {
+        Location {
+            id,
+            name: name.into(),
+            code: code.into(),
+            aliases: Vec::new(),
+            address: None,
+            timezone: "UTC".into(),
+            country: "US".into(),
+            calling_code: Some("1".into()),
+            trunk_prefix: Some("1".into()),
+        }
+    }
+
+    fn locations() -> Locations {
+        Locations::new(vec![location(1, "Portland", "pd"), location(2, "PlayaVista", "pv")])
+    }
+
+    fn kinds(errors: &[ImportError]) -> Vec<(u64, ImportErrorKind)> {
+        errors.iter().map(|e| {
+            let line = match e {
+                ImportError::Malformed(line, _)
+                | ImportError::MissingField(line, _)
+                | ImportError::Invalid(line, _)
+                | ImportError::InvalidFlag(line, _, _)
+                | ImportError::Rejected(line, _) => *line,
+                _ => 0,
+            };
+            (line, e.kind())
+        }).collect()
+    }
+
+    #[test]
+    fn parse_given_valid_rows_succeeds() {
+        let input = format!("{}{}{}",
+            HEADER,
+            "Jane,Doe,jdoe,Supervisors,Animator,503 555 1212,cell,pv\n",
+            "John,Smith,jsmith,Features,Compositor,,,\n");
+        let rows = parse(input.as_bytes(), &locations()).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].line, 2);
+        assert_eq!(rows[0].phone, Some(ImportPhone{
+            number: NumberString::new("+15035551212").unwrap(),
+            category: PhoneCategory::Cell,
+            location: location(2, "PlayaVista", "pv"),
+            label: None,
+            primary: None,
+        }));
+        assert_eq!(rows[1].line, 3);
+        assert_eq!(rows[1].phone, None);
+    }
+
+    #[test]
+    fn parse_given_mixed_case_headers_succeeds() {
+        let input = "First,Last,Login,Department,Title,Phone,Category,Location\nJane,Doe,jdoe,Supervisors,Animator,1234,ext,portland\n";
+        let rows = parse(input.as_bytes(), &locations()).unwrap();
+        assert_eq!(rows[0].login, "jdoe");
+        assert!(rows[0].phone.is_some());
+    }
+
+    #[test]
+    fn parse_given_invalid_rows_reports_each_line() {
+        let input = format!("{}{}{}{}",
+            HEADER,
+            "Jane,Doe,jdoe,Supervisors,Animator,1234,radio,portland\n",
+            "John,Smith,jsmith,Features,Compositor,1234,ext,\n",
+            "Sam,Slade,,Features,Compositor,abc,ext,tokyo\n");
+        let errors = parse(input.as_bytes(), &locations()).unwrap_err();
+        assert_eq!(kinds(&errors), vec![
+            (2, ImportErrorKind::Invalid),
+            (3, ImportErrorKind::MissingField),
+            (4, ImportErrorKind::MissingField),
+            (4, ImportErrorKind::Invalid),
+            (4, ImportErrorKind::Invalid),
+        ]);
+    }
+
+    #[test]
+    fn parse_given_label_and_primary_succeeds() {
+        let input = "first,last,login,department,title,number,category,location,label,primary\nJane,Doe,jdoe,Supervisors,Animator,5035551212,cell,pd,work cell,yes\n";
+        let rows = parse(input.as_bytes(), &locations()).unwrap();
+        let phone = rows[0].phone.as_ref().unwrap();
+        assert_eq!(phone.label.as_deref(), Some("work cell"));
+        assert_eq!(phone.primary, Some(true));
+    }
+
+    #[test]
+    fn parse_given_bad_primary_fails() {
+        let input = "first,last,login,department,title,number,category,location,label,primary\nJane,Doe,jdoe,Supervisors,Animator,5035551212,cell,pd,,maybe\n";
+        let errors = parse(input.as_bytes(), &locations()).unwrap_err();
+        assert_eq!(kinds(&errors), vec![(2, ImportErrorKind::InvalidFlag)]);
+    }
+
+    #[test]
+    fn parse_given_short_row_fails() {
+        let input = format!("{}{}", HEADER, "Jane,Doe,jdoe\n");
+        let errors = parse(input.as_bytes(), &locations()).unwrap_err();
+        assert_eq!(kinds(&errors), vec![(2, ImportErrorKind::Malformed)]);
+    }
+}
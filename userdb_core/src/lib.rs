@@ -4,37 +4,65 @@ pub mod read;
 pub mod update;
 pub mod delete;
 
+pub mod export;
+pub mod import;
+pub mod snapshot;
+
 mod utils;
+mod audit;
+mod config;
 mod constants;
+mod contact;
+mod contact_category;
+mod country;
+mod date;
+mod desk;
+mod dial_plan;
 mod errors;
 mod location;
 mod mode;
+mod org;
 mod person;
 mod person_select;
+mod person_status;
 mod person_view;
 mod phone;
 mod phone_category;
 mod phone_number;
 mod query_mode;
+mod search;
 mod phone_row;
 mod traits;
 mod number_string;
+mod user_db;
 
+pub use audit::{AuditAction, AuditEntity, AuditEntry};
+pub use config::{Config, Profile};
 pub use constants::*;
-pub use errors::{PhoneError, PhoneErrorKind};
-pub use location::Location;
+pub use contact::Contact;
+pub use contact_category::ContactCategory;
+pub use country::Country;
+pub use date::Date;
+pub use desk::{Desk, DeskOwner};
+pub use dial_plan::{DialPlan, ExtensionRange};
+pub use errors::{ConfigError, ConfigErrorKind, ImportError, ImportErrorKind, PhoneError, PhoneErrorKind, UserDbError, UserDbErrorKind};
+pub use location::{Location, Locations};
 pub use mode::Mode;
+pub use org::{OrgChart, OrgLine, OrgNode, OrgPerson, OrgView};
 pub use person::Person;
 pub use person_select::PersonSelect;
+pub use person_status::PersonStatus;
 pub use person_view::PersonView;
 pub use phone::Phone;
 pub use phone_category::PhoneCategory;
 pub use phone_number::PhoneNumber;
 pub use number_string::NumberString;
 pub use query_mode::QueryMode;
+pub use search::{Search, SearchTerm};
 pub use phone_row::PhoneRow;
 pub use traits::{Queryable, Selectable, Updateable, ToStaticStr };
 pub use utils::{JsonAdapter, where_joiner};
+pub use user_db::{UserDb, UserDbOptions};
 
 pub mod prelude {
     pub use super::{Queryable, Selectable, Updateable, ToStaticStr};
@@ -1,58 +1,156 @@
-use std::str::FromStr;
-use crate::traits::ToStaticStr;
-use crate::PhoneError;
+use sqlx::{Executor, Postgres};
 use std::fmt;
+use std::str::FromStr;
 use serde::{Serialize, Deserialize};
+use crate::Country;
+use crate::PhoneError;
+use crate::UserDbError;
 
-/// An enumerated list of DD locations, matching the enum in the database.
-#[derive(Debug,PartialEq, Eq, Serialize, Deserialize, Clone, sqlx::Type, Hash)]
-#[sqlx(rename="location")] //, rename_all = "lowercase")]
-pub enum Location {
-    Portland,
-    PlayaVista,
-    Vancouver,
-    Montreal,
-    Hyderabad
+/// A location (ie studio), as stored in the `location` table. Locations
+/// are loaded from the database at runtime; use `Locations` to look one
+/// up by its name, code or one of its aliases.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone)]
+pub struct Location {
+    pub id: i32,
+    /// The canonical name of the location (eg PlayaVista)
+    pub name: String,
+    /// A short code for the location (eg pv)
+    pub code: String,
+    /// Other names the location is known by (eg venice)
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    pub address: Option<String>,
+    /// The timezone of the location, as named in the tz database
+    pub timezone: String,
+    /// The two letter ISO code of the location's country
+    pub country: String,
+    /// The international calling code of the country, without the +
+    #[serde(default)]
+    pub calling_code: Option<String>,
+    /// The prefix dialed before national numbers within the country
+    #[serde(default)]
+    pub trunk_prefix: Option<String>,
 }
 
-
-impl FromStr for Location {
-    type Err = PhoneError;
-    /// Convert from a str to a Location. The input is 
-    /// case insensitive, and accepts both the name and 
-    /// two letter designation for the location. 
-    ///
-    /// In the case of Playa, we accept a number of different
-    /// aliases, including with a variety of separators between
-    /// words `playa` and `vista`, including a dash, a space, and
-    /// nothing. We also accept the legacy `venice` disgnation.
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_lowercase().as_str() {
-            "portland" | "pd"  => Ok(Self::Portland),
-            "playa" | "playavista" | "playa vista" | "playa-vista"| "venice" | "pv"=> Ok(Self::PlayaVista),
-            "vancouver" | "bc"  => Ok(Self::Vancouver),
-            "montreal" | "mt" => Ok(Self::Montreal),
-            "hyderabad" | "hb" => Ok(Self::Hyderabad),
-            _ => Err(Self::Err::InvalidLocation(s.to_string()))
+impl Location {
+    /// The country of the location, dialed as the location records, or
+    /// as we know the country to be if it records no calling code. None
+    /// if we know neither. Used as the default country for numbers
+    /// entered without a country code.
+    pub fn country(&self) -> Option<Country> {
+        match &self.calling_code {
+            Some(calling_code) => Some(Country::new(
+                &self.country,
+                calling_code,
+                self.trunk_prefix.as_deref().unwrap_or_default())),
+            None => Country::from_str(&self.country).ok(),
         }
     }
-}
 
-impl ToStaticStr for Location {
-    fn to_static_str(&self) -> &'static str {
-        match self {
-            Self::Portland => "Portland",
-            Self::PlayaVista => "PlayaVista",
-            Self::Vancouver => "Vancouver",
-            Self::Montreal => "Montreal",
-            Self::Hyderabad => "Hyderabad"
-        }
-        
+    /// Is the location known by `value`, as its name, code or an alias.
+    /// The comparison is case insensitive.
+    pub fn is_known_as(&self, value: &str) -> bool {
+        let value = value.to_lowercase();
+        self.name.to_lowercase() == value ||
+        self.code.to_lowercase() == value ||
+        self.aliases.iter().any(|alias| alias.to_lowercase() == value)
     }
 }
 
 impl fmt::Display for Location {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.to_static_str())
+        write!(f, "{}", self.name)
+    }
+}
+
+/// The locations in the database, loaded at runtime.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Locations {
+    inner: Vec<Location>,
+}
+
+impl Locations {
+    /// Construct from a list of locations
+    pub fn new(locations: Vec<Location>) -> Self {
+        Self { inner: locations }
+    }
+
+    /// Load every location from the database
+    pub async fn load<'c, E>(executor: E) -> Result<Self, UserDbError>
+    where
+        E: Executor<'c, Database = Postgres>,
+    {
+        Ok(Self::new(crate::read::location::query(executor).await?))
+    }
+
+    /// Look up the location known by `value`. The input is case
+    /// insensitive, and may be the name, the code or one of the
+    /// aliases of the location. Names take precedence over codes,
+    /// and codes over aliases.
+    pub fn parse(&self, value: &str) -> Result<&Location, PhoneError> {
+        let value = value.trim();
+        let lower = value.to_lowercase();
+        self.inner.iter().find(|l| l.name.to_lowercase() == lower)
+            .or_else(|| self.inner.iter().find(|l| l.code.to_lowercase() == lower))
+            .or_else(|| self.inner.iter().find(|l| l.is_known_as(value)))
+            .ok_or_else(|| PhoneError::InvalidLocation(value.to_string()))
+    }
+
+    /// Iterate over the locations
+    pub fn iter(&self) -> impl Iterator<Item = &Location> {
+        self.inner.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PhoneErrorKind;
+
+    fn locations() -> Locations {
+        let location = |id, name: &str, code: &str, aliases: &[&str], country: &str| Location {
+            id,
+            name: name.into(),
+            code: code.into(),
+            aliases: aliases.iter().map(|a| a.to_string()).collect(),
+            address: None,
+            timezone: "UTC".into(),
+            country: country.into(),
+            calling_code: None,
+            trunk_prefix: None,
+        };
+        Locations::new(vec![
+            location(1, "Portland", "pd", &[], "US"),
+            location(2, "PlayaVista", "pv", &["playa", "venice"], "US"),
+            location(3, "Hyderabad", "hb", &[], "IN"),
+            location(4, "London", "ld", &[], "GB"),
+            Location {
+                calling_code: Some("44".into()),
+                trunk_prefix: Some("0".into()),
+                ..location(5, "Manchester", "mc", &[], "GB")
+            },
+        ])
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn parse_given_name_code_or_alias_succeeds() {
+        let locations = locations();
+        for value in &["PlayaVista", "pv", "PV", "Venice", " playa "] {
+            assert_eq!(locations.parse(value).unwrap().name, "PlayaVista");
+        }
+    }
+
+    #[test]
+    fn parse_given_unknown_location_fails() {
+        let result = locations().parse("mars").unwrap_err().kind();
+        assert_eq!(result, PhoneErrorKind::InvalidLocation);
+    }
+
+    #[test]
+    fn country_given_known_and_unknown_countries() {
+        let locations = locations();
+        assert_eq!(locations.parse("hb").unwrap().country(), Country::from_str("IN").ok());
+        assert_eq!(locations.parse("ld").unwrap().country(), None);
+        assert_eq!(locations.parse("mc").unwrap().country(), Some(Country::new("GB", "44", "0")));
+    }
+}
@@ -2,9 +2,13 @@
 use serde::{Serialize, Deserialize};
 use std::fmt;
 use std::str::FromStr;
+use crate::country::is_e164_length;
+use crate::Country;
+use crate::PhoneCategory;
 use crate::PhoneError;
 
-/// A wrapper around a string that ensures all of the characters are integers
+/// A wrapper around a string that ensures all of the characters are integers,
+/// aside from an optional leading `+` marking a number with a country code
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone)]
 pub struct NumberString {
     inner: String
@@ -15,16 +19,14 @@ impl FromStr for NumberString {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         // filter out non-numbers
-        let new_s = s.chars().filter(|x| x.is_numeric()).collect::<String>();
+        let digits = s.chars().filter(|x| x.is_numeric()).collect::<String>();
         // if we have nothing left, well, thats an error
-        if new_s.len() == 0 {
+        if digits.is_empty() {
             return Err(Self::Err::InvalidNumber(s.to_string()))
         }
-        // if !s.chars().all(|x| x.is_numeric()) {
-        //     return Err(Self::Err::InvalidNumber(s.to_string()))
-        // }
-
-        Ok(Self{inner:new_s})
+        // a leading + is kept, as it marks an international number
+        let inner = if s.trim_start().starts_with('+') { format!("+{}", digits) } else { digits };
+        Ok(Self{inner})
     }
 }
 
@@ -44,6 +46,39 @@ impl NumberString {
     pub fn from_usize(value: usize) -> Self {
        Self::from_str(&value.to_string()).unwrap()
     }
+
+    /// Is the number international, ie does it start with a `+`
+    pub fn is_international(&self) -> bool {
+        self.inner.starts_with('+')
+    }
+
+    /// Normalize the number as it is stored for a phone of `category`.
+    /// Extensions are internal, and are returned unchanged, while other
+    /// numbers are converted to E.164 as by `to_e164`.
+    pub fn normalize(&self, category: &PhoneCategory, country: Option<&Country>) -> Result<Self, PhoneError> {
+        match category {
+            PhoneCategory::Extension => Ok(self.clone()),
+            _ => self.to_e164(country),
+        }
+    }
+
+    /// Convert the number to E.164. National numbers are read as
+    /// belonging to `country`, while international numbers are validated
+    /// as is.
+    pub fn to_e164(&self, country: Option<&Country>) -> Result<Self, PhoneError> {
+        let invalid = || PhoneError::InvalidNumber(self.inner.clone());
+        if let Some(digits) = self.inner.strip_prefix('+') {
+            let valid = match Country::from_e164(&self.inner) {
+                Some(country) => country.is_valid_e164(&self.inner),
+                None => is_e164_length(digits),
+            };
+            return if valid { Ok(self.clone()) } else { Err(invalid()) };
+        }
+        let country = country.ok_or_else(|| PhoneError::MissingCountry(self.inner.clone()))?;
+        country.to_e164(&self.inner)
+            .map(|inner| Self{inner})
+            .ok_or_else(invalid)
+    }
 }
 
 #[cfg(test)]
@@ -51,6 +86,10 @@ mod tests {
     use super::*;
     use crate::PhoneErrorKind;
 
+    fn known(code: &str) -> Country {
+        Country::from_str(code).unwrap()
+    }
+
     #[test]
     fn from_str_converts_if_presented_solely_with_digits() {
         let results = NumberString::from_str("12345").unwrap();
@@ -69,6 +108,56 @@ mod tests {
         assert_eq!(results.inner.as_str(), "3103881111");
     }
     
+    #[test]
+    fn from_str_keeps_leading_plus() {
+        let results = NumberString::from_str("+91 40 1234 5678").unwrap();
+        assert_eq!(results.inner.as_str(), "+914012345678");
+    }
+
+    #[test]
+    fn to_e164_given_national_number_uses_country() {
+        let tests = &[
+            ("310-386-4321", known("US"), "+13103864321"),
+            ("040 1234 5678", known("IN"), "+914012345678"),
+            ("+1 604 555 1212", known("IN"), "+16045551212"),
+        ];
+        for (number, country, expect) in tests {
+            let results = NumberString::new(number).unwrap().to_e164(Some(country)).unwrap();
+            assert_eq!(results.inner.as_str(), *expect);
+        }
+    }
+
+    #[test]
+    fn to_e164_given_invalid_number_fails() {
+        let tests = &[("+1310386432", Some(known("US"))), ("12345", Some(known("IN"))), ("+4412", None)];
+        for (number, country) in tests {
+            let result = NumberString::new(number).unwrap().to_e164(country.as_ref()).unwrap_err().kind();
+            assert_eq!(result, PhoneErrorKind::InvalidNumber);
+        }
+    }
+
+    #[test]
+    fn normalize_keeps_extensions_whatever_their_length() {
+        let tests = &[
+            ("4321", PhoneCategory::Extension, "4321"),
+            ("54321", PhoneCategory::Extension, "54321"),
+            ("3103864321", PhoneCategory::Extension, "3103864321"),
+            ("310-386-4321", PhoneCategory::Cell, "+13103864321"),
+        ];
+        for (number, category, expect) in tests {
+            let results = NumberString::new(number).unwrap().normalize(category, Some(&known("US"))).unwrap();
+            assert_eq!(results.inner.as_str(), *expect);
+        }
+        let result = NumberString::new("4321").unwrap().normalize(&PhoneCategory::Home, Some(&known("US"))).unwrap_err().kind();
+        assert_eq!(result, PhoneErrorKind::InvalidNumber);
+    }
+
+    #[test]
+    fn to_e164_given_national_number_without_country_fails() {
+        let result = NumberString::new("3103864321").unwrap().to_e164(None).unwrap_err().kind();
+        assert_eq!(result, PhoneErrorKind::MissingCountry);
+    }
+
     #[test]
     fn from_usize_constructs_NumberString() {
         let results = NumberString::from_usize(12345);
@@ -0,0 +1,299 @@
+use sqlx::{Executor, Postgres};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use serde::{Serialize, Deserialize};
+use crate::UserDbError;
+
+/// A person, along with the id of their manager, as needed to
+/// build the org chart
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone)]
+pub struct OrgPerson {
+    pub person_id: i32,
+    pub login: String,
+    pub fullname: String,
+    pub title: String,
+    pub department: String,
+    pub manager_id: Option<i32>,
+}
+
+impl fmt::Display for OrgPerson {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}) - {}", self.fullname, self.login, self.title)
+    }
+}
+
+/// A person and, recursively, everyone who reports to them
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone)]
+pub struct OrgNode {
+    pub login: String,
+    pub fullname: String,
+    pub title: String,
+    pub department: String,
+    #[serde(default)]
+    pub reports: Vec<OrgNode>,
+}
+
+/// The org chart of a single person: their management chain, from the
+/// top down to their direct manager, and the tree of their reports.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone)]
+pub struct OrgView {
+    pub chain: Vec<OrgPerson>,
+    pub tree: OrgNode,
+}
+
+/// A line of the org chart as drawn by `OrgChart::lines`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct OrgLine<'a> {
+    /// The branches drawn ahead of the person
+    pub prefix: String,
+    pub person: &'a OrgPerson,
+    /// Is this the person the chart was drawn for
+    pub subject: bool,
+}
+
+impl<'a> fmt::Display for OrgLine<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.prefix, self.person)
+    }
+}
+
+/// Who reports to whom, for everyone in the database
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct OrgChart {
+    people: Vec<OrgPerson>,
+    // indices into people of each manager's reports, by the manager's id
+    reports: HashMap<i32, Vec<usize>>,
+}
+
+impl OrgChart {
+    /// Construct from a list of people
+    pub fn new(people: Vec<OrgPerson>) -> Self {
+        let mut reports: HashMap<i32, Vec<usize>> = HashMap::new();
+        for (idx, person) in people.iter().enumerate() {
+            if let Some(manager_id) = person.manager_id {
+                reports.entry(manager_id).or_default().push(idx);
+            }
+        }
+        // keep the reports of each manager in a stable order
+        for idxs in reports.values_mut() {
+            idxs.sort_by(|a, b| people[*a].login.cmp(&people[*b].login));
+        }
+        Self { people, reports }
+    }
+
+    /// Load everyone from the database
+    pub async fn load<'c, E>(executor: E) -> Result<Self, UserDbError>
+    where
+        E: Executor<'c, Database = Postgres>,
+    {
+        Ok(Self::new(crate::read::org::query(executor).await?))
+    }
+
+    /// Look up a person by their login
+    pub fn person(&self, login: &str) -> Option<&OrgPerson> {
+        self.people.iter().find(|p| p.login == login)
+    }
+
+    fn by_id(&self, id: i32) -> Option<&OrgPerson> {
+        self.people.iter().find(|p| p.person_id == id)
+    }
+
+    /// The managers of the person with the supplied login, from the top
+    /// of the organization down to their direct manager. Returns None if
+    /// there is no such person.
+    pub fn chain(&self, login: &str) -> Option<Vec<&OrgPerson>> {
+        let person = self.person(login)?;
+        let mut seen = HashSet::new();
+        seen.insert(person.person_id);
+        let mut chain = Vec::new();
+        let mut manager_id = person.manager_id;
+        while let Some(manager) = manager_id.and_then(|id| self.by_id(id)) {
+            // the database prevents cycles, but we guard against them anyway
+            if !seen.insert(manager.person_id) {
+                break;
+            }
+            chain.push(manager);
+            manager_id = manager.manager_id;
+        }
+        chain.reverse();
+        Some(chain)
+    }
+
+    /// The person with the supplied login, along with their direct and
+    /// indirect reports. Returns None if there is no such person.
+    pub fn tree(&self, login: &str) -> Option<OrgNode> {
+        let person = self.person(login)?;
+        Some(self.node(person, &mut HashSet::new()))
+    }
+
+    /// The whole organization, as a tree per person without a manager
+    pub fn forest(&self) -> Vec<OrgNode> {
+        let mut roots = self.people.iter()
+            .filter(|p| p.manager_id.and_then(|id| self.by_id(id)).is_none())
+            .collect::<Vec<_>>();
+        roots.sort_by(|a, b| a.login.cmp(&b.login));
+        let mut seen = HashSet::new();
+        roots.into_iter().map(|p| self.node(p, &mut seen)).collect()
+    }
+
+    /// The chain and tree of the person with the supplied login
+    pub fn view(&self, login: &str) -> Option<OrgView> {
+        let chain = self.chain(login)?.into_iter().cloned().collect();
+        let tree = self.tree(login)?;
+        Some(OrgView { chain, tree })
+    }
+
+    /// The org chart of the person with the supplied login, drawn as a
+    /// single tree: their management chain above them, and their reports
+    /// below them.
+    pub fn lines(&self, login: &str) -> Option<Vec<OrgLine<'_>>> {
+        let person = self.person(login)?;
+        let chain = self.chain(login)?;
+        let mut lines = Vec::new();
+        let mut indent = String::new();
+        for (depth, manager) in chain.iter().enumerate() {
+            lines.push(OrgLine { prefix: branch(&indent, depth == 0, true), person: manager, subject: false });
+            if depth > 0 {
+                indent.push_str("   ");
+            }
+        }
+        lines.push(OrgLine { prefix: branch(&indent, chain.is_empty(), true), person, subject: true });
+        if !chain.is_empty() {
+            indent.push_str("   ");
+        }
+        let mut seen = chain.iter().map(|p| p.person_id).collect::<HashSet<_>>();
+        seen.insert(person.person_id);
+        self.report_lines(person, &indent, &mut seen, &mut lines);
+        Some(lines)
+    }
+
+    fn report_lines<'a>(&'a self, person: &OrgPerson, indent: &str, seen: &mut HashSet<i32>, lines: &mut Vec<OrgLine<'a>>) {
+        let reports = self.reports_of(person, seen);
+        let cnt = reports.len();
+        for (idx, report) in reports.into_iter().enumerate() {
+            let last = idx + 1 == cnt;
+            lines.push(OrgLine { prefix: branch(indent, false, last), person: report, subject: false });
+            let indent = format!("{}{}", indent, if last { "   " } else { "│  " });
+            self.report_lines(report, &indent, seen, lines);
+        }
+    }
+
+    fn node(&self, person: &OrgPerson, seen: &mut HashSet<i32>) -> OrgNode {
+        seen.insert(person.person_id);
+        let reports = self.reports_of(person, seen)
+            .into_iter()
+            .map(|report| self.node(report, seen))
+            .collect();
+        OrgNode {
+            login: person.login.clone(),
+            fullname: person.fullname.clone(),
+            title: person.title.clone(),
+            department: person.department.clone(),
+            reports,
+        }
+    }
+
+    // the reports of the person not yet visited, marking them as visited
+    fn reports_of(&self, person: &OrgPerson, seen: &mut HashSet<i32>) -> Vec<&OrgPerson> {
+        self.reports.get(&person.person_id)
+            .into_iter()
+            .flatten()
+            .map(|idx| &self.people[*idx])
+            .filter(|report| seen.insert(report.person_id))
+            .collect()
+    }
+}
+
+// the branch drawn ahead of a person in the chart
+fn branch(indent: &str, root: bool, last: bool) -> String {
+    if root {
+        String::new()
+    } else {
+        format!("{}{}", indent, if last { "└─ " } else { "├─ " })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chart() -> OrgChart {
+        let person = |person_id, login: &str, manager_id| OrgPerson {
+            person_id,
+            login: login.into(),
+            fullname: login.to_uppercase(),
+            title: "Engineer".into(),
+            department: "Dev".into(),
+            manager_id,
+        };
+        OrgChart::new(vec![
+            person(1, "ceo", None),
+            person(2, "cto", Some(1)),
+            person(3, "lead", Some(2)),
+            person(4, "dev2", Some(3)),
+            person(5, "dev1", Some(3)),
+            person(6, "intern", Some(5)),
+            person(7, "loner", None),
+        ])
+    }
+
+    fn logins(node: &OrgNode) -> Vec<String> {
+        let mut rval = vec![node.login.clone()];
+        for report in &node.reports {
+            rval.extend(logins(report));
+        }
+        rval
+    }
+
+    #[test]
+    fn chain_given_login_starts_at_the_top() {
+        let chart = chart();
+        let chain = chart.chain("dev1").unwrap().iter().map(|p| p.login.as_str()).collect::<Vec<_>>();
+        assert_eq!(chain, vec!["ceo", "cto", "lead"]);
+        assert!(chart.chain("ceo").unwrap().is_empty());
+        assert!(chart.chain("nobody").is_none());
+    }
+
+    #[test]
+    fn tree_given_login_includes_indirect_reports() {
+        let tree = chart().tree("lead").unwrap();
+        assert_eq!(logins(&tree), vec!["lead", "dev1", "intern", "dev2"]);
+    }
+
+    #[test]
+    fn forest_has_a_tree_per_person_without_a_manager() {
+        let forest = chart().forest();
+        let roots = forest.iter().map(|n| n.login.as_str()).collect::<Vec<_>>();
+        assert_eq!(roots, vec!["ceo", "loner"]);
+        assert_eq!(logins(&forest[0]).len(), 6);
+    }
+
+    #[test]
+    fn lines_given_login_draws_chain_and_reports() {
+        let chart = chart();
+        let lines = chart.lines("lead").unwrap()
+            .iter()
+            .map(|l| format!("{}{}", l.prefix, l.person.login))
+            .collect::<Vec<_>>();
+        assert_eq!(lines, vec![
+            "ceo",
+            "└─ cto",
+            "   └─ lead",
+            "      ├─ dev1",
+            "      │  └─ intern",
+            "      └─ dev2",
+        ]);
+        let subject = chart.lines("lead").unwrap().into_iter().position(|l| l.subject);
+        assert_eq!(subject, Some(2));
+    }
+
+    #[test]
+    fn chain_given_cycle_terminates() {
+        let mut people = chart().people;
+        people[0].manager_id = Some(3);
+        let chart = OrgChart::new(people);
+        let chain = chart.chain("lead").unwrap().iter().map(|p| p.login.as_str()).collect::<Vec<_>>();
+        assert_eq!(chain, vec!["ceo", "cto"]);
+        assert_eq!(logins(&chart.tree("lead").unwrap()).len(), 6);
+    }
+}
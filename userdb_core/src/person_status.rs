@@ -0,0 +1,90 @@
+use std::str::FromStr;
+use crate::PhoneError;
+use serde::{Serialize,Deserialize};
+use crate::traits::ToStaticStr;
+use std::fmt;
+
+/// Where a person is in their employment. Everyone but those who have
+/// been `Terminated` is considered active, and is found by `phone`.
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize, Clone, Copy, sqlx::Type)]
+#[sqlx(rename="personstatus")]
+pub enum PersonStatus {
+    #[default]
+    Active,
+    OnLeave,
+    Contractor,
+    Terminated
+}
+
+impl PersonStatus {
+    /// Is the person still with us
+    pub fn is_active(&self) -> bool {
+        !matches!(self, Self::Terminated)
+    }
+}
+
+impl FromStr for PersonStatus {
+    type Err = PhoneError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "active" | "a" => Ok(Self::Active),
+            "onleave" | "on-leave" | "leave" | "l" => Ok(Self::OnLeave),
+            "contractor" | "c" => Ok(Self::Contractor),
+            "terminated" | "t" | "inactive" => Ok(Self::Terminated),
+            _ => Err(Self::Err::InvalidPersonStatus(s.to_string()))
+        }
+    }
+}
+
+impl ToStaticStr for PersonStatus {
+    fn to_static_str(&self) -> &'static str {
+        match self {
+            Self::Active => "Active",
+            Self::OnLeave => "OnLeave",
+            Self::Contractor => "Contractor",
+            Self::Terminated => "Terminated"
+        }
+    }
+}
+
+impl fmt::Display for PersonStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_static_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PhoneErrorKind;
+
+    #[test]
+    fn from_str_given_valid_strs_constructs_status() {
+        let tests = &[
+            (&vec!["active", "A"], PersonStatus::Active),
+            (&vec!["OnLeave", "on-leave", "leave"], PersonStatus::OnLeave),
+            (&vec!["contractor", "c"], PersonStatus::Contractor),
+            (&vec!["terminated", "t", "inactive"], PersonStatus::Terminated),
+        ];
+
+        for test in tests {
+            for item in test.0 {
+                let result = PersonStatus::from_str(item).unwrap();
+                assert_eq!(result, test.1);
+            }
+        }
+    }
+
+    #[test]
+    fn from_str_given_bad_data_constructs_err() {
+        let result = PersonStatus::from_str("retired").unwrap_err().kind();
+        assert_eq!(result, PhoneErrorKind::InvalidPersonStatus);
+    }
+
+    #[test]
+    fn is_active_given_terminated_is_false() {
+        assert!(PersonStatus::OnLeave.is_active());
+        assert!(!PersonStatus::Terminated.is_active());
+    }
+}
@@ -1,3 +1,6 @@
+use crate::Contact;
+use crate::Desk;
+use crate::PersonStatus;
 use crate::Phone;
 use serde::{Serialize, Deserialize};
 
@@ -11,5 +14,26 @@ pub struct PersonView {
     pub login: String,
     pub department: String,
     pub title: String,
-    pub phones: Option<Vec<Phone>>
+    /// The login of the person's manager
+    #[serde(default)]
+    pub manager: Option<String>,
+    #[serde(default)]
+    pub status: PersonStatus,
+    /// The date the person started, as YYYY-MM-DD
+    #[serde(default)]
+    pub start_date: Option<String>,
+    /// The date the person finished, as YYYY-MM-DD
+    #[serde(default)]
+    pub end_date: Option<String>,
+    pub phones: Option<Vec<Phone>>,
+    /// Email addresses, chat handles and the like
+    #[serde(default)]
+    pub contacts: Option<Vec<Contact>>,
+    /// Where the person sits, at each of their locations
+    #[serde(default)]
+    pub desks: Option<Vec<Desk>>,
+    /// How well the person matched a search, when they were found by one.
+    /// Better matches score higher
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f32>,
 }
@@ -4,21 +4,35 @@ use sqlx::FromRow;
 use std::str::FromStr;
 
 // internal crate
-use crate::Location;
 use crate::PhoneCategory;
 use crate::PhoneNumber;
 
 /// Struct which represents a Phone entry in the 
 /// backing database. It is also capable of serializing
 /// and deserializing via serde
-#[derive(Serialize, Deserialize, Debug, FromRow)]
+#[derive(Serialize, Deserialize, Debug, Clone, FromRow)]
 pub struct Phone {
     pub phone_id: i32,
     #[serde(deserialize_with = "deserialize_phonenumber")]
     pub number: PhoneNumber,
     pub category: PhoneCategory,
-    pub location: Location
-} 
+    /// The name of the phone's location
+    pub location: String,
+    /// An optional description, to tell apart phones of the same category
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Whether this is the person's primary phone of its category. This
+    /// is only known when the phone is read along with a person.
+    #[serde(rename = "is_primary", default)]
+    #[sqlx(rename = "is_primary", default)]
+    pub primary: bool,
+    /// The number which dials an extension directly from outside (its
+    /// DID), in E.164, according to the dial plan of its location.
+    /// None for other categories.
+    #[serde(default)]
+    #[sqlx(default)]
+    pub did: Option<String>,
+}
 
 // custom deserializer for PhoneNumber. Because the different variants
 // of PhoneNumber are indistinguishable based on shape (ie they all just wrap String), 
@@ -6,13 +6,17 @@ use crate::traits::ToStaticStr;
 use std::fmt;
 
 /// The category of a phone number - either a `Home` number, a `Cell`
-/// phone number, or an internal `Extension`.
-#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+/// phone number, an internal `Extension`, a `Pager`, a `Fax`, or a `Desk`
+/// phone with its own direct line.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone, sqlx::Type)]
 #[sqlx(rename="phonecategory")]//, rename_all = "lowercase")]
 pub enum PhoneCategory {
     Home,
     Cell,
-    Extension
+    Extension,
+    Pager,
+    Fax,
+    Desk
 }
 
 impl FromStr for PhoneCategory {
@@ -23,6 +27,9 @@ impl FromStr for PhoneCategory {
             "home" | "h" => Ok(Self::Home),
             "cell" | "c" => Ok(Self::Cell),
             "ext" | "extension" | "e" => Ok(Self::Extension),
+            "pager" | "p" => Ok(Self::Pager),
+            "fax" | "f" => Ok(Self::Fax),
+            "desk" | "d" | "direct" | "directline" => Ok(Self::Desk),
             _ => Err(Self::Err::InvalidCategory(s.to_string()))
         }
     }
@@ -33,7 +40,10 @@ impl ToStaticStr for PhoneCategory {
         match self {
             Self::Home => "Home",
             Self::Cell => "Cell",
-            Self::Extension => "Extension"
+            Self::Extension => "Extension",
+            Self::Pager => "Pager",
+            Self::Fax => "Fax",
+            Self::Desk => "Desk"
         }
     }
 }
@@ -55,7 +65,10 @@ mod tests {
         let tests = &[
             (&vec!["home", "h"], PhoneCategory::Home),
             (&vec!["cell", "c"], PhoneCategory::Cell),
-            (&vec!["ext", "extension", "e"], PhoneCategory::Extension)
+            (&vec!["ext", "extension", "e"], PhoneCategory::Extension),
+            (&vec!["pager", "p"], PhoneCategory::Pager),
+            (&vec!["fax", "f"], PhoneCategory::Fax),
+            (&vec!["desk", "d", "direct", "DirectLine"], PhoneCategory::Desk)
         ];
 
         for test in tests {
@@ -77,7 +90,10 @@ mod tests {
         let tests = &[
             (PhoneCategory::Home, "Home"),
             (PhoneCategory::Cell, "Cell"),
-            (PhoneCategory::Extension, "Extension")
+            (PhoneCategory::Extension, "Extension"),
+            (PhoneCategory::Pager, "Pager"),
+            (PhoneCategory::Fax, "Fax"),
+            (PhoneCategory::Desk, "Desk")
         ];
 
         for test in tests {
@@ -91,7 +107,10 @@ mod tests {
         let tests = &[
             (PhoneCategory::Home, "Home"),
             (PhoneCategory::Cell, "Cell"),
-            (PhoneCategory::Extension, "Extension")
+            (PhoneCategory::Extension, "Extension"),
+            (PhoneCategory::Pager, "Pager"),
+            (PhoneCategory::Fax, "Fax"),
+            (PhoneCategory::Desk, "Desk")
         ];
 
         for test in tests {
@@ -1,5 +1,7 @@
 use serde::{Serialize, Deserialize};
 use std::str::FromStr;
+use crate::country::is_e164_length;
+use crate::Country;
 use crate::PhoneError;
 use std::fmt;
 
@@ -10,6 +12,8 @@ pub enum PhoneNumber {
     Extension(String),
     /// An area code prefix'ed number works for the US an Canada
     TenDigit(String),
+    /// A number with a country code, stored in E.164 (eg +914012345678)
+    International(String),
     /// All other forms
     Other(String),
 }
@@ -20,6 +24,7 @@ impl PhoneNumber {
         match self {
             Self::Extension(ext) => ext.clone(),
             Self::TenDigit(tdig) => tdig.clone(),
+            Self::International(intl) => intl.clone(),
             Self::Other(other) => other.clone()
         }
     }
@@ -29,6 +34,12 @@ impl FromStr for PhoneNumber {
     type Err = PhoneError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(digits) = s.strip_prefix('+') {
+            if !digits.chars().all(|x| x.is_numeric()) || !is_e164_length(digits) {
+                return Err(PhoneError::InvalidNumber(s.to_string()))
+            }
+            return Ok(Self::International(s.to_string()))
+        }
         let characters = s.chars().collect::<Vec<_>>();
         if  !characters.iter().all(|x| x.is_numeric()) {
             return Err(PhoneError::InvalidNumber(s.to_string()))
@@ -48,6 +59,10 @@ impl fmt::Display for PhoneNumber {
         match self {
             Self::TenDigit(tdig) => write!(f, "{}-{}-{}", &tdig[0..3], &tdig[3..6], &tdig[6..]),
             Self::Extension(ext) => write!(f, "{}", ext),
+            Self::International(intl) => match Country::from_e164(intl) {
+                Some(country) => write!(f, "{}", country.format(intl)),
+                None => write!(f, "{}", intl),
+            },
             Self::Other(other)   => write!(f, "{}", other)
         }
     }
@@ -63,7 +78,8 @@ mod tests {
         let tests = &[
             ("3103864321", PhoneNumber::TenDigit("3103864321".into())),
             ("4321", PhoneNumber::Extension("4321".into())),
-            ("32345678", PhoneNumber::Other("32345678".into()))
+            ("32345678", PhoneNumber::Other("32345678".into())),
+            ("+914012345678", PhoneNumber::International("+914012345678".into()))
         ];
         for (num, expect) in tests {
             let results = PhoneNumber::from_str(num).unwrap();
@@ -73,7 +89,7 @@ mod tests {
 
     #[test]
     fn from_str_given_bad_input_should_fail() {
-        let inputs = &["afb123", "123a4567a", "123456", "12345", "123", "12", "1"];
+        let inputs = &["+91a4012345", "+1234", "+1234567890123456", "afb123", "123a4567a", "123456", "12345", "123", "12", "1"];
         for input in inputs {
             let result = PhoneNumber::from_str(input).unwrap_err().kind();
             assert_eq!(result, PhoneErrorKind::InvalidNumber)
@@ -85,7 +101,10 @@ mod tests {
         let tests = &[
             ( PhoneNumber::TenDigit( "3103864321".into() ), "310-386-4321".to_string() ),
             ( PhoneNumber::Extension( "4321".into() ),"4321".into() ),
-            ( PhoneNumber::Other( "32345".into() ),"32345".into() )
+            ( PhoneNumber::Other( "32345".into() ),"32345".into() ),
+            ( PhoneNumber::International( "+13103864321".into() ),"310-386-4321".into() ),
+            ( PhoneNumber::International( "+914012345678".into() ),"+91 40 1234 5678".into() ),
+            ( PhoneNumber::International( "+442071234567".into() ),"+442071234567".into() )
         ];
         for (num, expect) in tests {
             let results = num.to_string();
@@ -1,68 +1,193 @@
 use prettytable::{Row, Cell};
+use crate::Phone;
+use crate::PhoneCategory;
 use crate::PhoneNumber;
-use crate::Location;
 use colored::*;
+use std::str::FromStr;
 
 
-/// Represents a presentation row of phone data as a struct 
-/// with fields whose native types are all wrapped in Options.
+/// Represents a presentation row of phone data as a struct
+/// with a list of phones per category, all at a single location.
 /// This struct is used to transform the data returned from
 /// the database into a reasonable line of information in
-/// the terminal
-#[derive(Debug)]
+/// the terminal. A category holding several phones is rendered
+/// one phone per line, with the primary phone marked by a `*`
+/// and labels shown in parentheses.
+#[derive(Debug, Default)]
 pub struct PhoneRow {
-    pub ext: Option<PhoneNumber>,
-    pub home: Option<PhoneNumber>,
-    pub pager: Option<PhoneNumber>,
-    pub cell: Option<PhoneNumber>,
-    pub location: Option<Location>
-}
-
-impl Default for PhoneRow {
-    fn default() -> Self {
-        Self {
-            ext: None,
-            home:None,
-            pager: None,
-            cell: None,
-            location: None
-        }
-    }
+    pub ext: Vec<Phone>,
+    pub home: Vec<Phone>,
+    pub desk: Vec<Phone>,
+    pub pager: Vec<Phone>,
+    pub cell: Vec<Phone>,
+    pub fax: Vec<Phone>,
+    pub location: Option<String>
 }
 
 impl PhoneRow {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Group `phones` into a row per location, in the order in which
+    /// each location is first encountered.
+    pub fn from_phones(phones: &[Phone]) -> Vec<Self> {
+        let mut rows: Vec<Self> = Vec::new();
+        for phone in phones {
+            let idx = match rows.iter().position(|row| row.location.as_ref() == Some(&phone.location)) {
+                Some(idx) => idx,
+                None => {
+                    let mut row = Self::new();
+                    row.location = Some(phone.location.clone());
+                    rows.push(row);
+                    rows.len() - 1
+                }
+            };
+            rows[idx].push(phone.clone());
+        }
+        rows
+    }
+
+    /// Add a phone to the list matching its category
+    pub fn push(&mut self, phone: Phone) {
+        match phone.category {
+            PhoneCategory::Home =>      self.home.push(phone),
+            PhoneCategory::Extension => self.ext.push(phone),
+            PhoneCategory::Cell =>      self.cell.push(phone),
+            PhoneCategory::Pager =>     self.pager.push(phone),
+            PhoneCategory::Fax =>       self.fax.push(phone),
+            PhoneCategory::Desk =>      self.desk.push(phone),
+        }
+    }
+
     /// return the left row.
     pub fn row_left(&self) -> Row {
-        let Self{ext, home,..} = self;
-        let mut row = Vec::new();
-       
-        row.push(format_phone(ext, "Ext: ",4));
-        row.push(format_phone(home, "H:",12));        
+        let Self{ext, home, desk,..} = self;
+        let row = vec![
+            format_phones(ext, "Ext: ",4),
+            format_phones(home, "H:",12),
+            format_phones(desk, "D:",12),
+        ];
         Row::new(row)
     }
-    /// return the right row. 
+    /// return the right row.
     pub fn row_right(&self) -> Row {
-        let Self{pager, cell, location,..} = self;
+        let Self{pager, cell, fax, location,..} = self;
         let mut row = Vec::new();
-        row.push(format_phone(pager, "P:",4));
-        row.push(format_phone(cell, "C:",12));
+        row.push(format_phones(pager, "P:",12));
+        row.push(format_phones(cell, "C:",12));
+        row.push(format_phones(fax, "F:",12));
         match location {
             Some(loc) => row.push(Cell::new(&format!("{} {}","Loc:".bright_cyan(), loc))),
             None => row.push(Cell::new(&format!("{}", "Loc:".bright_cyan())))
         };
         Row::new(row)
     }
+
+    /// The row as plain text, for displays which do not use tables: the
+    /// location, followed by the phones of each category which has any.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!("Loc: {}", self.location.as_deref().unwrap_or(""))];
+        for (label, phones) in &[
+            ("Ext:", &self.ext), ("H:", &self.home), ("D:", &self.desk),
+            ("P:", &self.pager), ("C:", &self.cell), ("F:", &self.fax),
+        ] {
+            for (idx, phone) in phones.iter().enumerate() {
+                let prefix = if idx == 0 { *label } else { "" };
+                lines.push(format!("  {:4} {}", prefix, format_phone(phone)));
+            }
+        }
+        lines
+    }
 }
 
+// the phones of a category, one per line. Only the first line is labeled
+fn format_phones(phones: &[Phone], label: &str, width: u8) -> Cell {
+    if phones.is_empty() {
+        return Cell::new(&format!("{} {:width$}", label.bright_cyan(), " ", width=width as usize));
+    }
+    let indent = " ".repeat(label.len());
+    let lines = phones.iter().enumerate().map(|(idx, phone)| {
+        let prefix = if idx == 0 { label.bright_cyan().to_string() } else { indent.clone() };
+        format!("{} {}", prefix, format_phone(phone))
+    }).collect::<Vec<_>>();
+    Cell::new(&lines.join("\n"))
+}
+
+fn format_phone(phone: &Phone) -> String {
+    let primary = if phone.primary { "*" } else { "" };
+    let did = match phone.did.as_deref().map(PhoneNumber::from_str) {
+        Some(Ok(did)) => format!(" → {}", did),
+        _ => String::new(),
+    };
+    match &phone.label {
+        Some(label) => format!("{}{}{} ({})", phone.number, did, primary, label),
+        None => format!("{}{}{}", phone.number, did, primary),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn phone(phone_id: i32, number: &str, category: PhoneCategory, location: &str) -> Phone {
+        Phone {
+            phone_id,
+            number: PhoneNumber::Other(number.into()),
+            category,
+            location: location.into(),
+            label: None,
+            primary: false,
+            did: None,
+        }
+    }
 
-fn format_phone(num: &Option<PhoneNumber>, label: &str, width: u8) -> Cell {
-    if num.is_some() {
-        let num = num.as_ref().unwrap();
-        Cell::new(&format!("{} {}", label.bright_cyan(), num))
-    } else {
-        Cell::new(&format!("{} {:width$}", label.bright_cyan(), " ", width=width as usize))
+    #[test]
+    fn from_phones_keeps_every_phone_of_a_category() {
+        let phones = vec![
+            phone(1, "5035550001", PhoneCategory::Cell, "Portland"),
+            phone(2, "5035550002", PhoneCategory::Cell, "Portland"),
+            phone(3, "6045550003", PhoneCategory::Cell, "Vancouver"),
+            phone(4, "1234", PhoneCategory::Extension, "Portland"),
+        ];
+        let rows = PhoneRow::from_phones(&phones);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].location.as_deref(), Some("Portland"));
+        assert_eq!(rows[0].cell.iter().map(|p| p.phone_id).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(rows[0].ext.len(), 1);
+        assert_eq!(rows[1].cell.len(), 1);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn lines_lists_location_then_phones_by_category() {
+        let mut ext = phone(1, "1234", PhoneCategory::Extension, "Portland");
+        ext.primary = true;
+        let phones = vec![
+            phone(2, "5035550001", PhoneCategory::Cell, "Portland"),
+            ext,
+            phone(3, "5035550002", PhoneCategory::Cell, "Portland"),
+        ];
+        let rows = PhoneRow::from_phones(&phones);
+        assert_eq!(rows[0].lines(), vec![
+            "Loc: Portland",
+            "  Ext: 1234*",
+            "  C:   5035550001",
+            "       5035550002",
+        ]);
+    }
+
+    #[test]
+    fn format_phone_marks_primary_and_label() {
+        let mut cell = phone(1, "5035550001", PhoneCategory::Cell, "Portland");
+        cell.primary = true;
+        cell.label = Some("work cell".into());
+        assert_eq!(format_phone(&cell), "5035550001* (work cell)");
+    }
+
+    #[test]
+    fn format_phone_shows_extension_did() {
+        let mut ext = phone(1, "6432", PhoneCategory::Extension, "Vancouver");
+        ext.did = Some("+16045556432".into());
+        assert_eq!(format_phone(&ext), "6432 → 604-555-6432");
+    }
+}
@@ -2,4 +2,9 @@ pub mod person;
 pub mod title;
 pub mod department;
 pub mod phone;
-pub mod person_simple;
\ No newline at end of file
+pub mod person_simple;
+pub mod dial_plan;
+pub mod location;
+pub mod contact;
+pub mod org;
+pub mod audit;
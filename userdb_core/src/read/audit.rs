@@ -0,0 +1,185 @@
+//! Read the audit log of changes made to the database
+use std::collections::HashMap;
+use strfmt::strfmt;
+use futures::TryStreamExt;
+use sqlx::FromRow;
+use sqlx::{Executor, Postgres};
+use crate::AuditEntity;
+use crate::AuditEntry;
+use crate::Date;
+use crate::QueryMode;
+use crate::prelude::*;
+use crate::JsonAdapter;
+use crate::utils::from_json;
+use crate::where_joiner;
+use crate::UserDbError;
+
+// the most recent changes come first
+const QUERY: &str = r"
+SELECT
+    row_to_json(r) AS inner
+FROM (
+        SELECT
+            audit.id AS audit_id,
+            to_char(audit.at, 'YYYY-MM-DD HH24:MI:SSOF') AS at,
+            audit.actor, audit.action, audit.entity, audit.entity_id,
+            audit.login, audit.before, audit.after
+        FROM
+            audit
+        {query}
+        ORDER BY
+            audit.at DESC, audit.id DESC
+        {limit}
+    ) AS r;";
+
+/// The parameters for an audit log query. The time range is inclusive
+/// of both the `since` and `until` days.
+#[derive(Debug, PartialEq, Eq, Default)]
+pub struct AuditQuery {
+    pub entity: Option<AuditEntity>,
+    pub entity_id: Option<i32>,
+    pub login: Option<String>,
+    pub actor: Option<String>,
+    pub since: Option<Date>,
+    pub until: Option<Date>,
+    pub limit: Option<u32>,
+}
+
+impl Queryable for AuditQuery {
+    fn query(&self, mode: &QueryMode) -> String {
+        let mut lookup = HashMap::new();
+        let mut where_clause = String::new();
+        // start with 1 as the $var in postgres's prepared statements
+        let mut cnt = 1;
+        if self.entity.is_some() {
+            where_clause = format!("{} audit.entity = ${}", where_joiner(cnt), cnt);
+            cnt +=1;
+        }
+        if self.entity_id.is_some() {
+            where_clause = format!("{}\n{} audit.entity_id = ${}", where_clause, where_joiner(cnt), cnt);
+            cnt +=1;
+        }
+        if self.login.is_some() {
+            where_clause = format!("{}\n{} audit.login {} ${}", where_clause, where_joiner(cnt), mode, cnt);
+            cnt +=1;
+        }
+        if self.actor.is_some() {
+            where_clause = format!("{}\n{} audit.actor {} ${}", where_clause, where_joiner(cnt), mode, cnt);
+            cnt +=1;
+        }
+        if self.since.is_some() {
+            where_clause = format!("{}\n{} audit.at >= ${}::date", where_clause, where_joiner(cnt), cnt);
+            cnt +=1;
+        }
+        if self.until.is_some() {
+            where_clause = format!("{}\n{} audit.at < ${}::date + 1", where_clause, where_joiner(cnt), cnt);
+            //cnt +=1;
+        }
+        lookup.insert("query".into(), where_clause);
+        lookup.insert("limit".into(), self.limit.map(|limit| format!("LIMIT {}", limit)).unwrap_or_default());
+        strfmt(QUERY, &lookup).unwrap()
+    }
+}
+
+impl AuditQuery {
+    /// New up an empty AuditQuery intance
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Set the kind of entity on self and return self, as per the
+    /// owning builder pattern.
+    pub fn entity(mut self, entity: Option<AuditEntity>) -> Self {
+        self.entity = entity;
+        self
+    }
+    /// Set the id of the entity on self and return self, as per the
+    /// owning builder pattern.
+    pub fn entity_id(mut self, entity_id: Option<i32>) -> Self {
+        self.entity_id = entity_id;
+        self
+    }
+    /// Set the login of the person affected on self and return self, as
+    /// per the owning builder pattern.
+    pub fn login(mut self, login: Option<String>) -> Self {
+        self.login = login;
+        self
+    }
+    /// Set who made the change on self and return self, as per the
+    /// owning builder pattern.
+    pub fn actor(mut self, actor: Option<String>) -> Self {
+        self.actor = actor;
+        self
+    }
+    /// Set the first day of the range on self and return self, as per
+    /// the owning builder pattern.
+    pub fn since(mut self, since: Option<Date>) -> Self {
+        self.since = since;
+        self
+    }
+    /// Set the last day of the range on self and return self, as per
+    /// the owning builder pattern.
+    pub fn until(mut self, until: Option<Date>) -> Self {
+        self.until = until;
+        self
+    }
+    /// Set the maximum number of entries to return on self and return
+    /// self, as per the owning builder pattern.
+    pub fn limit(mut self, limit: Option<u32>) -> Self {
+        self.limit = limit;
+        self
+    }
+}
+
+/// Given an AuditQuery instance and a mode, retrieve the matching entries
+/// from the audit log, most recent first
+pub async fn query<'c, E>(
+    executor: E,
+    query: AuditQuery,
+    mode: QueryMode
+) -> Result<Vec<AuditEntry>, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    from_json(query_json(executor, query, mode).await?)
+}
+
+/// As `query`, but return each entry as the raw json produced by the database
+pub async fn query_json<'c, E>(
+    executor: E,
+    query: AuditQuery,
+    mode: QueryMode
+) -> Result<Vec<serde_json::Value>, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let mut rval = Vec::new();
+    let querymode = query.query(&mode);
+    let mut rows = sqlx::query(&querymode);
+    let AuditQuery{entity, entity_id, login, actor, since, until, ..} = query;
+    let like = mode == QueryMode::ILike || mode == QueryMode::Like;
+
+    if let Some(entity) = entity {
+        rows = rows.bind(entity.to_static_str());
+    }
+    if let Some(entity_id) = entity_id {
+        rows = rows.bind(entity_id);
+    }
+    if let Some(login) = login {
+        rows = rows.bind(if like { format!("%{}%", login) } else { login });
+    }
+    if let Some(actor) = actor {
+        rows = rows.bind(if like { format!("%{}%", actor) } else { actor });
+    }
+    if let Some(since) = since {
+        rows = rows.bind(since.to_string());
+    }
+    if let Some(until) = until {
+        rows = rows.bind(until.to_string());
+    }
+    let mut rows = rows.fetch(executor);
+    while let Some(row) = rows.try_next().await? {
+        let JsonAdapter{inner} = JsonAdapter::from_row(&row)?;
+        rval.push(inner);
+    }
+    Ok(rval)
+}
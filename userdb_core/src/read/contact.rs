@@ -0,0 +1,144 @@
+//! Read contact channels, such as email addresses and chat handles
+use std::collections::HashMap;
+use strfmt::strfmt;
+use futures::TryStreamExt;
+use sqlx::FromRow;
+use sqlx::{Executor, Postgres};
+use crate::Contact;
+use crate::ContactCategory;
+use crate::QueryMode;
+use crate::prelude::*;
+use crate::JsonAdapter;
+use crate::utils::from_json;
+use crate::where_joiner;
+use crate::UserDbError;
+
+const QUERY: &str = r"
+SELECT
+    row_to_json(r) AS inner
+FROM (
+        SELECT
+            contact.id AS contact_id, contact.category, contact.value, contact.label,
+            contact.is_primary, person.login
+        FROM
+            contact
+        JOIN
+            person ON person.id = contact.person_id
+        {query}
+        ORDER BY
+            person.login, contact.category, contact.is_primary DESC, contact.id
+    ) AS r;";
+
+/// The parameters for a contact query.
+#[derive(Debug, PartialEq, Eq, Default)]
+pub struct ContactQuery {
+    pub id: Option<u32>,
+    pub login: Option<String>,
+    pub category: Option<ContactCategory>,
+    pub value: Option<String>,
+}
+
+impl Queryable for ContactQuery {
+    fn query(&self, mode: &QueryMode) -> String {
+        let mut lookup = HashMap::new();
+        let mut where_clause = String::new();
+        // start with 1 as the $var in postgres's prepared statements
+        let mut cnt = 1;
+        if self.id.is_some() {
+            where_clause = format!("{} contact.id = ${}::integer", where_joiner(cnt), cnt);
+            cnt +=1;
+        }
+        if self.login.is_some() {
+            where_clause = format!("{}\n{} person.login {} ${}", where_clause, where_joiner(cnt), mode, cnt);
+            cnt +=1;
+        }
+        if self.category.is_some() {
+            where_clause = format!("{}\n{} contact.category = ${}::contactcategory", where_clause, where_joiner(cnt), cnt);
+            cnt +=1;
+        }
+        if self.value.is_some() {
+            where_clause = format!("{}\n{} contact.value {} ${}", where_clause, where_joiner(cnt), mode, cnt);
+            //cnt +=1;
+        }
+        lookup.insert("query".into(), where_clause);
+        strfmt(QUERY, &lookup).unwrap()
+    }
+}
+
+impl ContactQuery {
+    /// New up an empty ContactQuery intance
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Set the id on self, returning self as per the
+    /// owning builder pattern.
+    pub fn id(mut self, id: Option<u32>) -> Self {
+        self.id = id;
+        self
+    }
+    /// Set the login on self and return self, as per the
+    /// owning builder pattern.
+    pub fn login(mut self, login: Option<String>) -> Self {
+        self.login = login;
+        self
+    }
+    /// Set the category on self and return self, as per the
+    /// owning builder pattern.
+    pub fn category(mut self, category: Option<ContactCategory>) -> Self {
+        self.category = category;
+        self
+    }
+    /// Set the address or handle on self and return self, as per the
+    /// owning builder pattern.
+    pub fn value(mut self, value: Option<String>) -> Self {
+        self.value = value;
+        self
+    }
+}
+
+/// Given a ContactQuery instance and a mode, retrieve the matching contacts from the database
+pub async fn query<'c, E>(
+    executor: E,
+    query: ContactQuery,
+    mode: QueryMode
+) -> Result<Vec<Contact>, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    from_json(query_json(executor, query, mode).await?)
+}
+
+/// As `query`, but return each contact as the raw json produced by the database
+pub async fn query_json<'c, E>(
+    executor: E,
+    query: ContactQuery,
+    mode: QueryMode
+) -> Result<Vec<serde_json::Value>, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let mut rval = Vec::new();
+    let querymode = query.query(&mode);
+    let mut rows = sqlx::query(&querymode);
+    let ContactQuery{id, login, category, value} = query;
+    let like = mode == QueryMode::ILike || mode == QueryMode::Like;
+
+    if let Some(id) = id {
+        rows = rows.bind(id.to_string());
+    }
+    if let Some(login) = login {
+        rows = rows.bind(if like { format!("%{}%", login) } else { login });
+    }
+    if let Some(category) = category {
+        rows = rows.bind(category.to_static_str());
+    }
+    if let Some(value) = value {
+        rows = rows.bind(if like { format!("%{}%", value) } else { value });
+    }
+    let mut rows = rows.fetch(executor);
+    while let Some(row) = rows.try_next().await? {
+        let JsonAdapter{inner} = JsonAdapter::from_row(&row)?;
+        rval.push(inner);
+    }
+    Ok(rval)
+}
@@ -0,0 +1,57 @@
+//! Read the dial plan of each location
+use futures::TryStreamExt;
+use sqlx::FromRow;
+use sqlx::{Executor, Postgres};
+use crate::DialPlan;
+use crate::JsonAdapter;
+use crate::Location;
+use crate::utils::from_json;
+use crate::UserDbError;
+
+const QUERY: &str = r"
+SELECT
+    row_to_json(r) AS inner
+FROM (
+        SELECT
+            dialplan.location,
+            dialplan.switchboard,
+            dialplan.did_prefix,
+            COALESCE(
+                (SELECT
+                    json_agg(json_build_object('first', first_ext, 'last', last_ext) ORDER BY first_ext)
+                FROM
+                    extension_range
+                WHERE
+                    extension_range.location = dialplan.location),
+                '[]'::json) AS extensions
+        FROM
+            dialplan
+        WHERE
+            $1::text IS NULL OR dialplan.location = $1::text
+        ORDER BY
+            dialplan.location
+    ) AS r;";
+
+/// Retrieve the dial plans of every location, or of `location` alone.
+pub async fn query<'c, E>(executor: E, location: Option<&Location>) -> Result<Vec<DialPlan>, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    from_json(query_json(executor, location).await?)
+}
+
+/// As `query`, but return each dial plan as the raw json produced by the database
+pub async fn query_json<'c, E>(executor: E, location: Option<&Location>) -> Result<Vec<serde_json::Value>, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let mut rval = Vec::new();
+    let mut rows = sqlx::query(QUERY)
+                    .bind(location.map(|l| l.name.as_str()))
+                    .fetch(executor);
+    while let Some(row) = rows.try_next().await? {
+        let JsonAdapter{inner} = JsonAdapter::from_row(&row)?;
+        rval.push(inner);
+    }
+    Ok(rval)
+}
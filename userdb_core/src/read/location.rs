@@ -0,0 +1,73 @@
+//! Read locations
+use futures::TryStreamExt;
+use sqlx::FromRow;
+use sqlx::{Executor, Postgres};
+use std::num::NonZeroU32;
+use crate::JsonAdapter;
+use crate::Location;
+use crate::utils::{from_json, Count};
+use crate::UserDbError;
+
+const QUERY: &str = r"
+SELECT 
+    row_to_json(r) AS inner 
+FROM (
+        SELECT 
+            * 
+        FROM
+            location 
+        ORDER BY 
+            name
+        LIMIT $1
+        OFFSET $2
+    ) AS r;";
+
+const COUNT: &str = r"
+SELECT
+    count(*) AS count
+FROM
+    location;";
+
+/// Retrieve all of the locations in the db, ordered by name.
+pub async fn query<'c, E>(executor: E) -> Result<Vec<Location>, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    from_json(query_json(executor).await?)
+}
+
+/// As `query`, but return each location as the raw json produced by the database
+pub async fn query_json<'c, E>(executor: E) -> Result<Vec<serde_json::Value>, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    page_json(executor, None, 0).await
+}
+
+/// As `query_json`, but return at most `limit` locations, after skipping
+/// `offset` of them
+pub async fn page_json<'c, E>(executor: E, limit: Option<NonZeroU32>, offset: u32) -> Result<Vec<serde_json::Value>, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let mut rval = Vec::new();
+    // a NULL limit returns every location
+    let mut rows = sqlx::query(QUERY)
+                    .bind(limit.map(|limit| i64::from(limit.get())))
+                    .bind(i64::from(offset))
+                    .fetch(executor);
+    while let Some(row) = rows.try_next().await? {
+        let JsonAdapter{inner} = JsonAdapter::from_row(&row)?;
+        rval.push(inner);
+    }
+    Ok(rval)
+}
+
+/// The number of locations in the db
+pub async fn count<'c, E>(executor: E) -> Result<i64, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let Count{count} = sqlx::query_as(COUNT).fetch_one(executor).await?;
+    Ok(count)
+}
@@ -0,0 +1,55 @@
+//! Read the reporting relationships between people
+use futures::TryStreamExt;
+use sqlx::FromRow;
+use sqlx::{Executor, Postgres};
+use crate::JsonAdapter;
+use crate::OrgPerson;
+use crate::utils::from_json;
+use crate::UserDbError;
+
+const QUERY: &str = r"
+SELECT 
+    row_to_json(r) AS inner 
+FROM (
+        SELECT 
+            p.id AS person_id,
+            p.login,
+            p.first || ' ' || p.last AS fullname,
+            title.name AS title,
+            dept.name AS department,
+            p.manager_id
+        FROM
+            person AS p
+        JOIN 
+            title ON p.title_id = title.id
+        JOIN 
+            department AS dept ON p.department_id = dept.id
+        WHERE
+            p.status <> 'Terminated'
+        ORDER BY 
+            p.login
+    ) AS r;";
+
+/// Retrieve everyone in the db who has not been terminated, along with
+/// the id of their manager, ordered by login.
+pub async fn query<'c, E>(executor: E) -> Result<Vec<OrgPerson>, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    from_json(query_json(executor).await?)
+}
+
+/// As `query`, but return each person as the raw json produced by the database
+pub async fn query_json<'c, E>(executor: E) -> Result<Vec<serde_json::Value>, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let mut rval = Vec::new();
+    let mut rows = sqlx::query(QUERY)
+                    .fetch(executor);
+    while let Some(row) = rows.try_next().await? {
+        let JsonAdapter{inner} = JsonAdapter::from_row(&row)?;
+        rval.push(inner);
+    }
+    Ok(rval)
+}
@@ -1,31 +1,37 @@
 
 use futures::TryStreamExt;
 use sqlx::FromRow;
+use sqlx::{Executor, Postgres};
 use std::collections::HashMap;
 use strfmt::strfmt;
 use crate::where_joiner;
 use crate::QueryMode;
 use crate::JsonAdapter;
+use crate::utils::from_json;
+use crate::UserDbError;
+use crate::PersonView;
+use crate::Date;
+use crate::NumberString;
+use crate::country::NATIONAL_LENGTH;
 
 /// Struct encapsulating potential query parameters
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct PersonQuery {
     pub name: Option<String>,
     pub login: Option<String>,
     pub title: Option<String>,
-    pub dept: Option<String>
+    pub dept: Option<String>,
+    /// An email address or chat handle of the person
+    pub contact: Option<String>,
+    /// The trailing digits of one of the person's phone numbers, or of the
+    /// direct dial number of one of their extensions
+    pub number: Option<NumberString>,
+    /// Include people who have been terminated
+    pub include_inactive: bool,
+    /// Look people up as they were at the end of this day, rather than now
+    pub as_of: Option<Date>,
 }
 
-impl Default for PersonQuery {
-    fn default() -> Self {
-        Self {
-            name: None,
-            login: None,
-            title: None,
-            dept: None
-        }
-    }
-}
 impl PersonQuery {
 
     /// Generate a prepared statement to query for person(s) as a string
@@ -49,9 +55,24 @@ impl PersonQuery {
         }
         if self.dept.is_some() {
             where_clause = format!("{}\n{} department {} ${}", where_clause, where_joiner(cnt), mode, cnt);
-            //cnt +=1;
+            cnt +=1;
+        }
+        if self.contact.is_some() {
+            where_clause = format!("{}\n{} person_id IN (SELECT person_id FROM contact WHERE value {} ${})", where_clause, where_joiner(cnt), mode, cnt);
+            cnt +=1;
+        }
+        if self.number.is_some() {
+            // everyone holding the phone is found, not just the first. The
+            // number is the last parameter before the day, if any
+            where_clause = format!("{}\n{} person_id IN (SELECT person_id FROM {} WHERE number LIKE ${} OR did LIKE ${})",
+                where_clause, where_joiner(cnt), person_view(self.as_of.is_some(), cnt + 1), cnt, cnt);
+            cnt +=1;
+        }
+        if !self.include_inactive {
+            where_clause = format!("{}\n{} status <> 'Terminated'", where_clause, where_joiner(cnt));
         }
         lookup.insert("query".into(), where_clause);
+        lookup.insert("view".into(), person_view(self.as_of.is_some(), cnt));
         strfmt(QUERY, &lookup).unwrap()
     }
 
@@ -78,22 +99,64 @@ impl PersonQuery {
         self.dept = dept;
         self
     }
+
+    pub fn contact(mut self, contact: Option<String>) -> Self {
+        self.contact = contact;
+        self
+    }
+
+    pub fn number(mut self, number: Option<NumberString>) -> Self {
+        self.number = number;
+        self
+    }
+
+    pub fn include_inactive(mut self, include_inactive: bool) -> Self {
+        self.include_inactive = include_inactive;
+        self
+    }
+
+    pub fn as_of(mut self, as_of: Option<Date>) -> Self {
+        self.as_of = as_of;
+        self
+    }
 }
 
-const QUERY: &'static str = r"
+/// The digits a number is matched by, as the end of a stored number. The
+/// trunk prefix of a full national number, eg the 0 of 040 1234 5678, is
+/// not stored, so is dropped. Anything shorter, such as the last few digits
+/// or an extension, is matched as entered.
+pub(crate) fn number_suffix(number: &NumberString) -> String {
+    let number = number.to_string();
+    match number.strip_prefix('0') {
+        Some(national) if national.len() == NATIONAL_LENGTH => national.to_string(),
+        _ => number,
+    }
+}
+
+/// The view to select people from: personview, or its state at the end
+/// of the day bound to parameter `cnt` when looking back in time
+pub(crate) fn person_view(as_of: bool, cnt: u8) -> String {
+    if as_of {
+        format!("personview_as_of(${}::date)", cnt)
+    } else {
+        "personview".to_string()
+    }
+}
+
+const QUERY: &str = r"
 WITH pview AS
 ( 
     SELECT * 
-    FROM personview
+    FROM {view}
     {query}
 )
 SELECT row_to_json(ln2) as inner from (
     SELECT DISTINCT ON (person_id) *  
-    FROM ( SELECT pv.person_id, pv.first, pv.last, pv.login, pv.fullname, pv.department, pv.title,
+    FROM ( SELECT pv.person_id, pv.first, pv.last, pv.login, pv.fullname, pv.department, pv.title, pv.manager, pv.status, pv.start_date, pv.end_date,
             ( SELECT 
-                json_agg(rowval) AS phones 
+                json_agg(rowval ORDER BY rowval.is_primary DESC, rowval.phone_id) AS phones 
               FROM 
-                    ( SELECT phone_id, number, category, location 
+                    ( SELECT phone_id, number, category, location, label, is_primary, did 
                         FROM 
                             pview 
                         WHERE 
@@ -102,55 +165,128 @@ SELECT row_to_json(ln2) as inner from (
                             pv.phone_id IS NOT NULL
                     ) 
                 rowval
-            ) 
+            ),
+            ( SELECT
+                json_agg(rowval ORDER BY rowval.category, rowval.is_primary DESC, rowval.contact_id) AS contacts
+              FROM
+                    ( SELECT id AS contact_id, category, value, label, is_primary
+                        FROM
+                            contact
+                        WHERE
+                            contact.person_id = pv.person_id
+                    )
+                rowval
+            ),
+            ( SELECT
+                json_agg(rowval ORDER BY rowval.location NULLS FIRST, rowval.desk_id) AS desks
+              FROM
+                    ( SELECT id AS desk_id, location, building, floor, room
+                        FROM
+                            desk
+                        WHERE
+                            desk.person_id = pv.person_id
+                    )
+                rowval
+            )
             FROM pview AS pv
         ) AS ln
 ) AS ln2;";
 
 
-/// Given a PersonQuery instance and a mode, retrieve the results from the database
-pub async fn query(
-    pool: &sqlx::PgPool, 
+/// Given a PersonQuery instance and a mode, retrieve the matching people from the database
+pub async fn query<'c, E>(
+    executor: E, 
     query: PersonQuery, 
     mode: QueryMode
-) -> Result<Vec<serde_json::Value>, sqlx::Error> {
+) -> Result<Vec<PersonView>, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    from_json(query_json(executor, query, mode).await?)
+}
+
+/// As `query`, but return each person as the raw json produced by the database
+pub async fn query_json<'c, E>(
+    executor: E, 
+    query: PersonQuery, 
+    mode: QueryMode
+) -> Result<Vec<serde_json::Value>, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
     let mut rval = Vec::new();
     let querymode = query.query(&mode);
     let  mut rows = sqlx::query(&querymode);
-    let PersonQuery{name, login, title, dept} = query;
-    if name.is_some() {
-        let mut name = name.unwrap();
+    let PersonQuery{name, login, title, dept, contact, number, as_of, ..} = query;
+    if let Some(mut name) = name {
         if mode == QueryMode::ILike || mode == QueryMode::Like {
             name = format!("%{}%", name);
         }
         rows = rows.bind(name);
     }
-    if login.is_some() {
-        let mut login = login.unwrap();
+    if let Some(mut login) = login {
         if mode == QueryMode::ILike || mode == QueryMode::Like {
             login = format!("%{}%", login);
         }
         rows = rows.bind(login);
     }
-    if title.is_some() {
-        let mut title = title.unwrap();
+    if let Some(mut title) = title {
         if mode == QueryMode::ILike || mode == QueryMode::Like {
             title = format!("%{}%", title);
         }
         rows = rows.bind(title);
     }
-    if dept.is_some() {
-        let mut dept = dept.unwrap();
+    if let Some(mut dept) = dept {
         if mode == QueryMode::ILike || mode == QueryMode::Like {
             dept = format!("%{}%", dept);
         }
         rows = rows.bind(dept);
     }
-    let mut rows = rows.fetch(pool);
+    if let Some(mut contact) = contact {
+        if mode == QueryMode::ILike || mode == QueryMode::Like {
+            contact = format!("%{}%", contact);
+        }
+        rows = rows.bind(contact);
+    }
+    if let Some(number) = number {
+        rows = rows.bind(format!("%{}", number_suffix(&number)));
+    }
+    if let Some(as_of) = as_of {
+        rows = rows.bind(as_of.to_string());
+    }
+    let mut rows = rows.fetch(executor);
                    
     while let Some(row) = rows.try_next().await? {
-        let JsonAdapter{inner} =JsonAdapter::from_row(&row).unwrap();   
+        let JsonAdapter{inner} =JsonAdapter::from_row(&row)?;
         rval.push(inner);
     }
     Ok(rval)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn number_suffix_drops_trunk_prefix() {
+        let suffix = |number: &str| number_suffix(&NumberString::from_str(number).unwrap());
+        assert_eq!(suffix("040 1234 5678"), "4012345678");
+        assert_eq!(suffix("310-376-7091"), "3103767091");
+        assert_eq!(suffix("+1 310 376 7091"), "+13103767091");
+        assert_eq!(suffix("0712"), "0712");
+        assert_eq!(suffix("07091"), "07091");
+        assert_eq!(suffix("000"), "000");
+    }
+
+    #[test]
+    fn query_given_number_and_as_of_matches_history() {
+        let query = PersonQuery::new()
+            .login(Some("jdoe".into()))
+            .number(NumberString::from_str("7091").ok())
+            .as_of(Date::from_str("2020-03-31").ok())
+            .query(&QueryMode::ILike);
+        assert!(query.contains("SELECT person_id FROM personview_as_of($3::date) WHERE number LIKE $2 OR did LIKE $2"));
+        assert!(query.contains("FROM personview_as_of($3::date)\n"));
+    }
+}
@@ -1,27 +1,74 @@
 
 use futures::TryStreamExt;
 use sqlx::FromRow;
+use sqlx::{Executor, Postgres};
+use sqlx::postgres::PgArguments;
+use sqlx::query::Query;
 use std::collections::HashMap;
+use std::num::NonZeroU32;
+use std::str::FromStr;
 use strfmt::strfmt;
 use crate::QueryMode;
 use crate::JsonAdapter;
+use crate::utils::{from_json, limit_clause, Count};
+use crate::UserDbError;
+use crate::PersonView;
+use crate::Date;
+use crate::Search;
+use crate::read::person::person_view;
 
 /// Struct encapsulating potential query parameters
 #[derive(Debug)]
 pub struct SimplePersonQuery {
+   /// The search, written in the query language described in `Search`
    pub value: String,
+   /// Include people who have been terminated
+   pub include_inactive: bool,
+   /// Look people up as they were at the end of this day, rather than now
+   pub as_of: Option<Date>,
+   /// Also find people whose name or login is close to the value, to
+   /// allow for typos
+   pub fuzzy: bool,
+   /// The most people to return, best matches first. A limit of zero
+   /// cannot be expressed, as it would return no one
+   pub limit: Option<NonZeroU32>,
+   /// The number of people to skip before returning any, eg to page
+   /// through the results
+   pub offset: u32,
 }
 
 impl SimplePersonQuery {
 
-    /// Generate a prepared statement to query for person(s) as a string
-    pub fn query(&self, mode: &QueryMode) -> String {
+    /// Generate a prepared statement to query for the person(s) matching
+    /// `search`, which is parsed from the value, as a string
+    pub fn query(&self, search: &Search, mode: &QueryMode) -> String {
+        let mut lookup = self.lookup(search, mode);
+        lookup.insert("limit".into(), limit_clause(self.limit, self.offset));
+        strfmt(QUERY, &lookup).unwrap()
+    }
+
+    /// Generate a prepared statement counting the people matching `search`,
+    /// regardless of the limit and offset. It takes the same parameters
+    /// as `query`.
+    pub fn count_query(&self, search: &Search, mode: &QueryMode) -> String {
+        strfmt(COUNT, &self.lookup(search, mode)).unwrap()
+    }
+
+    // the view and where clause shared by `query` and `count_query`
+    fn lookup(&self, search: &Search, mode: &QueryMode) -> HashMap<String, String> {
         let mut lookup = HashMap::new();
        
-        let where_clause = format!("WHERE fullname {} $1 OR login {} $1 or title {} $1 OR department {} $1", mode, mode, mode, mode);
+        // $1 is the values people are ranked by, followed by the parameters
+        // of the search, and finally the day, if any
+        let as_of = 2 + search.param_count(self.fuzzy);
+        let view = person_view(self.as_of.is_some(), as_of);
+        let where_clause = format!("WHERE {}{}",
+            search.to_sql(mode, 2, &view, self.fuzzy),
+            if self.include_inactive { "" } else { " AND status <> 'Terminated'" });
          
         lookup.insert("query".into(), where_clause);
-        strfmt(QUERY, &lookup).unwrap()
+        lookup.insert("view".into(), view);
+        lookup
     }
 
     pub fn new<I>(value: I) -> Self 
@@ -29,26 +76,70 @@ impl SimplePersonQuery {
         I: Into<String>
     {
         SimplePersonQuery{
-            value: value.into()
+            value: value.into(),
+            include_inactive: false,
+            as_of: None,
+            fuzzy: false,
+            limit: None,
+            offset: 0,
         }
     }
 
+    /// Include people who have been terminated
+    pub fn include_inactive(mut self, include_inactive: bool) -> Self {
+        self.include_inactive = include_inactive;
+        self
+    }
+
+    /// Look people up as they were at the end of the supplied day
+    pub fn as_of(mut self, as_of: Option<Date>) -> Self {
+        self.as_of = as_of;
+        self
+    }
+
+    /// Also find people whose name or login is close to the value
+    pub fn fuzzy(mut self, fuzzy: bool) -> Self {
+        self.fuzzy = fuzzy;
+        self
+    }
+
+    /// Return at most `limit` people
+    pub fn limit(mut self, limit: Option<NonZeroU32>) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Skip the first `offset` people
+    pub fn offset(mut self, offset: u32) -> Self {
+        self.offset = offset;
+        self
+    }
+
 }
 
-const QUERY: &'static str = r"
-WITH pview AS
-( 
-    SELECT * 
-    FROM personview
+// The search picks out the people first, as filters on a phone (eg loc:)
+// match some of a person's rows in the view, while every phone of the
+// people found is listed.
+const QUERY: &str = r"
+WITH matched AS
+(
+    SELECT DISTINCT person_id
+    FROM {view}
     {query}
+),
+pview AS
+( 
+    SELECT *, coalesce((SELECT sum(searchScore(value, login, fullname, title, department)) FROM unnest($1::text[]) AS value), 0)::real AS score
+    FROM {view}
+    WHERE person_id IN (SELECT person_id FROM matched)
 )
 SELECT row_to_json(ln2) as inner from (
     SELECT DISTINCT ON (person_id) *  
-    FROM ( SELECT pv.person_id, pv.first, pv.last, pv.login, pv.fullname, pv.department, pv.title,
+    FROM ( SELECT pv.person_id, pv.first, pv.last, pv.login, pv.fullname, pv.department, pv.title, pv.manager, pv.status, pv.start_date, pv.end_date, pv.score,
             ( SELECT 
-                json_agg(rowval) AS phones 
+                json_agg(rowval ORDER BY rowval.is_primary DESC, rowval.phone_id) AS phones 
               FROM 
-                    ( SELECT phone_id, number, category, location 
+                    ( SELECT phone_id, number, category, location, label, is_primary, did 
                         FROM 
                             pview 
                         WHERE 
@@ -57,34 +148,161 @@ SELECT row_to_json(ln2) as inner from (
                             pv.phone_id IS NOT NULL
                     ) 
                 rowval
-            ) 
+            ),
+            ( SELECT
+                json_agg(rowval ORDER BY rowval.category, rowval.is_primary DESC, rowval.contact_id) AS contacts
+              FROM
+                    ( SELECT id AS contact_id, category, value, label, is_primary
+                        FROM
+                            contact
+                        WHERE
+                            contact.person_id = pv.person_id
+                    )
+                rowval
+            ),
+            ( SELECT
+                json_agg(rowval ORDER BY rowval.location NULLS FIRST, rowval.desk_id) AS desks
+              FROM
+                    ( SELECT id AS desk_id, location, building, floor, room
+                        FROM
+                            desk
+                        WHERE
+                            desk.person_id = pv.person_id
+                    )
+                rowval
+            )
             FROM pview AS pv
         ) AS ln
-) AS ln2;";
+) AS ln2
+ORDER BY ln2.score DESC, ln2.fullname
+{limit};";
+
+// $1, the ranking values, goes unused, so that the parameters are
+// numbered as they are in QUERY
+const COUNT: &str = r"
+SELECT count(DISTINCT person_id) AS count
+FROM {view}
+{query};";
 
 
-/// Given a SimplePersonQuery instance and a mode, retrieve the results from the database
-pub async fn query(
-    pool: &sqlx::PgPool, 
+/// Given a SimplePersonQuery instance and a mode, retrieve the matching people from the database
+pub async fn query<'c, E>(
+    executor: E, 
     query: SimplePersonQuery, 
     mode: QueryMode
-) -> Result<Vec<serde_json::Value>, sqlx::Error> {
-    let mut rval = Vec::new();
-    let querymode = query.query(&mode);
-    let  mut rows = sqlx::query(&querymode);
-    let SimplePersonQuery{mut value} = query;
-
-    if mode == QueryMode::ILike || mode == QueryMode::Like {
-        value = format!("%{}%", value);
-    }
+) -> Result<Vec<PersonView>, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    from_json(query_json(executor, query, mode).await?)
+}
 
-    rows = rows.bind(value);
-    
-    let mut rows = rows.fetch(pool);
+/// As `query`, but return each person as the raw json produced by the database
+pub async fn query_json<'c, E>(
+    executor: E, 
+    query: SimplePersonQuery, 
+    mode: QueryMode
+) -> Result<Vec<serde_json::Value>, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let mut rval = Vec::new();
+    let search = Search::from_str(&query.value)?;
+    let querymode = query.query(&search, &mode);
+    let rows = sqlx::query(&querymode);
+    let mut rows = bind(rows, &search, &mode, &query).fetch(executor);
                    
     while let Some(row) = rows.try_next().await? {
-        let JsonAdapter{inner} =JsonAdapter::from_row(&row).unwrap();   
+        let JsonAdapter{inner} =JsonAdapter::from_row(&row)?;
         rval.push(inner);
     }
     Ok(rval)
-}
\ No newline at end of file
+}
+
+/// The number of people matching the query, ignoring its limit and offset
+pub async fn count<'c, E>(
+    executor: E, 
+    query: &SimplePersonQuery, 
+    mode: QueryMode
+) -> Result<i64, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let search = Search::from_str(&query.value)?;
+    let countquery = query.count_query(&search, &mode);
+    let row = bind(sqlx::query(&countquery), &search, &mode, query)
+        .fetch_one(executor).await?;
+    let Count{count} = Count::from_row(&row)?;
+    Ok(count)
+}
+
+// bind the parameters of `query`, as `SimplePersonQuery::query` numbers them
+fn bind<'q>(
+    mut rows: Query<'q, Postgres, PgArguments>,
+    search: &Search,
+    mode: &QueryMode,
+    query: &SimplePersonQuery,
+) -> Query<'q, Postgres, PgArguments> {
+    rows = rows.bind(search.ranking_values().into_iter().map(String::from).collect::<Vec<_>>());
+    for param in search.params(mode, query.fuzzy) {
+        rows = rows.bind(param);
+    }
+    if let Some(as_of) = &query.as_of {
+        rows = rows.bind(as_of.to_string());
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(value: &str, person_query: SimplePersonQuery) -> String {
+        person_query.query(&Search::from_str(value).unwrap(), &QueryMode::ILike)
+    }
+
+    #[test]
+    fn query_orders_by_score_then_name() {
+        let sql = query("sam", SimplePersonQuery::new("sam"));
+        assert!(sql.contains("sum(searchScore(value, login, fullname, title, department)) FROM unnest($1::text[])"));
+        assert!(sql.contains("ORDER BY ln2.score DESC, ln2.fullname\n;"));
+        assert!(sql.contains("AND status <> 'Terminated'"));
+        assert!(!sql.contains("fuzzyMatch"));
+    }
+
+    #[test]
+    fn query_lists_every_phone_of_the_people_matched() {
+        let sql = query("loc:pv", SimplePersonQuery::new("loc:pv"));
+        let (matched, rest) = sql.split_once("pview AS").unwrap();
+        assert!(matched.contains("SELECT DISTINCT person_id\n    FROM personview\n    WHERE "));
+        assert!(rest.contains("FROM personview\n    WHERE person_id IN (SELECT person_id FROM matched)\n)"));
+    }
+
+    #[test]
+    fn query_given_fuzzy_matches_similar_names() {
+        let sql = query("sam", SimplePersonQuery::new("sam").fuzzy(true).include_inactive(true));
+        assert!(sql.contains("OR fuzzyMatch($3, login, fullname))"));
+        assert!(!sql.contains("Terminated"));
+    }
+
+    #[test]
+    fn query_given_limit_and_as_of_numbers_the_day_last() {
+        let person_query = SimplePersonQuery::new("sam")
+            .fuzzy(true)
+            .limit(NonZeroU32::new(5))
+            .as_of(Date::from_str("2020-03-31").ok());
+        let sql = query("sam", person_query);
+        assert!(sql.contains("FROM personview_as_of($4::date)\n"));
+        assert!(sql.ends_with("ORDER BY ln2.score DESC, ln2.fullname\nLIMIT 5;"));
+    }
+
+    #[test]
+    fn query_given_offset_pages_and_count_ignores_it() {
+        let person_query = SimplePersonQuery::new("sam").limit(NonZeroU32::new(5)).offset(10);
+        let search = Search::from_str("sam").unwrap();
+        assert!(person_query.query(&search, &QueryMode::ILike).ends_with("LIMIT 5 OFFSET 10;"));
+        let count = person_query.count_query(&search, &QueryMode::ILike);
+        assert!(count.starts_with("\nSELECT count(DISTINCT person_id) AS count\nFROM personview\nWHERE "));
+        assert!(!count.contains("LIMIT"));
+    }
+}
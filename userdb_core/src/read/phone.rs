@@ -3,6 +3,10 @@ use std::collections::HashMap;
 use strfmt::strfmt;
 use futures::TryStreamExt;
 use sqlx::FromRow;
+use sqlx::{Executor, Postgres};
+use sqlx::postgres::PgArguments;
+use sqlx::query::Query;
+use std::num::NonZeroU32;
 use crate::PhoneCategory;
 //use crate::PhoneNumber;
 use crate::NumberString;
@@ -10,42 +14,66 @@ use crate::Location;
 use crate::QueryMode;
 use crate::prelude::*;
 use crate::JsonAdapter;
+use crate::utils::{from_json, limit_clause, Count};
 use crate::where_joiner;
+use crate::UserDbError;
+use crate::Phone;
+use crate::Date;
 
-const QUERY: &'static str = r"
+const QUERY: &str = r"
 SELECT 
     row_to_json(r) AS inner 
 FROM (
         SELECT 
-            id as phone_id, * 
+            id as phone_id, *, extensionDid(number, category, location) AS did 
         FROM
-            phone 
+            {table} AS phone
         {query}
-    ) AS r;";
+    ) AS r
+ORDER BY r.phone_id
+{limit};";
+
+const COUNT: &str = r"
+SELECT
+    count(*) AS count
+FROM
+    {table} AS phone
+{query};";
 
 
 /// The parameters for a phone query.
-#[derive(Debug,PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Default)]
 pub struct PhoneQuery {
     pub id: Option<u32>,
     pub number: Option<NumberString>,
     pub category: Option<PhoneCategory>,
-    pub location: Option<Location>
+    pub location: Option<Location>,
+    /// Look phones up as they were at the end of this day, rather than now
+    pub as_of: Option<Date>,
+    /// The most phones to return
+    pub limit: Option<NonZeroU32>,
+    /// The number of phones to skip before returning any
+    pub offset: u32,
 }
 
-impl Default for PhoneQuery {
-    fn default() -> Self {
-        Self {
-            id: None,
-            number: None,
-            category: None,
-            location: None
-        }
+impl Queryable for PhoneQuery {
+    fn query(&self, mode: &QueryMode) -> String {
+        let mut lookup = self.lookup(mode);
+        lookup.insert("limit".into(), limit_clause(self.limit, self.offset));
+        strfmt(QUERY, &lookup).unwrap()
     }
 }
 
-impl Queryable for PhoneQuery {
-    fn query(&self, mode: &QueryMode) -> String {
+impl PhoneQuery {
+    /// Generate a prepared statement counting the phones matching self,
+    /// regardless of the limit and offset. It takes the same parameters
+    /// as `query`.
+    pub fn count_query(&self, mode: &QueryMode) -> String {
+        strfmt(COUNT, &self.lookup(mode)).unwrap()
+    }
+
+    // the table and where clause shared by `query` and `count_query`
+    fn lookup(&self, mode: &QueryMode) -> HashMap<String, String> {
         let mut lookup = HashMap::new();
         let mut where_clause = String::new();
         // start with 1 as the $var in postgres's prepared statements 
@@ -68,14 +96,15 @@ impl Queryable for PhoneQuery {
         if self.location.is_some() {
             // see category comment
             //where_clause = format!("{}\n{} location = LOWER(${})::location", where_clause, where_joiner(cnt),  cnt);
-            where_clause = format!("{}\n{} location = ${}::location", where_clause, where_joiner(cnt),  cnt);
-            //cnt +=1;
+            where_clause = format!("{}\n{} location = ${}", where_clause, where_joiner(cnt),  cnt);
+            cnt +=1;
         }
+        let table = if self.as_of.is_some() { format!("phone_as_of(${}::date)", cnt) } else { "phone".to_string() };
         lookup.insert("query".into(), where_clause);
-        strfmt(QUERY, &lookup).unwrap()
+        lookup.insert("table".into(), table);
+        lookup
     }
-}
-impl PhoneQuery {
+
     /// New up an empty PhoneQuery intance
     pub fn new() -> Self {
         Self::default()
@@ -104,50 +133,114 @@ impl PhoneQuery {
         self.location = location;
         self
     }
+    /// Set the day to look phones up as of on self and return self, as
+    /// per the owning builder pattern.
+    pub fn as_of(mut self, as_of: Option<Date>) -> Self {
+        self.as_of = as_of;
+        self
+    }
+    /// Set the most phones to return on self and return self, as per
+    /// the owning builder pattern.
+    pub fn limit(mut self, limit: Option<NonZeroU32>) -> Self {
+        self.limit = limit;
+        self
+    }
+    /// Set the number of phones to skip on self and return self, as per
+    /// the owning builder pattern.
+    pub fn offset(mut self, offset: u32) -> Self {
+        self.offset = offset;
+        self
+    }
 }
 
-/// Given a PersonQuery instance and a mode, retrieve the results from the database
-pub async fn query(
-    pool: &sqlx::PgPool, 
+/// Given a PhoneQuery instance and a mode, retrieve the matching phones from the database
+pub async fn query<'c, E>(
+    executor: E, 
     query: PhoneQuery, 
     mode: QueryMode
-) -> Result<Vec<serde_json::Value>, sqlx::Error> {
+) -> Result<Vec<Phone>, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    from_json(query_json(executor, query, mode).await?)
+}
+
+/// As `query`, but return each phone as the raw json produced by the database
+pub async fn query_json<'c, E>(
+    executor: E, 
+    query: PhoneQuery, 
+    mode: QueryMode
+) -> Result<Vec<serde_json::Value>, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
     let mut rval = Vec::new();
     let querymode = query.query(&mode);
-    let  mut rows = sqlx::query(&querymode);
-    let PhoneQuery{id, number, category, location} = query;
+    let rows = sqlx::query(&querymode);
+    // uncomment to print out query for debugging purposes
+    // use sqlx::Execute;
+    //println!("sql {}", rows.sql());
+    let mut rows = bind(rows, &query, &mode).fetch(executor);
+                   
+    while let Some(row) = rows.try_next().await? {
+        let JsonAdapter{inner} = JsonAdapter::from_row(&row)?;
+        rval.push(inner);
+    }
+    Ok(rval)
+}
+
+/// The number of phones matching the query, ignoring its limit and offset
+pub async fn count<'c, E>(
+    executor: E, 
+    query: &PhoneQuery, 
+    mode: QueryMode
+) -> Result<i64, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let countquery = query.count_query(&mode);
+    let row = bind(sqlx::query(&countquery), query, &mode).fetch_one(executor).await?;
+    let Count{count} = Count::from_row(&row)?;
+    Ok(count)
+}
+
+// bind the parameters of `query`, as `PhoneQuery::query` numbers them
+fn bind<'q>(
+    mut rows: Query<'q, Postgres, PgArguments>,
+    query: &PhoneQuery,
+    mode: &QueryMode,
+) -> Query<'q, Postgres, PgArguments> {
+    let PhoneQuery{id, number, category, location, as_of, ..} = query;
     let id = id.map(|v| v.to_string());
-    let category = category.map(|c| c.to_static_str().to_string());
-    let location = location.map(|l| l.to_static_str().to_string());
+    // exact matches need the number in the form it is stored in. Without
+    // a category, numbers which cannot be read as E.164 are taken as is
+    let country = location.as_ref().and_then(Location::country);
+    let number = match (number.clone(), category) {
+        (Some(number), Some(category)) if *mode == QueryMode::Exact => Some(number.normalize(category, country.as_ref()).unwrap_or(number)),
+        (Some(number), None) if *mode == QueryMode::Exact => Some(number.to_e164(country.as_ref()).unwrap_or(number)),
+        (number, _) => number,
+    };
+    let category = category.as_ref().map(|c| c.to_static_str().to_string());
+    let location = location.as_ref().map(|l| l.name.clone());
 
-    if id.is_some() {
-        let id = id.unwrap();
+    if let Some(id) = id {
         rows = rows.bind(id);
     }
-    if number.is_some() {
-        //let mut number = number.unwrap().inner();
-        let mut number = number.unwrap().to_string();
-        if mode == QueryMode::ILike || mode == QueryMode::Like {
+    if let Some(number) = number {
+        let mut number = number.to_string();
+        if *mode == QueryMode::ILike || *mode == QueryMode::Like {
             number = format!("%{}%", number);
         }
         rows = rows.bind(number);
     }
-    if category.is_some() {
-        let  category = category.unwrap();
+    if let Some(category) = category {
         rows = rows.bind(category);
     }
-    if location.is_some() {
-        let  location = location.unwrap();
+    if let Some(location) = location {
         rows = rows.bind(location);
     }
-    // uncomment to print out query for debugging purposes
-    // use sqlx::Execute;
-    //println!("sql {}", rows.sql());
-    let mut rows = rows.fetch(pool);
-                   
-    while let Some(row) = rows.try_next().await? {
-        let JsonAdapter{inner} = JsonAdapter::from_row(&row).unwrap();   
-        rval.push(inner);
+    if let Some(as_of) = as_of {
+        rows = rows.bind(as_of.to_string());
     }
-    Ok(rval)
+    rows
 }
\ No newline at end of file
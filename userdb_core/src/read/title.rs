@@ -1,10 +1,14 @@
 
 use futures::TryStreamExt;
 use sqlx::FromRow;
+use sqlx::{Executor, Postgres};
+use std::num::NonZeroU32;
 use serde::{Serialize, Deserialize};
 use crate::JsonAdapter;
+use crate::utils::{from_json, Count};
+use crate::UserDbError;
 
-const QUERY: &'static str = r"
+const QUERY: &str = r"
 SELECT 
     row_to_json(r) AS inner 
 FROM (
@@ -14,8 +18,16 @@ FROM (
             title 
         ORDER BY 
             name
+        LIMIT $1
+        OFFSET $2
     ) AS r;";
 
+const COUNT: &str = r"
+SELECT
+    count(*) AS count
+FROM
+    title;";
+
      
 #[derive(Serialize,Deserialize,Debug)]
 pub struct TitleView {
@@ -23,15 +35,46 @@ pub struct TitleView {
     pub name: String,
 }
 
-/// Given a reference to the PgPool and a QueryParam instance, look up the 
-/// matching values in the db and return a vector of json objects.
-pub async fn query(pool: &sqlx::PgPool) -> Result<Vec<serde_json::Value>, sqlx::Error> {    
+/// Retrieve all of the titles in the db, ordered by name.
+pub async fn query<'c, E>(executor: E) -> Result<Vec<TitleView>, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    from_json(query_json(executor).await?)
+}
+
+/// As `query`, but return each title as the raw json produced by the database
+pub async fn query_json<'c, E>(executor: E) -> Result<Vec<serde_json::Value>, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    page_json(executor, None, 0).await
+}
+
+/// As `query_json`, but return at most `limit` titles, after skipping
+/// `offset` of them
+pub async fn page_json<'c, E>(executor: E, limit: Option<NonZeroU32>, offset: u32) -> Result<Vec<serde_json::Value>, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
     let mut rval = Vec::new();
-    let mut rows = sqlx::query(&QUERY)
-                    .fetch(pool);
+    // a NULL limit returns every title
+    let mut rows = sqlx::query(QUERY)
+                    .bind(limit.map(|limit| i64::from(limit.get())))
+                    .bind(i64::from(offset))
+                    .fetch(executor);
     while let Some(row) = rows.try_next().await? {
-        let JsonAdapter{inner} =JsonAdapter::from_row(&row).unwrap();   
+        let JsonAdapter{inner} =JsonAdapter::from_row(&row)?;
         rval.push(inner);
     }
     Ok(rval)
-}
\ No newline at end of file
+}
+
+/// The number of titles in the db
+pub async fn count<'c, E>(executor: E) -> Result<i64, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let Count{count} = sqlx::query_as(COUNT).fetch_one(executor).await?;
+    Ok(count)
+}
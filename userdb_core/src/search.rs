@@ -0,0 +1,480 @@
+//! The query language of the phone command. A search is made of terms,
+//! each optionally qualified by a field, eg `dept:"vancouver soft"`.
+//! Terms written one after another must all match, and may be combined
+//! with `AND`, `OR` and `NOT` (or a leading `-`), and grouped with
+//! parentheses:
+//!
+//! ```text
+//! dept:"vancouver soft" title:engineer loc:bc -cat:home
+//! sam AND (slade OR spade)
+//! ```
+//!
+//! A search is compiled into the condition of a WHERE clause over the
+//! person view, with every value bound as a parameter.
+use std::iter::Peekable;
+use std::str::FromStr;
+use std::vec::IntoIter;
+use crate::read::person::number_suffix;
+use crate::traits::ToStaticStr;
+use crate::NumberString;
+use crate::PhoneCategory;
+use crate::PhoneError;
+use crate::QueryMode;
+
+// the most terms a search may have, and the deepest it may nest, so that
+// a search always compiles to a statement postgres will accept
+const MAX_TERMS: usize = 32;
+const MAX_DEPTH: usize = 16;
+
+/// A search for people, parsed from the query language
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Search {
+    /// People matching the term
+    Term(SearchTerm),
+    /// People not matching the search
+    Not(Box<Search>),
+    /// People matching all of the searches. An empty search matches everyone
+    And(Vec<Search>),
+    /// People matching any of the searches
+    Or(Vec<Search>),
+}
+
+/// A value, and what it is matched against
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum SearchTerm {
+    /// An unqualified value, matched against the name, login, title,
+    /// department, contacts and desks of people
+    Any(String),
+    /// `name:` the full name
+    Name(String),
+    /// `login:`
+    Login(String),
+    /// `title:`
+    Title(String),
+    /// `dept:` the department
+    Dept(String),
+    /// `contact:` an email address or chat handle
+    Contact(String),
+    /// `desk:` the building or room of a desk
+    Desk(String),
+    /// `loc:` the name, code or alias of the location of a phone or desk
+    Location(String),
+    /// `cat:` the category of a phone
+    Category(PhoneCategory),
+    /// `number:` the last digits of a phone number, or of the direct dial
+    /// number of an extension
+    Number(NumberString),
+}
+
+impl SearchTerm {
+    // the term for a value qualified by `field`, if any
+    fn new(field: Option<&str>, value: String) -> Result<Option<Self>, PhoneError> {
+        let term = match field.map(str::to_lowercase).as_deref() {
+            None => Self::Any(value),
+            Some("name") => Self::Name(value),
+            Some("login") => Self::Login(value),
+            Some("title") => Self::Title(value),
+            Some("dept") | Some("department") => Self::Dept(value),
+            Some("contact") => Self::Contact(value),
+            Some("desk") => Self::Desk(value),
+            Some("loc") | Some("location") => Self::Location(value),
+            Some("cat") | Some("category") => Self::Category(PhoneCategory::from_str(&value)?),
+            Some("number") => Self::Number(NumberString::from_str(&value)?),
+            Some(_) => return Ok(None),
+        };
+        Ok(Some(term))
+    }
+}
+
+impl FromStr for Search {
+    type Err = PhoneError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens = tokenize(s)?;
+        // searching for nothing finds everyone
+        if tokens.is_empty() {
+            return Ok(Self::And(Vec::new()));
+        }
+        let mut parser = Parser { input: s, tokens: tokens.into_iter().peekable(), depth: 0, terms: 0 };
+        let search = parser.any()?;
+        match parser.tokens.next() {
+            None => Ok(search),
+            Some(_) => Err(parser.invalid("unmatched )")),
+        }
+    }
+}
+
+impl Search {
+    /// Join command line arguments into a search. When a search is given
+    /// as several arguments, the shell has removed the quotes around values
+    /// containing spaces, eg `dept:"vancouver soft"`, so they are put back.
+    pub fn join_args<I, S>(args: I) -> String
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let args = args.into_iter().map(Into::into).collect::<Vec<String>>();
+        if args.len() < 2 {
+            return args.into_iter().next().unwrap_or_default();
+        }
+        args.into_iter()
+            .map(|arg| {
+                if !arg.contains(char::is_whitespace) || arg.contains('"') {
+                    return arg;
+                }
+                match arg.split_once(':') {
+                    Some((field, value)) if !field.contains(char::is_whitespace) => format!("{}:\"{}\"", field, value),
+                    _ => format!("\"{}\"", arg),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// The terms of the search, in the order they were written
+    pub fn terms(&self) -> Vec<&SearchTerm> {
+        match self {
+            Self::Term(term) => vec![term],
+            Self::Not(search) => search.terms(),
+            Self::And(searches) | Self::Or(searches) => searches.iter().flat_map(Search::terms).collect(),
+        }
+    }
+
+    /// The values people are ranked by: the unqualified values and names
+    /// searched for, other than those which must not match
+    pub fn ranking_values(&self) -> Vec<&str> {
+        match self {
+            Self::Term(SearchTerm::Any(value)) | Self::Term(SearchTerm::Name(value)) => vec![value],
+            Self::Term(_) | Self::Not(_) => Vec::new(),
+            Self::And(searches) | Self::Or(searches) => searches.iter().flat_map(Search::ranking_values).collect(),
+        }
+    }
+
+    /// The condition selecting the people matching the search from `view`,
+    /// with the parameters numbered from `first`. The parameters are
+    /// supplied by `params`, in the same order.
+    pub(crate) fn to_sql(&self, mode: &QueryMode, first: u8, view: &str, fuzzy: bool) -> String {
+        let mut cnt = first;
+        self.condition(mode, &mut cnt, view, fuzzy)
+    }
+
+    /// The number of parameters the search refers to
+    pub(crate) fn param_count(&self, fuzzy: bool) -> u8 {
+        self.terms().iter()
+            .map(|term| if fuzzy && matches!(term, SearchTerm::Any(_)) { 2 } else { 1 })
+            .sum()
+    }
+
+    /// The values to bind to the parameters of `to_sql`
+    pub(crate) fn params(&self, mode: &QueryMode, fuzzy: bool) -> Vec<String> {
+        let matched = |value: &str| match mode {
+            QueryMode::ILike | QueryMode::Like => format!("%{}%", value),
+            QueryMode::Exact => value.to_string(),
+        };
+        let mut params = Vec::new();
+        for term in self.terms() {
+            match term {
+                SearchTerm::Any(value) => {
+                    params.push(matched(value));
+                    if fuzzy {
+                        params.push(value.clone());
+                    }
+                }
+                SearchTerm::Name(value)
+                | SearchTerm::Login(value)
+                | SearchTerm::Title(value)
+                | SearchTerm::Dept(value)
+                | SearchTerm::Contact(value)
+                | SearchTerm::Desk(value) => params.push(matched(value)),
+                SearchTerm::Location(value) => params.push(value.clone()),
+                SearchTerm::Category(category) => params.push(category.to_static_str().to_string()),
+                SearchTerm::Number(number) => params.push(format!("%{}", number_suffix(number))),
+            }
+        }
+        params
+    }
+
+    fn condition(&self, mode: &QueryMode, cnt: &mut u8, view: &str, fuzzy: bool) -> String {
+        let join = |searches: &[Search], op: &str, cnt: &mut u8| {
+            let conditions = searches.iter()
+                .map(|search| search.condition(mode, cnt, view, fuzzy))
+                .collect::<Vec<_>>();
+            format!("({})", conditions.join(op))
+        };
+        match self {
+            Self::And(searches) if searches.is_empty() => "TRUE".to_string(),
+            Self::And(searches) => join(searches, " AND ", cnt),
+            Self::Or(searches) => join(searches, " OR ", cnt),
+            Self::Not(search) => format!("NOT {}", search.condition(mode, cnt, view, fuzzy)),
+            Self::Term(term) => {
+                let param = *cnt;
+                *cnt += 1;
+                match term {
+                    SearchTerm::Any(_) => {
+                        let fuzzy = if fuzzy {
+                            *cnt += 1;
+                            format!(" OR fuzzyMatch(${}, login, fullname)", param + 1)
+                        } else {
+                            String::new()
+                        };
+                        format!(
+                            "(fullname {mode} ${p} OR login {mode} ${p} OR title {mode} ${p} OR department {mode} ${p} \
+                            OR person_id IN (SELECT person_id FROM contact WHERE value {mode} ${p}) \
+                            OR person_id IN (SELECT person_id FROM desk WHERE building {mode} ${p} OR room {mode} ${p}){fuzzy})",
+                            mode = mode, p = param, fuzzy = fuzzy)
+                    }
+                    SearchTerm::Name(_) => format!("fullname {} ${}", mode, param),
+                    SearchTerm::Login(_) => format!("login {} ${}", mode, param),
+                    SearchTerm::Title(_) => format!("title {} ${}", mode, param),
+                    SearchTerm::Dept(_) => format!("department {} ${}", mode, param),
+                    SearchTerm::Contact(_) => format!("person_id IN (SELECT person_id FROM contact WHERE value {} ${})", mode, param),
+                    SearchTerm::Desk(_) => format!("person_id IN (SELECT person_id FROM desk WHERE building {mode} ${p} OR room {mode} ${p})", mode = mode, p = param),
+                    // locations are matched as Locations::parse does, by name, code or alias
+                    SearchTerm::Location(_) => {
+                        let locations = format!(
+                            "(SELECT name FROM location WHERE lower(name) = lower(${p}) OR lower(code) = lower(${p}) \
+                            OR lower(${p}) IN (SELECT lower(alias) FROM unnest(aliases) AS alias))", p = param);
+                        format!("(person_id IN (SELECT person_id FROM {view} WHERE location IN {locations}) \
+                            OR person_id IN (SELECT person_id FROM desk WHERE location IN {locations}))",
+                            view = view, locations = locations)
+                    }
+                    // phones are matched across all of the person's phones, so that
+                    // eg -cat:home finds the people without a home phone
+                    SearchTerm::Category(_) => format!("person_id IN (SELECT person_id FROM {} WHERE category = ${}::phonecategory)", view, param),
+                    SearchTerm::Number(_) => format!("person_id IN (SELECT person_id FROM {} WHERE number LIKE ${p} OR did LIKE ${p})", view, p = param),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Token {
+    Open,
+    Close,
+    Minus,
+    And,
+    Or,
+    Not,
+    /// A value, and the field qualifying it, if any
+    Word(Option<String>, String),
+}
+
+// split the search into tokens. Words run up to whitespace or a
+// parenthesis, except within quotes
+fn tokenize(s: &str) -> Result<Vec<Token>, PhoneError> {
+    let invalid = |reason: &str| PhoneError::InvalidSearch(s.trim().to_string(), reason.to_string());
+    let mut tokens = Vec::new();
+    let mut chars = s.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' | '-' => {
+                chars.next();
+                tokens.push(match c { '(' => Token::Open, ')' => Token::Close, _ => Token::Minus });
+            }
+            _ => {
+                let mut field = None;
+                let mut value = String::new();
+                let mut quoted = false;
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' {
+                        break;
+                    }
+                    chars.next();
+                    match c {
+                        '"' => {
+                            quoted = true;
+                            loop {
+                                match chars.next() {
+                                    Some('"') => break,
+                                    Some(c) => value.push(c),
+                                    None => return Err(invalid("missing closing quote")),
+                                }
+                            }
+                        }
+                        ':' if field.is_none() && !quoted => field = Some(std::mem::take(&mut value)),
+                        c => value.push(c),
+                    }
+                }
+                let token = match (&field, value.as_str()) {
+                    (None, "AND") if !quoted => Token::And,
+                    (None, "OR") if !quoted => Token::Or,
+                    (None, "NOT") if !quoted => Token::Not,
+                    (Some(field), "") => return Err(invalid(&format!("missing value for {}:", field))),
+                    (None, "") => return Err(invalid("empty quotes")),
+                    _ => Token::Word(field, value),
+                };
+                tokens.push(token);
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+// a recursive descent parser, binding OR loosest, then AND, then NOT
+struct Parser<'a> {
+    input: &'a str,
+    tokens: Peekable<IntoIter<Token>>,
+    depth: usize,
+    terms: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn invalid(&self, reason: &str) -> PhoneError {
+        PhoneError::InvalidSearch(self.input.trim().to_string(), reason.to_string())
+    }
+
+    fn peek(&mut self) -> Option<&Token> {
+        self.tokens.peek()
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        self.tokens.next()
+    }
+
+    // searches joined by OR
+    fn any(&mut self) -> Result<Search, PhoneError> {
+        let mut searches = vec![self.all()?];
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            searches.push(self.all()?);
+        }
+        Ok(if searches.len() == 1 { searches.remove(0) } else { Search::Or(searches) })
+    }
+
+    // searches joined by AND, or simply written one after another
+    fn all(&mut self) -> Result<Search, PhoneError> {
+        let mut searches = vec![self.not()?];
+        loop {
+            match self.peek() {
+                None | Some(Token::Close) | Some(Token::Or) => break,
+                Some(Token::And) => {
+                    self.next();
+                }
+                _ => (),
+            }
+            searches.push(self.not()?);
+        }
+        Ok(if searches.len() == 1 { searches.remove(0) } else { Search::And(searches) })
+    }
+
+    fn not(&mut self) -> Result<Search, PhoneError> {
+        match self.peek() {
+            Some(Token::Minus) | Some(Token::Not) => {
+                self.next();
+                self.nested(|parser| parser.not()).map(|search| Search::Not(Box::new(search)))
+            }
+            _ => self.term(),
+        }
+    }
+
+    fn term(&mut self) -> Result<Search, PhoneError> {
+        match self.next() {
+            Some(Token::Open) => {
+                let search = self.nested(|parser| parser.any())?;
+                match self.next() {
+                    Some(Token::Close) => Ok(search),
+                    _ => Err(self.invalid("missing )")),
+                }
+            }
+            Some(Token::Word(field, value)) => {
+                self.terms += 1;
+                if self.terms > MAX_TERMS {
+                    return Err(self.invalid(&format!("more than {} terms", MAX_TERMS)));
+                }
+                match SearchTerm::new(field.as_deref(), value)? {
+                    Some(term) => Ok(Search::Term(term)),
+                    None => Err(self.invalid(&format!(
+                        "unknown field {}:. Should be one of: name, login, title, dept, contact, desk, loc, cat, number",
+                        field.unwrap_or_default()))),
+                }
+            }
+            Some(Token::Close) => Err(self.invalid("unmatched )")),
+            Some(Token::And) | Some(Token::Or) => Err(self.invalid("AND and OR must be between two terms")),
+            Some(Token::Minus) | Some(Token::Not) | None => Err(self.invalid("missing a term")),
+        }
+    }
+
+    fn nested<F>(&mut self, parse: F) -> Result<Search, PhoneError>
+    where
+        F: FnOnce(&mut Self) -> Result<Search, PhoneError>,
+    {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(self.invalid("nested too deeply"));
+        }
+        let search = parse(self)?;
+        self.depth -= 1;
+        Ok(search)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PhoneErrorKind;
+
+    fn any(value: &str) -> Search {
+        Search::Term(SearchTerm::Any(value.into()))
+    }
+
+    #[test]
+    fn from_str_given_fields_and_negation_parses() {
+        let search = Search::from_str(r#"dept:"vancouver soft" title:engineer loc:bc -cat:home"#).unwrap();
+        assert_eq!(search, Search::And(vec![
+            Search::Term(SearchTerm::Dept("vancouver soft".into())),
+            Search::Term(SearchTerm::Title("engineer".into())),
+            Search::Term(SearchTerm::Location("bc".into())),
+            Search::Not(Box::new(Search::Term(SearchTerm::Category(PhoneCategory::Home)))),
+        ]));
+    }
+
+    #[test]
+    fn from_str_given_operators_binds_and_before_or() {
+        let search = Search::from_str("sam AND slade OR NOT (jean-luc OR \"AND\")").unwrap();
+        assert_eq!(search, Search::Or(vec![
+            Search::And(vec![any("sam"), any("slade")]),
+            Search::Not(Box::new(Search::Or(vec![any("jean-luc"), any("AND")]))),
+        ]));
+        assert_eq!(Search::from_str("  ").unwrap(), Search::And(vec![]));
+        assert_eq!(Search::from_str("a:b\"c:d\"").unwrap_err().kind(), PhoneErrorKind::InvalidSearch);
+        assert_eq!(Search::from_str("name:\"a:b\"").unwrap(), Search::Term(SearchTerm::Name("a:b".into())));
+    }
+
+    #[test]
+    fn from_str_given_bad_searches_fails() {
+        let too_deep = format!("{}sam{}", "(".repeat(MAX_DEPTH + 1), ")".repeat(MAX_DEPTH + 1));
+        let too_long = "sam ".repeat(MAX_TERMS + 1);
+        for value in &["sam)", "(sam", "sam AND", "OR sam", "-", "dept:", "\"sam", "\"\"", "phone:7091", &too_deep, &too_long] {
+            assert_eq!(Search::from_str(value).unwrap_err().kind(), PhoneErrorKind::InvalidSearch, "{}", value);
+        }
+        assert_eq!(Search::from_str("cat:mobile").unwrap_err().kind(), PhoneErrorKind::InvalidCategory);
+        assert_eq!(Search::from_str("number:x").unwrap_err().kind(), PhoneErrorKind::InvalidNumber);
+    }
+
+    #[test]
+    fn to_sql_given_search_numbers_params_in_order() {
+        let search = Search::from_str("sam -cat:home OR number:7091 OR name:slade").unwrap();
+        let sql = search.to_sql(&QueryMode::ILike, 2, "personview", true);
+        assert!(sql.starts_with("(((fullname ILIKE $2 OR"));
+        assert!(sql.contains("OR fuzzyMatch($3, login, fullname))"));
+        assert!(sql.contains(" AND NOT person_id IN (SELECT person_id FROM personview WHERE category = $4::phonecategory))"));
+        assert!(sql.ends_with(" OR person_id IN (SELECT person_id FROM personview WHERE number LIKE $5 OR did LIKE $5) OR fullname ILIKE $6)"));
+        assert_eq!(search.param_count(true), 5);
+        assert_eq!(search.params(&QueryMode::ILike, true), vec!["%sam%", "sam", "Home", "%7091", "%slade%"]);
+        assert_eq!(search.ranking_values(), vec!["sam", "slade"]);
+        assert_eq!(Search::And(vec![]).to_sql(&QueryMode::ILike, 2, "personview", false), "TRUE");
+    }
+
+    #[test]
+    fn join_args_given_unquoted_values_requotes_them() {
+        assert_eq!(Search::join_args(vec!["sam slade"]), "sam slade");
+        assert_eq!(Search::join_args(vec!["dept:vancouver soft", "title:engineer", "sam slade", "-cat:home"]),
+            r#"dept:"vancouver soft" title:engineer "sam slade" -cat:home"#);
+        assert_eq!(Search::join_args(vec![r#"dept:"a b" c"#, "d"]), r#"dept:"a b" c d"#);
+        assert_eq!(Search::join_args(Vec::<String>::new()), "");
+    }
+}
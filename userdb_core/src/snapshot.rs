@@ -0,0 +1,815 @@
+//! Snapshots of the whole userdb, for seeding and migrating databases.
+//!
+//! A snapshot is a json document holding every location, department, title,
+//! person, phone, the phones assigned to each person, and each person's
+//! contacts, manager and desks. Rows refer to one another
+//! by name, login, and number rather than by id, so a snapshot may be
+//! restored into a database whose ids differ from the one it was taken from.
+//!
+//! Version 2 added phone labels and primary flags. Version 1 snapshots
+//! are restored with no labels, and no primary phones. Version 3 added
+//! locations. Earlier snapshots are restored into the existing locations.
+//! Version 4 added contacts. Version 5 added the login of each person's
+//! manager. Earlier snapshots are restored without managers. Version 6
+//! added desks. Version 7 added each person's status and dates. Earlier
+//! snapshots are restored with everyone active. Version 8 added the
+//! calling code and trunk prefix of each location. Earlier snapshots are
+//! restored with locations dialed as their country is known to be.
+//!
+//! Numbers are normalized to E.164 as they are restored, using the country
+//! of their location, so snapshots taken before numbers were stored in
+//! E.164 are restored in the same form as the numbers already present.
+//!
+//! ```json
+//! {
+//!   "format": "userdb-snapshot",
+//!   "version": 8,
+//!   "created": "2020-12-11 10:31:44.120863-08",
+//!   "locations": [{"name": "Portland", "code": "pd", "aliases": [], "address": null, "timezone": "America/Los_Angeles", "country": "US", "calling_code": "1", "trunk_prefix": "1"}],
+//!   "departments": ["Supervisors"],
+//!   "titles": ["Animator"],
+//!   "people": [{"first": "Jane", "last": "Doe", "login": "jdoe", "department": "Supervisors", "title": "Animator", "manager": null, "status": "Active", "start_date": "2020-01-06", "end_date": null}],
+//!   "phones": [{"number": "1234", "category": "Extension", "location": "Portland", "label": null}],
+//!   "people_phones": [{"login": "jdoe", "number": "1234", "category": "Extension", "location": "Portland", "primary": true}],
+//!   "contacts": [{"login": "jdoe", "category": "Email", "value": "jdoe@example.com", "label": null, "primary": true}],
+//!   "desks": [{"login": "jdoe", "location": "Portland", "building": "A", "floor": "2", "room": "2-110"}]
+//! }
+//! ```
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgConnection;
+use sqlx::prelude::*;
+use std::fmt;
+use std::io::{Read, Write};
+use std::str::FromStr;
+
+use crate::prelude::*;
+use crate::{ContactCategory, Locations, NumberString, PersonStatus, PhoneCategory, UserDb, UserDbError};
+
+/// Identifies a json document as a snapshot
+pub const SNAPSHOT_FORMAT: &str = "userdb-snapshot";
+/// The version of the snapshot layout written by `dump`. `restore` reads
+/// this version and any earlier one.
+pub const SNAPSHOT_VERSION: u32 = 8;
+
+/// The contents of the userdb
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub format: String,
+    pub version: u32,
+    /// When the snapshot was taken, according to the database
+    pub created: String,
+    #[serde(default)]
+    pub locations: Vec<SnapshotLocation>,
+    pub departments: Vec<String>,
+    pub titles: Vec<String>,
+    pub people: Vec<SnapshotPerson>,
+    pub phones: Vec<SnapshotPhone>,
+    pub people_phones: Vec<SnapshotAssignment>,
+    #[serde(default)]
+    pub contacts: Vec<SnapshotContact>,
+    #[serde(default)]
+    pub desks: Vec<SnapshotDesk>,
+}
+
+/// A location, identified by its name
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct SnapshotLocation {
+    pub name: String,
+    pub code: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    pub address: Option<String>,
+    pub timezone: String,
+    pub country: String,
+    #[serde(default)]
+    pub calling_code: Option<String>,
+    #[serde(default)]
+    pub trunk_prefix: Option<String>,
+}
+
+/// A person, referring to their department and title by name
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct SnapshotPerson {
+    pub first: String,
+    pub last: String,
+    pub login: String,
+    pub department: String,
+    pub title: String,
+    /// The login of the person's manager
+    #[serde(default)]
+    pub manager: Option<String>,
+    #[serde(default)]
+    pub status: PersonStatus,
+    /// As YYYY-MM-DD
+    #[serde(default)]
+    pub start_date: Option<String>,
+    /// As YYYY-MM-DD
+    #[serde(default)]
+    pub end_date: Option<String>,
+}
+
+/// A phone, identified by its number, category and location
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct SnapshotPhone {
+    pub number: String,
+    pub category: PhoneCategory,
+    pub location: String,
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+/// A phone assigned to the person with `login`
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct SnapshotAssignment {
+    pub login: String,
+    pub number: String,
+    pub category: PhoneCategory,
+    pub location: String,
+    #[serde(default)]
+    #[sqlx(rename = "is_primary")]
+    pub primary: bool,
+}
+
+/// A contact of the person with `login`
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct SnapshotContact {
+    pub login: String,
+    pub category: ContactCategory,
+    pub value: String,
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default)]
+    #[sqlx(rename = "is_primary")]
+    pub primary: bool,
+}
+
+/// The desk of the person with `login`, at `location` if they have one
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct SnapshotDesk {
+    pub login: String,
+    #[serde(default)]
+    pub location: Option<String>,
+    #[serde(default)]
+    pub building: Option<String>,
+    #[serde(default)]
+    pub floor: Option<String>,
+    #[serde(default)]
+    pub room: Option<String>,
+}
+
+impl Snapshot {
+    /// Read a snapshot, checking that it is one, and that it is a
+    /// version we understand.
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, UserDbError> {
+        let invalid = |e: serde_json::Error| UserDbError::InvalidSnapshot(e.to_string());
+        let value: serde_json::Value = serde_json::from_reader(reader).map_err(invalid)?;
+        match value.get("format").and_then(|format| format.as_str()) {
+            Some(SNAPSHOT_FORMAT) => (),
+            _ => return Err(UserDbError::InvalidSnapshot(format!("missing \"format\": \"{}\"", SNAPSHOT_FORMAT))),
+        }
+        match value.get("version").and_then(|version| version.as_u64()) {
+            Some(version) if version <= SNAPSHOT_VERSION as u64 => (),
+            Some(version) => return Err(UserDbError::InvalidSnapshot(format!(
+                "version {} is newer than the supported version {}", version, SNAPSHOT_VERSION))),
+            None => return Err(UserDbError::InvalidSnapshot("missing version".into())),
+        }
+        serde_json::from_value(value).map_err(invalid)
+    }
+
+    /// Write the snapshot as json.
+    pub fn to_writer<W: Write>(&self, writer: W) -> Result<(), UserDbError> {
+        serde_json::to_writer_pretty(writer, self)?;
+        Ok(())
+    }
+}
+
+/// How `restore` treats rows which already exist in the database
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ConflictPolicy {
+    /// Leave existing rows alone
+    Skip,
+    /// Overwrite existing locations, people (including their status),
+    /// desks, phone and contact labels, and primary flags with the
+    /// snapshot's values
+    Update,
+    /// Abandon the restore
+    Fail,
+}
+
+impl FromStr for ConflictPolicy {
+    type Err = UserDbError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "skip" => Ok(Self::Skip),
+            "update" => Ok(Self::Update),
+            "fail" => Ok(Self::Fail),
+            _ => Err(UserDbError::InvalidConflictPolicy(s.to_string())),
+        }
+    }
+}
+
+impl ToStaticStr for ConflictPolicy {
+    fn to_static_str(&self) -> &'static str {
+        match self {
+            Self::Skip => "skip",
+            Self::Update => "update",
+            Self::Fail => "fail",
+        }
+    }
+}
+
+impl fmt::Display for ConflictPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_static_str())
+    }
+}
+
+/// The number of rows written to each table by `restore`
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct RestoreSummary {
+    pub locations: usize,
+    pub departments: usize,
+    pub titles: usize,
+    pub people: usize,
+    pub phones: usize,
+    pub people_phones: usize,
+    pub contacts: usize,
+    pub managers: usize,
+    pub desks: usize,
+}
+
+const SELECT_NOW: &str = "SELECT now()::text AS created;";
+
+const SELECT_LOCATIONS: &str = r"
+SELECT
+    name, code, aliases, address, timezone, country, calling_code, trunk_prefix
+FROM
+    location
+ORDER BY
+    name;
+";
+
+const SELECT_DEPARTMENTS: &str = "SELECT name FROM department ORDER BY name;";
+
+const SELECT_TITLES: &str = "SELECT name FROM title ORDER BY name;";
+
+const SELECT_PEOPLE: &str = r"
+SELECT
+    person.first, person.last, person.login,
+    department.name AS department,
+    title.name AS title,
+    manager.login AS manager,
+    person.status,
+    person.start_date::text AS start_date,
+    person.end_date::text AS end_date
+FROM
+    person
+    JOIN department ON department.id = person.department_id
+    JOIN title ON title.id = person.title_id
+    LEFT JOIN person AS manager ON manager.id = person.manager_id
+ORDER BY
+    person.login;
+";
+
+const SELECT_PHONES: &str = r"
+SELECT
+    number, category, location, label
+FROM
+    phone
+ORDER BY
+    number, category, location;
+";
+
+const SELECT_PEOPLE_PHONES: &str = r"
+SELECT
+    person.login, phone.number, phone.category, phone.location, people_phones.is_primary
+FROM
+    people_phones
+    JOIN person ON person.id = people_phones.person_id
+    JOIN phone ON phone.id = people_phones.phone_id
+ORDER BY
+    person.login, phone.number, phone.category, phone.location;
+";
+
+const SELECT_CONTACTS: &str = r"
+SELECT
+    person.login, contact.category, contact.value, contact.label, contact.is_primary
+FROM
+    contact
+    JOIN person ON person.id = contact.person_id
+ORDER BY
+    person.login, contact.category, contact.value;
+";
+
+const SELECT_DESKS: &str = r"
+SELECT
+    person.login, desk.location, desk.building, desk.floor, desk.room
+FROM
+    desk
+    JOIN person ON person.id = desk.person_id
+ORDER BY
+    person.login, desk.location NULLS FIRST;
+";
+
+#[derive(FromRow)]
+struct Name {
+    name: String,
+}
+
+#[derive(FromRow)]
+struct Created {
+    created: String,
+}
+
+/// Take a snapshot of the database. The tables are read in a single
+/// transaction, so the snapshot is consistent even if the database is
+/// being written to.
+pub async fn dump(db: &UserDb) -> Result<Snapshot, UserDbError> {
+    let mut tx = db.begin().await?;
+    sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ READ ONLY;")
+        .execute(&mut tx).await?;
+    let Created{created} = sqlx::query_as(SELECT_NOW).fetch_one(&mut tx).await?;
+    let locations = sqlx::query_as(SELECT_LOCATIONS).fetch_all(&mut tx).await?;
+    let departments: Vec<Name> = sqlx::query_as(SELECT_DEPARTMENTS).fetch_all(&mut tx).await?;
+    let titles: Vec<Name> = sqlx::query_as(SELECT_TITLES).fetch_all(&mut tx).await?;
+    let people = sqlx::query_as(SELECT_PEOPLE).fetch_all(&mut tx).await?;
+    let phones = sqlx::query_as(SELECT_PHONES).fetch_all(&mut tx).await?;
+    let people_phones = sqlx::query_as(SELECT_PEOPLE_PHONES).fetch_all(&mut tx).await?;
+    let contacts = sqlx::query_as(SELECT_CONTACTS).fetch_all(&mut tx).await?;
+    let desks = sqlx::query_as(SELECT_DESKS).fetch_all(&mut tx).await?;
+    tx.commit().await?;
+    Ok(Snapshot {
+        format: SNAPSHOT_FORMAT.into(),
+        version: SNAPSHOT_VERSION,
+        created,
+        locations,
+        departments: departments.into_iter().map(|Name{name}| name).collect(),
+        titles: titles.into_iter().map(|Name{name}| name).collect(),
+        people,
+        phones,
+        people_phones,
+        contacts,
+        desks,
+    })
+}
+
+// A restore statement for each table. The `{conflict}` placeholder is
+// filled in according to the ConflictPolicy.
+const INSERT_LOCATION: &str = r"
+INSERT INTO location
+    (name, code, aliases, address, timezone, country, calling_code, trunk_prefix)
+VALUES
+    ($1, $2, $3, $4, $5, $6, $7, $8)
+{conflict}
+RETURNING id;
+";
+
+const UPDATE_LOCATION: &str = r"
+ON CONFLICT (name) DO UPDATE SET
+    code = EXCLUDED.code,
+    aliases = EXCLUDED.aliases,
+    address = EXCLUDED.address,
+    timezone = EXCLUDED.timezone,
+    country = EXCLUDED.country,
+    calling_code = EXCLUDED.calling_code,
+    trunk_prefix = EXCLUDED.trunk_prefix";
+
+const INSERT_DEPARTMENT: &str = "INSERT INTO department (name) VALUES ($1) {conflict} RETURNING id;";
+
+const INSERT_TITLE: &str = "INSERT INTO title (name) VALUES ($1) {conflict} RETURNING id;";
+
+const INSERT_PERSON: &str = r"
+INSERT INTO person
+    (first, last, login, department_id, title_id, status, start_date, end_date)
+VALUES
+    ($1, $2, $3,
+        (SELECT id FROM department WHERE name = $4),
+        (SELECT id FROM title WHERE name = $5),
+        $6::personstatus, $7::date, $8::date)
+{conflict}
+RETURNING id;
+";
+
+const UPDATE_PERSON: &str = r"
+ON CONFLICT (login) DO UPDATE SET
+    first = EXCLUDED.first,
+    last = EXCLUDED.last,
+    department_id = EXCLUDED.department_id,
+    title_id = EXCLUDED.title_id,
+    status = EXCLUDED.status,
+    start_date = EXCLUDED.start_date,
+    end_date = EXCLUDED.end_date";
+
+// an unknown manager becomes the id 0, which no one has, so that
+// postgres rejects it
+const UPDATE_MANAGER: &str = r"
+UPDATE person SET
+    manager_id = CASE WHEN $2::text IS NULL THEN NULL
+        ELSE COALESCE((SELECT manager.id FROM person AS manager WHERE manager.login = $2), 0) END
+WHERE
+    login = $1;
+";
+
+// cleared ahead of UPDATE_MANAGER, so that managers may be swapped
+// without passing through a cycle
+const CLEAR_MANAGERS: &str = "UPDATE person SET manager_id = NULL WHERE login = ANY($1);";
+
+// Existing phones are looked up ahead of inserting, rather than left to
+// an ON CONFLICT clause, as the dial plan is checked on insert before
+// conflicts are, and phones predating the plan would fail it.
+const SELECT_PHONE: &str = r"
+SELECT
+    id
+FROM
+    phone
+WHERE
+    number = $1
+AND
+    category = $2::phonecategory
+AND
+    location = $3;
+";
+
+const INSERT_PHONE: &str = r"
+INSERT INTO phone
+    (number, category, location, label)
+VALUES
+    ($1, $2::phonecategory, $3, $4)
+RETURNING id;
+";
+
+const UPDATE_PHONE: &str = r"
+UPDATE
+    phone
+SET
+    label = $4
+WHERE
+    number = $1
+AND
+    category = $2::phonecategory
+AND
+    location = $3
+RETURNING id;
+";
+
+const INSERT_PERSON_PHONE: &str = r"
+INSERT INTO people_phones
+    (person_id, phone_id, is_primary)
+SELECT
+    person.id, phone.id, $5
+FROM
+    person, phone
+WHERE
+    person.login = $1
+AND
+    phone.number = $2
+AND
+    phone.category = $3::phonecategory
+AND
+    phone.location = $4
+{conflict}
+RETURNING person_id;
+";
+
+const UPDATE_PERSON_PHONE: &str = r"
+ON CONFLICT (person_id, phone_id) DO UPDATE SET
+    is_primary = EXCLUDED.is_primary";
+
+const INSERT_CONTACT: &str = r"
+INSERT INTO contact
+    (person_id, category, value, label, is_primary)
+SELECT
+    person.id, $2::contactcategory, $3, $4, $5
+FROM
+    person
+WHERE
+    person.login = $1
+{conflict}
+RETURNING id;
+";
+
+const UPDATE_CONTACT: &str = r"
+ON CONFLICT (person_id, category, value) DO UPDATE SET
+    label = EXCLUDED.label,
+    is_primary = EXCLUDED.is_primary";
+
+const INSERT_DESK: &str = r"
+INSERT INTO desk
+    (person_id, location, building, floor, room)
+SELECT
+    person.id, $2, $3, $4, $5
+FROM
+    person
+WHERE
+    person.login = $1
+{conflict}
+RETURNING id;
+";
+
+const UPDATE_DESK: &str = r"
+ON CONFLICT (person_id, (COALESCE(location, ''))) DO UPDATE SET
+    building = EXCLUDED.building,
+    floor = EXCLUDED.floor,
+    room = EXCLUDED.room";
+
+// the number in E.164, read as a national number of the country of its
+// location, unless it is an extension. Numbers at unknown locations are
+// left for postgres to reject
+fn e164(locations: &Locations, number: &str, category: &PhoneCategory, location: &str) -> Result<String, UserDbError> {
+    match locations.parse(location) {
+        Ok(location) => Ok(NumberString::from_str(number)?.normalize(category, location.country().as_ref())?.to_string()),
+        Err(_) => Ok(number.to_string()),
+    }
+}
+
+// fill in the conflict clause of a restore statement
+fn statement(template: &str, conflict: &str) -> String {
+    template.replace("{conflict}", conflict)
+}
+
+/// Load `snapshot` into the database in a single transaction. Rows which
+/// already exist are handled according to `policy`. When `dry_run` is set
+/// the transaction is rolled back, leaving the database untouched.
+pub async fn restore(
+    db: &UserDb,
+    snapshot: &Snapshot,
+    policy: ConflictPolicy,
+    dry_run: bool,
+) -> Result<RestoreSummary, UserDbError> {
+    let mut tx = db.begin().await?;
+    let summary = restore_tables(&mut tx, snapshot, policy).await?;
+    if dry_run {
+        tx.rollback().await?;
+    } else {
+        tx.commit().await?;
+    }
+    Ok(summary)
+}
+
+async fn restore_tables(
+    conn: &mut PgConnection,
+    snapshot: &Snapshot,
+    policy: ConflictPolicy,
+) -> Result<RestoreSummary, UserDbError> {
+    // departments and titles have nothing to update beyond their name
+    let skip = match policy {
+        ConflictPolicy::Fail => "",
+        _ => "ON CONFLICT DO NOTHING",
+    };
+    let update = |clause| match policy {
+        ConflictPolicy::Update => clause,
+        _ => skip,
+    };
+    let mut summary = RestoreSummary::default();
+
+    let insert = statement(INSERT_LOCATION, update(UPDATE_LOCATION));
+    for location in &snapshot.locations {
+        summary.locations += sqlx::query(&insert)
+            .bind(&location.name)
+            .bind(&location.code)
+            .bind(&location.aliases)
+            .bind(&location.address)
+            .bind(&location.timezone)
+            .bind(&location.country)
+            .bind(&location.calling_code)
+            .bind(&location.trunk_prefix)
+            .fetch_optional(&mut *conn).await
+            .map_err(|e| match UserDbError::from(e) {
+                UserDbError::InvalidTimezone(_) => UserDbError::InvalidTimezone(location.timezone.clone()),
+                UserDbError::InvalidCountryCode(_) => UserDbError::InvalidCountryCode(location.country.clone()),
+                UserDbError::InvalidCallingCode(_) => UserDbError::InvalidCallingCode(location.calling_code.clone().unwrap_or_default()),
+                UserDbError::InvalidTrunkPrefix(_) => UserDbError::InvalidTrunkPrefix(location.trunk_prefix.clone().unwrap_or_default()),
+                e => e,
+            })?
+            .map_or(0, |_| 1);
+    }
+
+    let insert = statement(INSERT_DEPARTMENT, skip);
+    for department in &snapshot.departments {
+        summary.departments += sqlx::query(&insert)
+            .bind(department)
+            .fetch_optional(&mut *conn).await?
+            .map_or(0, |_| 1);
+    }
+
+    let insert = statement(INSERT_TITLE, skip);
+    for title in &snapshot.titles {
+        summary.titles += sqlx::query(&insert)
+            .bind(title)
+            .fetch_optional(&mut *conn).await?
+            .map_or(0, |_| 1);
+    }
+
+    // the people whose manager is taken from the snapshot. Everyone
+    // when updating, otherwise only those added by this restore
+    let mut managed = Vec::new();
+    let insert = statement(INSERT_PERSON, update(UPDATE_PERSON));
+    for person in &snapshot.people {
+        let row = sqlx::query(&insert)
+            .bind(&person.first)
+            .bind(&person.last)
+            .bind(&person.login)
+            .bind(&person.department)
+            .bind(&person.title)
+            .bind(person.status.to_static_str())
+            .bind(&person.start_date)
+            .bind(&person.end_date)
+            .fetch_optional(&mut *conn).await
+            // postgres can only tell us which id was missing
+            .map_err(|e| match UserDbError::from(e) {
+                UserDbError::UnknownDepartment(_) => UserDbError::UnknownDepartment(person.department.clone()),
+                UserDbError::UnknownTitle(_) => UserDbError::UnknownTitle(person.title.clone()),
+                e => e,
+            })?;
+        if row.is_some() {
+            summary.people += 1;
+            managed.push(person);
+        } else if policy == ConflictPolicy::Update {
+            managed.push(person);
+        }
+    }
+
+    // managers are set once everyone exists, as they may be restored
+    // after the people reporting to them
+    if policy == ConflictPolicy::Update {
+        let logins = managed.iter().map(|person| person.login.clone()).collect::<Vec<_>>();
+        sqlx::query(CLEAR_MANAGERS).bind(&logins).execute(&mut *conn).await?;
+    }
+    for person in managed.iter().filter(|person| person.manager.is_some()) {
+        sqlx::query(UPDATE_MANAGER)
+            .bind(&person.login)
+            .bind(&person.manager)
+            .execute(&mut *conn).await
+            .map_err(|e| match UserDbError::from(e) {
+                UserDbError::UnknownManager(_) => UserDbError::UnknownManager(person.manager.clone().unwrap_or_default()),
+                e => e,
+            })?;
+        summary.managers += 1;
+    }
+
+    // the locations restored, along with those already present
+    let locations = Locations::load(&mut *conn).await?;
+    for phone in &snapshot.phones {
+        let number = e164(&locations, &phone.number, &phone.category, &phone.location)?;
+        let exists = sqlx::query(SELECT_PHONE)
+            .bind(&number)
+            .bind(phone.category.to_static_str())
+            .bind(&phone.location)
+            .fetch_optional(&mut *conn).await?
+            .is_some();
+        let statement = match (exists, policy) {
+            (true, ConflictPolicy::Skip) => continue,
+            (true, ConflictPolicy::Update) => UPDATE_PHONE,
+            (true, ConflictPolicy::Fail) => {
+                return Err(UserDbError::DuplicatePhone(format!("{} {} {}", number, phone.category, phone.location)));
+            }
+            (false, _) => INSERT_PHONE,
+        };
+        summary.phones += sqlx::query(statement)
+            .bind(number)
+            .bind(phone.category.to_static_str())
+            .bind(&phone.location)
+            .bind(&phone.label)
+            .fetch_optional(&mut *conn).await
+            .map_err(|e| match UserDbError::from(e) {
+                UserDbError::InvalidNumber(_) => UserDbError::InvalidNumber(phone.number.clone()),
+                e => e,
+            })?
+            .map_or(0, |_| 1);
+    }
+
+    let insert = statement(INSERT_PERSON_PHONE, update(UPDATE_PERSON_PHONE));
+    for assignment in &snapshot.people_phones {
+        summary.people_phones += sqlx::query(&insert)
+            .bind(&assignment.login)
+            .bind(e164(&locations, &assignment.number, &assignment.category, &assignment.location)?)
+            .bind(assignment.category.to_static_str())
+            .bind(&assignment.location)
+            .bind(assignment.primary)
+            .fetch_optional(&mut *conn).await?
+            .map_or(0, |_| 1);
+    }
+
+    let insert = statement(INSERT_CONTACT, update(UPDATE_CONTACT));
+    for contact in &snapshot.contacts {
+        summary.contacts += sqlx::query(&insert)
+            .bind(&contact.login)
+            .bind(contact.category.to_static_str())
+            .bind(&contact.value)
+            .bind(&contact.label)
+            .bind(contact.primary)
+            .fetch_optional(&mut *conn).await
+            .map_err(|e| match UserDbError::from(e) {
+                UserDbError::InvalidEmail(_) => UserDbError::InvalidEmail(contact.value.clone()),
+                e => e,
+            })?
+            .map_or(0, |_| 1);
+    }
+
+    let insert = statement(INSERT_DESK, update(UPDATE_DESK));
+    for desk in &snapshot.desks {
+        summary.desks += sqlx::query(&insert)
+            .bind(&desk.login)
+            .bind(&desk.location)
+            .bind(&desk.building)
+            .bind(&desk.floor)
+            .bind(&desk.room)
+            .fetch_optional(&mut *conn).await?
+            .map_or(0, |_| 1);
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Location;
+    use crate::UserDbErrorKind;
+
+    const SNAPSHOT: &str = r#"{
+        "format": "userdb-snapshot",
+        "version": 1,
+        "created": "2020-12-06 10:31:44.120863-08",
+        "departments": ["Supervisors"],
+        "titles": ["Animator"],
+        "people": [{"first": "Jane", "last": "Doe", "login": "jdoe", "department": "Supervisors", "title": "Animator"}],
+        "phones": [{"number": "1234", "category": "Extension", "location": "Portland"}],
+        "people_phones": [{"login": "jdoe", "number": "1234", "category": "Extension", "location": "Portland"}]
+    }"#;
+
+    #[test]
+    fn from_reader_round_trips() {
+        let snapshot = Snapshot::from_reader(SNAPSHOT.as_bytes()).unwrap();
+        assert_eq!(snapshot.people[0].login, "jdoe");
+        assert_eq!(snapshot.phones[0].category, PhoneCategory::Extension);
+        let mut json = Vec::new();
+        snapshot.to_writer(&mut json).unwrap();
+        assert_eq!(Snapshot::from_reader(json.as_slice()).unwrap(), snapshot);
+    }
+
+    // whether a snapshot holds the default of part of the layout
+    type Defaulted = fn(&Snapshot) -> bool;
+
+    #[test]
+    fn from_reader_given_older_versions_defaults_newer_fields() {
+        // the version each part of the layout was added in
+        let tests: &[(u32, &str, Defaulted)] = &[
+            (2, "labels and primary", |s| s.phones[0].label.is_none() && !s.people_phones[0].primary),
+            (3, "locations", |s| s.locations.is_empty()),
+            (4, "contacts", |s| s.contacts.is_empty()),
+            (5, "managers", |s| s.people[0].manager.is_none()),
+            (6, "desks", |s| s.desks.is_empty()),
+            (7, "status", |s| s.people[0].status == PersonStatus::Active && s.people[0].start_date.is_none()),
+            (8, "dialing", |s| s.locations.iter().all(|l| l.calling_code.is_none() && l.trunk_prefix.is_none())),
+        ];
+        for version in 1..SNAPSHOT_VERSION {
+            let older = SNAPSHOT.replace("\"version\": 1", &format!("\"version\": {}", version));
+            let snapshot = Snapshot::from_reader(older.as_bytes()).unwrap();
+            for (added, name, defaulted) in tests.iter().filter(|(added, ..)| version < *added) {
+                assert!(defaulted(&snapshot), "version {} should default {}, added in {}", version, name, added);
+            }
+        }
+    }
+
+    #[test]
+    fn from_reader_given_other_json_fails() {
+        let result = Snapshot::from_reader(r#"{"version": 1}"#.as_bytes()).unwrap_err().kind();
+        assert_eq!(result, UserDbErrorKind::InvalidSnapshot);
+    }
+
+    #[test]
+    fn from_reader_given_newer_version_fails() {
+        let newer = SNAPSHOT.replace("\"version\": 1", "\"version\": 99");
+        let result = Snapshot::from_reader(newer.as_bytes()).unwrap_err().kind();
+        assert_eq!(result, UserDbErrorKind::InvalidSnapshot);
+    }
+
+    #[test]
+    fn e164_given_national_numbers_uses_country_of_location() {
+        let locations = Locations::new(vec![Location {
+            id: 1,
+            name: "Portland".into(),
+            code: "pd".into(),
+            aliases: vec![],
+            address: None,
+            timezone: "America/Los_Angeles".into(),
+            country: "US".into(),
+            calling_code: Some("1".into()),
+            trunk_prefix: Some("1".into()),
+        }]);
+        let (cell, ext) = (PhoneCategory::Cell, PhoneCategory::Extension);
+        assert_eq!(e164(&locations, "503-555-1212", &cell, "Portland").unwrap(), "+15035551212");
+        assert_eq!(e164(&locations, "+15035551212", &cell, "Portland").unwrap(), "+15035551212");
+        assert_eq!(e164(&locations, "1234", &ext, "Portland").unwrap(), "1234");
+        assert_eq!(e164(&locations, "12345", &ext, "Portland").unwrap(), "12345");
+        assert_eq!(e164(&locations, "5035551212", &cell, "Nowhere").unwrap(), "5035551212");
+        assert_eq!(e164(&locations, "1234", &cell, "Portland").unwrap_err().kind(), UserDbErrorKind::Phone);
+    }
+
+    #[test]
+    fn conflict_policy_from_str_given_bad_input_fails() {
+        let result = ConflictPolicy::from_str("merge").unwrap_err().kind();
+        assert_eq!(result, UserDbErrorKind::InvalidConflictPolicy);
+    }
+}
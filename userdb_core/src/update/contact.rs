@@ -0,0 +1,133 @@
+use sqlx::FromRow;
+use sqlx::{Executor, Postgres};
+use crate::prelude::*;
+use crate::ContactCategory;
+use crate::UserDbError;
+
+pub mod id {
+    use super::*;
+    /// A datastructure intended to be used to capture the
+    /// changes requested for a specific contact given its id in the database
+    #[derive(Debug)]
+    pub struct ContactUpdate {
+        pub id: i32,
+        pub category: Option<ContactCategory>,
+        pub value: Option<String>,
+        /// A new label. An empty label clears the existing one
+        pub label: Option<String>,
+        /// Mark or unmark the contact as primary
+        pub primary: Option<bool>,
+    }
+
+    impl Updateable for ContactUpdate {
+        fn is_empty(&self) -> bool {
+            self.category.is_none() &&
+            self.value.is_none() &&
+            self.label.is_none() &&
+            self.primary.is_none()
+        }
+
+        fn update(&self) -> String {
+            let mut retval = r"
+            UPDATE
+                contact".to_owned();
+            // count starts at 1, but we are already using $1 in the
+            // where clause. So our first variable will be $2
+            let mut cnt = 2;
+            let mut set = "\nSET\n\t";
+            if self.category.is_some() {
+                retval = format!("{}{}category = ${}::contactcategory",retval,set,cnt);
+                set = ",";
+                cnt +=1;
+            }
+            if self.value.is_some() {
+                retval = format!("{}{}value = ${}",retval,set,cnt);
+                set = ",";
+                cnt +=1;
+            }
+            if self.label.is_some() {
+                retval = format!("{}{}label = NULLIF(${}, '')",retval,set,cnt);
+                set = ",";
+                cnt +=1;
+            }
+            if self.primary.is_some() {
+                retval = format!("{}{}is_primary = ${}",retval,set,cnt);
+            }
+            retval += "\nWHERE\n\tid = $1\nRETURNING id";
+            retval
+        }
+    }
+
+    impl ContactUpdate {
+        /// Create a new instance of the ContactUpdate struct with the
+        /// provided contact id
+        pub fn new(id: i32) -> Self {
+            Self {
+                id,
+                category: None,
+                value: None,
+                label: None,
+                primary: None,
+            }
+        }
+        /// Optionally set the category
+        pub fn category(mut self, category: Option<ContactCategory>) -> Self {
+            self.category = category;
+            self
+        }
+        /// Optionally set the address or handle
+        pub fn value(mut self, value: Option<String>) -> Self {
+            self.value = value;
+            self
+        }
+        /// Optionally set the label
+        pub fn label(mut self, label: Option<String>) -> Self {
+            self.label = label;
+            self
+        }
+        /// Optionally mark or unmark the contact as primary
+        pub fn primary(mut self, primary: Option<bool>) -> Self {
+            self.primary = primary;
+            self
+        }
+    }
+
+    #[derive(FromRow)]
+    struct Rval {
+        id: i32
+    }
+
+    /// Update a contact, based on the supplied values.
+    pub async fn update<'c, E>(executor: E, inputs: ContactUpdate) -> Result<i32, UserDbError>
+    where
+        E: Executor<'c, Database = Postgres>,
+    {
+        let update_statement = inputs.update();
+        let missing = format!("Contact with id {}", inputs.id);
+        let ContactUpdate{id, category, value, label, primary} = inputs;
+        let value = value.map(|value| value.trim().to_string());
+        let mut update = sqlx::query_as(&update_statement).bind(id);
+
+        if let Some(category) = category {
+            update = update.bind(category.to_static_str());
+        }
+        if let Some(value) = &value {
+            update = update.bind(value.clone());
+        }
+        if let Some(label) = label {
+            update = update.bind(label);
+        }
+        if let Some(primary) = primary {
+            update = update.bind(primary);
+        }
+        match update.fetch_optional(executor).await {
+            Ok(Some(Rval{id})) => Ok(id),
+            Ok(None) => Err(UserDbError::NotFound(missing)),
+            Err(e) => Err(match UserDbError::from(e) {
+                // without a new value, the category changed to one of the emails
+                UserDbError::InvalidEmail(_) => UserDbError::InvalidEmail(value.unwrap_or(missing)),
+                e => e,
+            }),
+        }
+    }
+}
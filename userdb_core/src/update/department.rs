@@ -1,6 +1,35 @@
 use sqlx::FromRow;
+use sqlx::{Executor, Postgres};
 use crate::prelude::*;
+use crate::UserDbError;
+
+const MERGE: &str = r"
+SELECT
+    *
+FROM
+    mergeDepartment($1, $2) AS moved;
+";
+
+#[derive(FromRow)]
+struct Merged {
+    moved: i32
+}
 
+/// Move everyone in the department named `source` into the department
+/// named `destination`, then delete `source`. Returns the number of
+/// people moved.
+pub async fn merge<'c, E, I, J>(executor: E, source: I, destination: J) -> Result<i32, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+    I: AsRef<str>,
+    J: AsRef<str>,
+{
+    let Merged{moved} = sqlx::query_as(MERGE)
+    .bind(source.as_ref())
+    .bind(destination.as_ref())
+    .fetch_one(executor).await?;
+    Ok(moved)
+}
 
 pub mod id {
     use super::*;
@@ -19,7 +48,7 @@ pub mod id {
 
         fn update(&self) -> String {
             //let  retval = "UPDATE\n\tdepartment\n\tSET\n\t\tname = $2\nWHERE\n\tid = $1\nRETURNING id".to_owned() ;
-            let retval = r"
+            r"
             UPDATE
                 department
             SET
@@ -27,8 +56,7 @@ pub mod id {
             WHERE
                 id = $1
             RETURNING id
-            ".to_string();          
-            retval
+            ".to_string()
         }
     }
 
@@ -46,21 +74,27 @@ pub mod id {
 
     #[derive(FromRow)]
     struct Rval {
-        id: Option<i32>
+        id: i32
     }
 
     /// Update a department, based on the supplied values.
-    pub async fn update(pool: &sqlx::PgPool, inputs: DepartmentUpdate) -> Result<Option<i32>, sqlx::Error> {  
+    pub async fn update<'c, E>(executor: E, inputs: DepartmentUpdate) -> Result<i32, UserDbError>
+    where
+        E: Executor<'c, Database = Postgres>,
+    {  
 
         let update_statement = inputs.update();
+        let missing = format!("Department with id {}", inputs.id);
 
         let DepartmentUpdate{id, name} = inputs;
         let update = sqlx::query_as(&update_statement)
                                 .bind(id)
                                 .bind(name);
         
-        let Rval{id} = update.fetch_one(pool).await?;
-        Ok(id)
+        match update.fetch_optional(executor).await? {
+            Some(Rval{id}) => Ok(id),
+            None => Err(UserDbError::NotFound(missing)),
+        }
     }
 }
 
@@ -83,7 +117,7 @@ pub mod name {
         }
 
         fn update(&self) -> String {
-            let  retval = r"
+            r"
             UPDATE
                 department
             SET
@@ -94,9 +128,7 @@ pub mod name {
                        FROM 
                             department 
                        WHERE name = $1)
-            RETURNING id".to_owned() ;
-                          
-            retval
+            RETURNING id".to_owned()
         }
     }
 
@@ -125,13 +157,17 @@ pub mod name {
 
     #[derive(FromRow)]
     struct Rval {
-        id: Option<i32>
+        id: i32
     }
 
     /// Update a department, based on the supplied values.
-    pub async fn update(pool: &sqlx::PgPool, inputs: DepartmentUpdate) -> Result<Option<i32>, sqlx::Error> {  
+    pub async fn update<'c, E>(executor: E, inputs: DepartmentUpdate) -> Result<i32, UserDbError>
+    where
+        E: Executor<'c, Database = Postgres>,
+    {  
 
         let update_statement = inputs.update();
+        let missing = format!("Department '{}'", inputs.name);
 
         let DepartmentUpdate{name, new_name} = inputs;
 
@@ -139,7 +175,9 @@ pub mod name {
                                 .bind(name)
                                 .bind(new_name);
         
-        let Rval{id} = update.fetch_one(pool).await?;
-        Ok(id)
+        match update.fetch_optional(executor).await? {
+            Some(Rval{id}) => Ok(id),
+            None => Err(UserDbError::NotFound(missing)),
+        }
     }
 }
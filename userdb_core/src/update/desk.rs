@@ -0,0 +1,113 @@
+//! Set where a person sits. Desks are created as needed, so there is no
+//! separate create.
+use sqlx::FromRow;
+use sqlx::{Executor, Postgres};
+use crate::prelude::*;
+use crate::{DeskOwner, Location, UserDbError};
+
+/// A datastructure intended to be used to capture the changes requested
+/// for the desk of a person at a location, or their desk without one
+#[derive(Debug)]
+pub struct DeskUpdate {
+    pub owner: DeskOwner,
+    pub location: Option<Location>,
+    /// A new building. An empty building clears the existing one
+    pub building: Option<String>,
+    /// A new floor. An empty floor clears the existing one
+    pub floor: Option<String>,
+    /// A new room or desk. An empty room clears the existing one
+    pub room: Option<String>,
+}
+
+impl Updateable for DeskUpdate {
+    fn is_empty(&self) -> bool {
+        self.building.is_none() &&
+        self.floor.is_none() &&
+        self.room.is_none()
+    }
+
+    // $1 identifies the person and $2 is the location. Unlike the other
+    // updates, every field is bound, with NULL leaving it as it was
+    fn update(&self) -> String {
+        let person = match self.owner {
+            DeskOwner::Id(_) => "id = $1",
+            DeskOwner::Login(_) => "login = $1",
+        };
+        format!(r"
+        INSERT INTO desk
+            (person_id, location, building, floor, room)
+        SELECT
+            id, $2, NULLIF($3, ''), NULLIF($4, ''), NULLIF($5, '')
+        FROM
+            person
+        WHERE
+            {}
+        ON CONFLICT (person_id, (COALESCE(location, ''))) DO UPDATE SET
+            building = CASE WHEN $3 IS NULL THEN desk.building ELSE EXCLUDED.building END,
+            floor = CASE WHEN $4 IS NULL THEN desk.floor ELSE EXCLUDED.floor END,
+            room = CASE WHEN $5 IS NULL THEN desk.room ELSE EXCLUDED.room END
+        RETURNING id", person)
+    }
+}
+
+impl DeskUpdate {
+    /// Create a new instance of the DeskUpdate struct for the desk of
+    /// `owner`
+    pub fn new(owner: DeskOwner) -> Self {
+        Self {
+            owner,
+            location: None,
+            building: None,
+            floor: None,
+            room: None,
+        }
+    }
+    /// Optionally set the location of the desk
+    pub fn location(mut self, location: Option<Location>) -> Self {
+        self.location = location;
+        self
+    }
+    /// Optionally set the building
+    pub fn building(mut self, building: Option<String>) -> Self {
+        self.building = building;
+        self
+    }
+    /// Optionally set the floor
+    pub fn floor(mut self, floor: Option<String>) -> Self {
+        self.floor = floor;
+        self
+    }
+    /// Optionally set the room or desk
+    pub fn room(mut self, room: Option<String>) -> Self {
+        self.room = room;
+        self
+    }
+}
+
+#[derive(FromRow)]
+struct Rval {
+    id: i32
+}
+
+/// Update the desk of a person at a location, creating it if it does not
+/// exist yet, and return its id.
+pub async fn update<'c, E>(executor: E, inputs: DeskUpdate) -> Result<i32, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let update_statement = inputs.update();
+    let DeskUpdate{owner, location, building, floor, room} = inputs;
+    let trim = |value: Option<String>| value.map(|value| value.trim().to_string());
+    let mut update = sqlx::query_as(&update_statement);
+    update = match &owner {
+        DeskOwner::Id(id) => update.bind(*id),
+        DeskOwner::Login(login) => update.bind(login.clone()),
+    };
+    let rval: Option<Rval> = update
+        .bind(location.map(|location| location.name))
+        .bind(trim(building))
+        .bind(trim(floor))
+        .bind(trim(room))
+        .fetch_optional(executor).await?;
+    rval.map(|Rval{id}| id).ok_or_else(|| UserDbError::NotFound(owner.to_string()))
+}
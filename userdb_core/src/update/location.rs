@@ -0,0 +1,201 @@
+use sqlx::FromRow;
+use sqlx::{Executor, Postgres};
+use crate::prelude::*;
+use crate::UserDbError;
+
+pub mod id {
+    use super::*;
+    /// A datastructure intended to be used to capture the 
+    /// changes requested for a specific location given its id in the database
+    #[derive(Debug)]
+    pub struct LocationUpdate {
+        pub id: i32,
+        pub name: Option<String>,
+        pub code: Option<String>,
+        /// Replaces the existing aliases
+        pub aliases: Option<Vec<String>>,
+        /// A new address. An empty address clears the existing one
+        pub address: Option<String>,
+        pub timezone: Option<String>,
+        pub country: Option<String>,
+        /// A new calling code. An empty calling code clears the existing one
+        pub calling_code: Option<String>,
+        /// A new trunk prefix. An empty trunk prefix clears the existing one
+        pub trunk_prefix: Option<String>,
+    }
+
+    impl Updateable for LocationUpdate {
+        fn is_empty(&self) -> bool {
+            self.name.is_none() &&
+            self.code.is_none() &&
+            self.aliases.is_none() &&
+            self.address.is_none() &&
+            self.timezone.is_none() &&
+            self.country.is_none() &&
+            self.calling_code.is_none() &&
+            self.trunk_prefix.is_none()
+        }
+
+        fn update(&self) -> String {
+            let mut retval = r"
+            UPDATE 
+                location".to_owned();
+            // count starts at 1, but we are already using $1 in the
+            // where clause. So our first variable will be $2
+            let mut cnt = 2;
+            let mut set = "\nSET\n\t";
+            if self.name.is_some() {
+                retval = format!("{}{}name = ${}",retval,set,cnt);
+                set = ",";
+                cnt +=1;
+            }
+            if self.code.is_some() {
+                retval = format!("{}{}code = ${}",retval,set,cnt);
+                set = ",";
+                cnt +=1;
+            }
+            if self.aliases.is_some() {
+                retval = format!("{}{}aliases = ${}",retval,set,cnt);
+                set = ",";
+                cnt +=1;
+            }
+            if self.address.is_some() {
+                retval = format!("{}{}address = NULLIF(${}, '')",retval,set,cnt);
+                set = ",";
+                cnt +=1;
+            }
+            if self.timezone.is_some() {
+                retval = format!("{}{}timezone = ${}",retval,set,cnt);
+                set = ",";
+                cnt +=1;
+            }
+            if self.country.is_some() {
+                retval = format!("{}{}country = UPPER(${})",retval,set,cnt);
+                set = ",";
+                cnt +=1;
+            }
+            if self.calling_code.is_some() {
+                retval = format!("{}{}calling_code = NULLIF(${}, '')",retval,set,cnt);
+                set = ",";
+                cnt +=1;
+            }
+            if self.trunk_prefix.is_some() {
+                retval = format!("{}{}trunk_prefix = NULLIF(${}, '')",retval,set,cnt);
+            }
+            retval += "\nWHERE\n\tid = $1\nRETURNING id";
+            retval
+        }
+    }
+
+    impl LocationUpdate {
+        /// Create a new instance of the LocationUpdate struct with the 
+        /// provided location id
+        pub fn new(id: i32) -> Self {
+            Self {
+                id,
+                name: None,
+                code: None,
+                aliases: None,
+                address: None,
+                timezone: None,
+                country: None,
+                calling_code: None,
+                trunk_prefix: None,
+            }
+        }
+        /// Optionally set the name
+        pub fn name(mut self, name: Option<String>) -> Self {
+            self.name = name;
+            self
+        }
+        /// Optionally set the code
+        pub fn code(mut self, code: Option<String>) -> Self {
+            self.code = code;
+            self
+        }
+        /// Optionally replace the aliases
+        pub fn aliases(mut self, aliases: Option<Vec<String>>) -> Self {
+            self.aliases = aliases;
+            self
+        }
+        /// Optionally set the address
+        pub fn address(mut self, address: Option<String>) -> Self {
+            self.address = address;
+            self
+        }
+        /// Optionally set the timezone
+        pub fn timezone(mut self, timezone: Option<String>) -> Self {
+            self.timezone = timezone;
+            self
+        }
+        /// Optionally set the country
+        pub fn country(mut self, country: Option<String>) -> Self {
+            self.country = country;
+            self
+        }
+        /// Optionally set the calling code
+        pub fn calling_code(mut self, calling_code: Option<String>) -> Self {
+            self.calling_code = calling_code;
+            self
+        }
+        /// Optionally set the trunk prefix
+        pub fn trunk_prefix(mut self, trunk_prefix: Option<String>) -> Self {
+            self.trunk_prefix = trunk_prefix;
+            self
+        }
+    }
+
+    #[derive(FromRow)]
+    struct Rval {
+        id: i32
+    }
+
+    /// Update a location, based on the supplied values. Renaming a
+    /// location carries its phones and dial plan along with it.
+    pub async fn update<'c, E>(executor: E, inputs: LocationUpdate) -> Result<i32, UserDbError>
+    where
+        E: Executor<'c, Database = Postgres>,
+    {
+        let update_statement = inputs.update();
+        let missing = format!("Location with id {}", inputs.id);
+        let LocationUpdate{id, name, code, aliases, address, timezone, country, calling_code, trunk_prefix} = inputs;
+        let mut update = sqlx::query_as(&update_statement).bind(id);
+
+        if let Some(name) = name {
+            update = update.bind(name);
+        }
+        if let Some(code) = code {
+            update = update.bind(code);
+        }
+        if let Some(aliases) = aliases {
+            update = update.bind(aliases);
+        }
+        if let Some(address) = address {
+            update = update.bind(address);
+        }
+        if let Some(timezone) = &timezone {
+            update = update.bind(timezone);
+        }
+        if let Some(country) = &country {
+            update = update.bind(country);
+        }
+        if let Some(calling_code) = &calling_code {
+            update = update.bind(calling_code);
+        }
+        if let Some(trunk_prefix) = &trunk_prefix {
+            update = update.bind(trunk_prefix);
+        }
+        let result = update.fetch_optional(executor).await
+        .map_err(|e| match UserDbError::from(e) {
+            UserDbError::InvalidTimezone(_) => UserDbError::InvalidTimezone(timezone.clone().unwrap_or_default()),
+            UserDbError::InvalidCountryCode(_) => UserDbError::InvalidCountryCode(country.clone().unwrap_or_default()),
+            UserDbError::InvalidCallingCode(_) => UserDbError::InvalidCallingCode(calling_code.clone().unwrap_or_default()),
+            UserDbError::InvalidTrunkPrefix(_) => UserDbError::InvalidTrunkPrefix(trunk_prefix.clone().unwrap_or_default()),
+            e => e,
+        })?;
+        match result {
+            Some(Rval{id}) => Ok(id),
+            None => Err(UserDbError::NotFound(missing)),
+        }
+    }
+}
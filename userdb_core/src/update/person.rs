@@ -1,6 +1,35 @@
 //use crate::errors::PhoneError;
 use sqlx::FromRow;
+use sqlx::{Executor, Postgres};
+use std::str::FromStr;
 use crate::prelude::*;
+use crate::{Date, PersonStatus, UserDbError};
+
+// the manager is looked up by login. An unknown login becomes the id 0,
+// which no one has, so that postgres rejects it rather than quietly
+// removing the manager
+fn manager_id(cnt: i32) -> String {
+    format!(
+        "CASE WHEN ${0} = '' THEN NULL ELSE COALESCE((SELECT manager.id FROM person AS manager WHERE manager.login = ${0}), 0) END",
+        cnt)
+}
+
+// check the dates before handing them to postgres. Empty dates clear
+// the existing ones
+fn check_date(date: &Option<String>) -> Result<(), UserDbError> {
+    match date {
+        Some(date) if !date.is_empty() => Date::from_str(date).map(|_| ()).map_err(UserDbError::from),
+        _ => Ok(()),
+    }
+}
+
+// postgres can only tell us which id was missing
+fn unknown_manager(error: sqlx::Error, manager: Option<String>) -> UserDbError {
+    match UserDbError::from(error) {
+        UserDbError::UnknownManager(_) => UserDbError::UnknownManager(manager.unwrap_or_default()),
+        e => e,
+    }
+}
 
 pub mod id {
     use super::*;
@@ -13,7 +42,17 @@ pub mod id {
         pub first: Option<String>,
         pub last: Option<String>,
         pub department: Option<i32>,
-        pub title: Option<i32>
+        pub title: Option<i32>,
+        /// The login of the person's new manager. An empty login
+        /// removes their manager
+        pub manager: Option<String>,
+        pub status: Option<PersonStatus>,
+        /// The date the person started, as YYYY-MM-DD. An empty date
+        /// clears it
+        pub start_date: Option<String>,
+        /// The date the person finished, as YYYY-MM-DD. An empty date
+        /// clears it
+        pub end_date: Option<String>,
     }
 
     impl Updateable for PersonUpdate {
@@ -22,7 +61,11 @@ pub mod id {
             self.first.is_none() && 
             self.last.is_none() && 
             self.department.is_none() &&
-            self.title.is_none()
+            self.title.is_none() &&
+            self.manager.is_none() &&
+            self.status.is_none() &&
+            self.start_date.is_none() &&
+            self.end_date.is_none()
         }
 
         fn update(&self) -> String {
@@ -55,8 +98,26 @@ pub mod id {
             }
             if self.title.is_some() {
                 retval = format!("{}{}title_id = ${}",retval,set,cnt);
-                //set = ",";
-                //cnt +=1;
+                set = ",";
+                cnt +=1;
+            }
+            if self.manager.is_some() {
+                retval = format!("{}{}manager_id = {}",retval,set,manager_id(cnt));
+                set = ",";
+                cnt +=1;
+            }
+            if self.status.is_some() {
+                retval = format!("{}{}status = ${}::personstatus",retval,set,cnt);
+                set = ",";
+                cnt +=1;
+            }
+            if self.start_date.is_some() {
+                retval = format!("{}{}start_date = NULLIF(${}, '')::date",retval,set,cnt);
+                set = ",";
+                cnt +=1;
+            }
+            if self.end_date.is_some() {
+                retval = format!("{}{}end_date = NULLIF(${}, '')::date",retval,set,cnt);
             }
             retval += "\nWHERE\n\tid = $1\nRETURNING id";
             retval
@@ -72,6 +133,10 @@ pub mod id {
                 last: None,
                 department: None,
                 title: None,
+                manager: None,
+                status: None,
+                start_date: None,
+                end_date: None,
             }
         }
         /// Set the login
@@ -99,39 +164,79 @@ pub mod id {
             self.title = title;
             self
         }
+        /// Set the manager's login. An empty login removes the manager
+        pub fn manager(mut self, manager: Option<String>) -> Self {
+            self.manager = manager;
+            self
+        }
+        /// Set the status
+        pub fn status(mut self, status: Option<PersonStatus>) -> Self {
+            self.status = status;
+            self
+        }
+        /// Set the start date. An empty date clears it
+        pub fn start_date(mut self, start_date: Option<String>) -> Self {
+            self.start_date = start_date;
+            self
+        }
+        /// Set the end date. An empty date clears it
+        pub fn end_date(mut self, end_date: Option<String>) -> Self {
+            self.end_date = end_date;
+            self
+        }
     }
 
 
     #[derive(FromRow)]
     struct Rval {
-        id: Option<i32>
+        id: i32
     }
 
     /// Update a person, based on the supplied values.
-    pub async fn update(pool: &sqlx::PgPool, inputs: PersonUpdate) -> Result<Option<i32>, sqlx::Error> {  
+    pub async fn update<'c, E>(executor: E, inputs: PersonUpdate) -> Result<i32, UserDbError>
+    where
+        E: Executor<'c, Database = Postgres>,
+    {  
 
         let update_statement = inputs.update();
+        let missing = format!("Person with id {}", inputs.id);
         //println!("{}", &update_statement);
-        let PersonUpdate{id, login, first, last, department, title} = inputs;
+        let PersonUpdate{id, login, first, last, department, title, manager, status, start_date, end_date} = inputs;
+        check_date(&start_date)?;
+        check_date(&end_date)?;
         let mut update= sqlx::query_as(&update_statement).bind(id);
         
-        if login.is_some() {
-            update = update.bind(login.unwrap());
+        if let Some(login) = login {
+            update = update.bind(login);
+        }
+        if let Some(first) = first {
+            update = update.bind(first);
         }
-        if first.is_some() {
-            update = update.bind(first.unwrap());
+        if let Some(last) = last {
+            update = update.bind(last);
         }
-        if last.is_some() {
-            update = update.bind(last.unwrap());
+        if let Some(department) = department {
+            update = update.bind(department);
         }
-        if department.is_some() {
-            update = update.bind(department.unwrap());
+        if let Some(title) = title {
+            update = update.bind(title);
         }
-        if title.is_some() {
-            update = update.bind(title.unwrap());
+        if let Some(manager) = &manager {
+            update = update.bind(manager.clone());
+        }
+        if let Some(status) = status {
+            update = update.bind(status.to_static_str());
+        }
+        if let Some(start_date) = start_date {
+            update = update.bind(start_date.trim().to_string());
+        }
+        if let Some(end_date) = end_date {
+            update = update.bind(end_date.trim().to_string());
+        }
+        match update.fetch_optional(executor).await.map_err(|e| unknown_manager(e, manager))? {
+            Some(Rval{id}) => Ok(id),
+            None => Err(UserDbError::NotFound(missing)),
         }
-        let Rval{id} = update.fetch_one(pool).await?;
-        Ok(id)
     }
 }
 
@@ -147,7 +252,17 @@ pub mod login {
         pub first: Option<String>,
         pub last: Option<String>,
         pub department: Option<i32>,
-        pub title: Option<i32>
+        pub title: Option<i32>,
+        /// The login of the person's new manager. An empty login
+        /// removes their manager
+        pub manager: Option<String>,
+        pub status: Option<PersonStatus>,
+        /// The date the person started, as YYYY-MM-DD. An empty date
+        /// clears it
+        pub start_date: Option<String>,
+        /// The date the person finished, as YYYY-MM-DD. An empty date
+        /// clears it
+        pub end_date: Option<String>,
     }
 
     impl Updateable for PersonUpdate {
@@ -156,7 +271,11 @@ pub mod login {
             self.first.is_none() && 
             self.last.is_none() && 
             self.department.is_none() &&
-            self.title.is_none()
+            self.title.is_none() &&
+            self.manager.is_none() &&
+            self.status.is_none() &&
+            self.start_date.is_none() &&
+            self.end_date.is_none()
         }
 
         fn update(&self) -> String {
@@ -189,8 +308,26 @@ pub mod login {
             }
             if self.title.is_some() {
                 retval = format!("{}{}title_id = ${}",retval,set,cnt);
-                //set = ",";
-                //cnt +=1;
+                set = ",";
+                cnt +=1;
+            }
+            if self.manager.is_some() {
+                retval = format!("{}{}manager_id = {}",retval,set,manager_id(cnt));
+                set = ",";
+                cnt +=1;
+            }
+            if self.status.is_some() {
+                retval = format!("{}{}status = ${}::personstatus",retval,set,cnt);
+                set = ",";
+                cnt +=1;
+            }
+            if self.start_date.is_some() {
+                retval = format!("{}{}start_date = NULLIF(${}, '')::date",retval,set,cnt);
+                set = ",";
+                cnt +=1;
+            }
+            if self.end_date.is_some() {
+                retval = format!("{}{}end_date = NULLIF(${}, '')::date",retval,set,cnt);
             }
             retval += "\nWHERE\n\tid = (\n\tSELECT\n\t\tperson.id\n\tFROM\n\t\tperson\n\tWHERE\n\t\tperson.login = $1\n\t)\nRETURNING id";
             retval
@@ -206,6 +343,10 @@ pub mod login {
                 last: None,
                 department: None,
                 title: None,
+                manager: None,
+                status: None,
+                start_date: None,
+                end_date: None,
             }
         }
         /// Set the login
@@ -233,38 +374,78 @@ pub mod login {
             self.title = title;
             self
         }
+        /// Set the manager's login. An empty login removes the manager
+        pub fn manager(mut self, manager: Option<String>) -> Self {
+            self.manager = manager;
+            self
+        }
+        /// Set the status
+        pub fn status(mut self, status: Option<PersonStatus>) -> Self {
+            self.status = status;
+            self
+        }
+        /// Set the start date. An empty date clears it
+        pub fn start_date(mut self, start_date: Option<String>) -> Self {
+            self.start_date = start_date;
+            self
+        }
+        /// Set the end date. An empty date clears it
+        pub fn end_date(mut self, end_date: Option<String>) -> Self {
+            self.end_date = end_date;
+            self
+        }
     }
 
 
     #[derive(FromRow)]
     struct Rval {
-        id: Option<i32>
+        id: i32
     }
 
     /// Update a person, based on the supplied values.
-    pub async fn update(pool: &sqlx::PgPool, inputs: PersonUpdate) -> Result<Option<i32>, sqlx::Error> {  
+    pub async fn update<'c, E>(executor: E, inputs: PersonUpdate) -> Result<i32, UserDbError>
+    where
+        E: Executor<'c, Database = Postgres>,
+    {  
 
         let update_statement = inputs.update();
+        let missing = format!("Person '{}'", inputs.current_login);
         //println!("{}", &update_statement);
-        let PersonUpdate{current_login, login, first, last, department, title} = inputs;
+        let PersonUpdate{current_login, login, first, last, department, title, manager, status, start_date, end_date} = inputs;
+        check_date(&start_date)?;
+        check_date(&end_date)?;
         let mut update= sqlx::query_as(&update_statement).bind(current_login);
         
-        if login.is_some() {
-            update = update.bind(login.unwrap());
+        if let Some(login) = login {
+            update = update.bind(login);
+        }
+        if let Some(first) = first {
+            update = update.bind(first);
+        }
+        if let Some(last) = last {
+            update = update.bind(last);
+        }
+        if let Some(department) = department {
+            update = update.bind(department);
+        }
+        if let Some(title) = title {
+            update = update.bind(title);
+        }
+        if let Some(manager) = &manager {
+            update = update.bind(manager.clone());
         }
-        if first.is_some() {
-            update = update.bind(first.unwrap());
+        if let Some(status) = status {
+            update = update.bind(status.to_static_str());
         }
-        if last.is_some() {
-            update = update.bind(last.unwrap());
+        if let Some(start_date) = start_date {
+            update = update.bind(start_date.trim().to_string());
         }
-        if department.is_some() {
-            update = update.bind(department.unwrap());
+        if let Some(end_date) = end_date {
+            update = update.bind(end_date.trim().to_string());
         }
-        if title.is_some() {
-            update = update.bind(title.unwrap());
+        match update.fetch_optional(executor).await.map_err(|e| unknown_manager(e, manager))? {
+            Some(Rval{id}) => Ok(id),
+            None => Err(UserDbError::NotFound(missing)),
         }
-        let Rval{id} = update.fetch_one(pool).await?;
-        Ok(id)
     }
 }
\ No newline at end of file
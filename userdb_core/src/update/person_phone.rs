@@ -0,0 +1,47 @@
+//! Move phones from one person to another
+use std::convert::AsRef;
+use sqlx::prelude::*;
+use sqlx::{Executor, Postgres};
+use crate::UserDbError;
+
+const TRANSFER_PHONES: &str = r"
+SELECT
+    *
+FROM
+    transferPhones($1, $2, $3) AS phone_id;
+";
+
+#[derive(FromRow)]
+struct Rval {
+    phone_id: i32
+}
+
+/// Move the phones of the person with login `from` to the person with
+/// login `to`, or only the phone with `phone_id` if one is supplied.
+/// Moved phones stay primary unless `to` already has a primary phone of
+/// the same category. Returns the ids of the phones moved.
+pub async fn transfer<'c, E, I, J>(
+    executor: E,
+    from: I,
+    to: J,
+    phone_id: Option<u32>,
+) -> Result<Vec<i32>, UserDbError>
+where
+    E: Executor<'c, Database = Postgres>,
+    I: AsRef<str>,
+    J: AsRef<str>,
+{
+    let moved: Vec<Rval> = sqlx::query_as(TRANSFER_PHONES)
+    .bind(from.as_ref())
+    .bind(to.as_ref())
+    .bind(phone_id.map(|id| id as i32))
+    .fetch_all(executor).await?;
+    if moved.is_empty() {
+        let missing = match phone_id {
+            Some(phone_id) => format!("Phone with id {} for '{}'", phone_id, from.as_ref()),
+            None => format!("Phones for '{}'", from.as_ref()),
+        };
+        return Err(UserDbError::NotFound(missing));
+    }
+    Ok(moved.into_iter().map(|Rval{phone_id}| phone_id).collect())
+}
@@ -1,8 +1,11 @@
 use sqlx::FromRow;
+use sqlx::{Executor, Postgres};
 use crate::prelude::*;
 use crate::NumberString;
+use crate::Country;
 use crate::Location;
 use crate::PhoneCategory;
+use crate::UserDbError;
 
 pub mod id {
     use super::*;
@@ -14,13 +17,25 @@ pub mod id {
         pub number: Option<NumberString>,
         pub category: Option<PhoneCategory>,
         pub location: Option<Location>,
+        /// A new label. An empty label clears the existing one
+        pub label: Option<String>,
+        /// Mark or unmark the phone as primary for the people it is assigned to
+        pub primary: Option<bool>,
+        /// The country used to read a national `number`. Defaults to
+        /// the country of `location`. It is not itself stored
+        pub country: Option<Country>,
+        /// The category used to read `number`, so that extensions are
+        /// kept as entered. Defaults to `category`. It is not itself stored
+        pub current_category: Option<PhoneCategory>,
     }
 
     impl Updateable for PhoneUpdate {
         fn is_empty(&self) -> bool {
             self.number.is_none() && 
             self.category.is_none() && 
-            self.location.is_none()
+            self.location.is_none() &&
+            self.label.is_none() &&
+            self.primary.is_none()
         }
 
         fn update(&self) -> String {
@@ -43,11 +58,31 @@ pub mod id {
             }
             if self.location.is_some() {
                 retval = format!("{}{}location = ${}",retval,set,cnt);
-                //set = ",";
-                //cnt +=1;
+                set = ",";
+                cnt +=1;
+            }
+            if self.label.is_some() {
+                retval = format!("{}{}label = NULLIF(${}, '')",retval,set,cnt);
+                set = ",";
+                cnt +=1;
+            }
+            // set only becomes a comma once a column has been added
+            let updates_phone = set == ",";
+            if updates_phone {
+                retval += "\nWHERE\n\tid = $1\nRETURNING id";
+            } else {
+                // only the primary flag is changing
+                retval = "SELECT id FROM phone WHERE id = $1".to_owned();
+            }
+            // the primary flag lives on the person's assignment of the phone
+            if self.primary.is_some() {
+                retval = format!(r"
+            WITH updated AS ({}
+            ), flagged AS (
+                UPDATE people_phones SET is_primary = ${} WHERE phone_id IN (SELECT id FROM updated)
+            )
+            SELECT id FROM updated", retval, cnt);
             }
-           
-            retval += "\nWHERE\n\tid = $1\nRETURNING id";
             retval
         }
     }
@@ -61,6 +96,10 @@ pub mod id {
                 number: None,
                 category: None,
                 location: None,
+                label: None,
+                primary: None,
+                country: None,
+                current_category: None,
             }
         }
         /// Optionally set the number
@@ -78,32 +117,69 @@ pub mod id {
             self.location = location;
             self
         }
+        /// Optionally set the label
+        pub fn label(mut self, label: Option<String>) -> Self {
+            self.label = label;
+            self
+        }
+        /// Optionally mark or unmark the phone as primary
+        pub fn primary(mut self, primary: Option<bool>) -> Self {
+            self.primary = primary;
+            self
+        }
+        /// Optionally set the country used to read a national number
+        pub fn country(mut self, country: Option<Country>) -> Self {
+            self.country = country;
+            self
+        }
+        /// Optionally set the category used to read the number
+        pub fn current_category(mut self, current_category: Option<PhoneCategory>) -> Self {
+            self.current_category = current_category;
+            self
+        }
     }
 
 
     #[derive(FromRow)]
     struct Rval {
-        id: Option<i32>
+        id: i32
     }
 
     /// Update a person, based on the supplied values.
-    pub async fn update(pool: &sqlx::PgPool, inputs: PhoneUpdate) -> Result<Option<i32>, sqlx::Error> {  
+    pub async fn update<'c, E>(executor: E, inputs: PhoneUpdate) -> Result<i32, UserDbError>
+    where
+        E: Executor<'c, Database = Postgres>,
+    {  
 
         let update_statement = inputs.update();
+        let missing = format!("Phone with id {}", inputs.id);
         //println!("{}", &update_statement);
-        let PhoneUpdate{id, number, category, location} = inputs;
+        let PhoneUpdate{id, number, category, location, label, primary, country, current_category} = inputs;
+        let country = country.or_else(|| location.as_ref().and_then(Location::country));
         let mut update= sqlx::query_as(&update_statement).bind(id);
         
-        if number.is_some() {
-            update = update.bind(number.unwrap().to_string());
+        if let Some(number) = number {
+            let number = match category.as_ref().or(current_category.as_ref()) {
+                Some(category) => number.normalize(category, country.as_ref())?,
+                None => number.to_e164(country.as_ref())?,
+            };
+            update = update.bind(number.to_string());
+        }
+        if let Some(category) = category {
+            update = update.bind(category);
+        }
+        if let Some(location) = location {
+            update = update.bind(location.name);
+        }
+        if let Some(label) = label {
+            update = update.bind(label);
         }
-        if category.is_some() {
-            update = update.bind(category.unwrap());
+        if let Some(primary) = primary {
+            update = update.bind(primary);
         }
-        if location.is_some() {
-            update = update.bind(location.unwrap());
+        match update.fetch_optional(executor).await? {
+            Some(Rval{id}) => Ok(id),
+            None => Err(UserDbError::NotFound(missing)),
         }
-        let Rval{id} = update.fetch_one(pool).await?;
-        Ok(id)
     }
 }
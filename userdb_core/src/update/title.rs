@@ -1,5 +1,7 @@
 use sqlx::FromRow;
+use sqlx::{Executor, Postgres};
 use crate::prelude::*;
+use crate::UserDbError;
 
 
 pub mod id {
@@ -21,15 +23,14 @@ pub mod id {
 
         fn update(&self) -> String {
             //let  retval = "UPDATE\n\ttitle\n\tSET\n\t\tname = $2\nWHERE\n\tid = $1\nRETURNING id".to_owned() ;
-            let retval = r"
+            r"
             UPDATE
                 title
             SET
                 name = $2
             WHERE
                 id = $1
-            RETURNING id".to_owned() ;       
-            retval
+            RETURNING id".to_owned()
         }
     }
 
@@ -48,21 +49,27 @@ pub mod id {
 
     #[derive(FromRow)]
     struct Rval {
-        id: Option<i32>
+        id: i32
     }
 
     /// Update a title, based on the supplied values.
-    pub async fn update(pool: &sqlx::PgPool, inputs: TitleUpdate) -> Result<Option<i32>, sqlx::Error> {  
+    pub async fn update<'c, E>(executor: E, inputs: TitleUpdate) -> Result<i32, UserDbError>
+    where
+        E: Executor<'c, Database = Postgres>,
+    {  
 
         let update_statement = inputs.update();
+        let missing = format!("Title with id {}", inputs.id);
 
         let TitleUpdate{id, name} = inputs;
         let update = sqlx::query_as(&update_statement)
                                 .bind(id)
                                 .bind(name);
         
-        let Rval{id} = update.fetch_one(pool).await?;
-        Ok(id)
+        match update.fetch_optional(executor).await? {
+            Some(Rval{id}) => Ok(id),
+            None => Err(UserDbError::NotFound(missing)),
+        }
     }
 }
 
@@ -83,7 +90,7 @@ pub mod name {
         }
 
         fn update(&self) -> String {
-            let  retval = r"
+            r"
             UPDATE
                 title
             SET
@@ -94,9 +101,7 @@ pub mod name {
                        FROM 
                             title 
                        WHERE name = $1)
-            RETURNING id".to_owned() ;
-                          
-            retval
+            RETURNING id".to_owned()
         }
     }
 
@@ -118,20 +123,26 @@ pub mod name {
 
     #[derive(FromRow)]
     struct Rval {
-        id: Option<i32>
+        id: i32
     }
 
     /// Update a title, based on the supplied values.
-    pub async fn update(pool: &sqlx::PgPool, inputs: TitleUpdate) -> Result<Option<i32>, sqlx::Error> {  
+    pub async fn update<'c, E>(executor: E, inputs: TitleUpdate) -> Result<i32, UserDbError>
+    where
+        E: Executor<'c, Database = Postgres>,
+    {  
 
         let update_statement = inputs.update();
+        let missing = format!("Title '{}'", inputs.name);
 
         let TitleUpdate{name, new_name} = inputs;
         let update = sqlx::query_as(&update_statement)
                                 .bind(name)
                                 .bind(new_name);
         
-        let Rval{id} = update.fetch_one(pool).await?;
-        Ok(id)
+        match update.fetch_optional(executor).await? {
+            Some(Rval{id}) => Ok(id),
+            None => Err(UserDbError::NotFound(missing)),
+        }
     }
 }
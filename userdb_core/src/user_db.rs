@@ -0,0 +1,357 @@
+//! The `UserDb` owns the connection pool used to talk to the userdb.
+//!
+//! Every call in the create, read, update, and delete modules takes a
+//! reference to a `UserDb`. Construct one per process and share it:
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! use userdb_core::{Config, UserDb};
+//!
+//! let profile = Config::load_profile(None, None)?;
+//! let db = UserDb::connect(&profile).await?;
+//! let titles = userdb_core::read::title::query(&db).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The library calls accept any sqlx executor, so they may also be run
+//! against a connection or a `Transaction`. `UserDb::transaction` wraps
+//! a series of calls so that they commit or roll back together.
+use async_std::task;
+use either::Either;
+use futures::future::BoxFuture;
+use futures::stream::BoxStream;
+use sqlx::postgres::{PgDone, PgPool, PgPoolOptions, PgRow, PgStatement, PgTypeInfo, Postgres};
+use sqlx::{Acquire, Describe, Execute, Executor, Transaction};
+use sqlx::pool::PoolConnection;
+use std::time::Duration;
+
+use crate::Profile;
+use crate::UserDbError;
+
+const DEFAULT_MAX_CONNECTIONS: u32 = 5;
+const DEFAULT_MIN_CONNECTIONS: u32 = 0;
+const DEFAULT_CONNECT_TIMEOUT: u64 = 30;
+const DEFAULT_CONNECT_RETRIES: u32 = 0;
+const DEFAULT_RETRY_DELAY: u64 = 1000;
+
+// read by the audit triggers to attribute changes
+const SET_ACTOR: &str = "SELECT set_config('userdb.actor', $1, false)";
+// as above, for the current transaction only
+const SET_LOCAL_ACTOR: &str = "SELECT set_config('userdb.actor', $1, true)";
+
+/// Options used to construct a `UserDb`. Defaults may be overridden
+/// via the builder methods, or by a `Profile`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserDbOptions {
+    pub max_connections: u32,
+    pub min_connections: u32,
+    pub connect_timeout: Duration,
+    pub statement_timeout: Option<Duration>,
+    pub connect_retries: u32,
+    pub retry_delay: Duration,
+    /// Who changes are attributed to in the audit log. The database user
+    /// is recorded if this is not set.
+    pub actor: Option<String>,
+}
+
+impl Default for UserDbOptions {
+    fn default() -> Self {
+        Self {
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+            min_connections: DEFAULT_MIN_CONNECTIONS,
+            connect_timeout: Duration::from_secs(DEFAULT_CONNECT_TIMEOUT),
+            statement_timeout: None,
+            connect_retries: DEFAULT_CONNECT_RETRIES,
+            retry_delay: Duration::from_millis(DEFAULT_RETRY_DELAY),
+            actor: None,
+        }
+    }
+}
+
+impl From<&Profile> for UserDbOptions {
+    fn from(profile: &Profile) -> Self {
+        let defaults = Self::default();
+        Self {
+            max_connections: profile.max_connections.unwrap_or(defaults.max_connections),
+            min_connections: profile.min_connections.unwrap_or(defaults.min_connections),
+            connect_timeout: profile.connect_timeout.map(Duration::from_secs).unwrap_or(defaults.connect_timeout),
+            statement_timeout: profile.statement_timeout.map(Duration::from_millis),
+            connect_retries: profile.connect_retries.unwrap_or(defaults.connect_retries),
+            retry_delay: profile.retry_delay.map(Duration::from_millis).unwrap_or(defaults.retry_delay),
+            actor: profile.actor.clone(),
+        }
+    }
+}
+
+impl UserDbOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn max_connections(mut self, max: u32) -> Self {
+        self.max_connections = max;
+        self
+    }
+
+    pub fn min_connections(mut self, min: u32) -> Self {
+        self.min_connections = min;
+        self
+    }
+
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    pub fn statement_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.statement_timeout = timeout;
+        self
+    }
+
+    pub fn connect_retries(mut self, retries: u32) -> Self {
+        self.connect_retries = retries;
+        self
+    }
+
+    pub fn retry_delay(mut self, delay: Duration) -> Self {
+        self.retry_delay = delay;
+        self
+    }
+
+    pub fn actor(mut self, actor: Option<String>) -> Self {
+        self.actor = actor;
+        self
+    }
+
+    /// Build the pool and establish a connection to the database at `url`,
+    /// retrying transient failures up to `connect_retries` times.
+    pub async fn connect(self, url: &str) -> Result<UserDb, UserDbError> {
+        let mut attempt = 0;
+        loop {
+            match self.try_connect(url).await {
+                Ok(pool) => return Ok(UserDb::from_pool(pool)),
+                Err(e) if attempt < self.connect_retries && is_transient(&e) => {
+                    attempt += 1;
+                    task::sleep(self.retry_delay).await;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    // The pool does not connect until it is used unless min_connections
+    // is set, so we check out a connection up front to surface failures here.
+    async fn try_connect(&self, url: &str) -> Result<PgPool, sqlx::Error> {
+        let pool = self.pool_options().connect(url).await?;
+        pool.acquire().await?;
+        Ok(pool)
+    }
+
+    // translate our options into the pool's options. The statement timeout
+    // and the actor are session settings, so each connection is set up
+    // with them as it is established.
+    fn pool_options(&self) -> PgPoolOptions {
+        let options = PgPoolOptions::new()
+            .max_connections(self.max_connections)
+            .min_connections(self.min_connections)
+            .connect_timeout(self.connect_timeout);
+        if self.statement_timeout.is_none() && self.actor.is_none() {
+            return options;
+        }
+        let set_timeout = self.statement_timeout
+            .map(|timeout| format!("SET statement_timeout = {}", timeout.as_millis()));
+        let actor = self.actor.clone();
+        options.after_connect(move |conn| {
+            let set_timeout = set_timeout.clone();
+            let actor = actor.clone();
+            Box::pin(async move {
+                if let Some(set_timeout) = set_timeout {
+                    conn.execute(set_timeout.as_str()).await?;
+                }
+                if let Some(actor) = actor {
+                    sqlx::query(SET_ACTOR).bind(actor).execute(conn).await?;
+                }
+                Ok(())
+            })
+        })
+    }
+}
+
+// errors which may go away if we try again
+fn is_transient(error: &sqlx::Error) -> bool {
+    matches!(error, sqlx::Error::Io(_) | sqlx::Error::PoolTimedOut)
+}
+
+/// Handle to the userdb. Cloning a `UserDb` is cheap, and the clones share
+/// the same pool.
+#[derive(Debug, Clone)]
+pub struct UserDb {
+    pool: PgPool,
+}
+
+impl UserDb {
+    /// Connect to the database described by `profile`.
+    pub async fn connect(profile: &Profile) -> Result<Self, UserDbError> {
+        UserDbOptions::from(profile).connect(profile.url()).await
+    }
+
+    /// Wrap an existing pool.
+    pub fn from_pool(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Retrieve a reference to the underlying pool.
+    pub fn pool(&self) -> &PgPool {
+        &self.pool
+    }
+
+    /// Close the pool, waiting for checked out connections to be returned.
+    pub async fn close(&self) {
+        self.pool.close().await
+    }
+
+    /// Start a transaction. It is rolled back when dropped unless
+    /// `commit` is called.
+    pub async fn begin(&self) -> Result<Transaction<'static, Postgres>, UserDbError> {
+        Ok(self.pool.begin().await?)
+    }
+
+    /// Start a transaction whose changes are attributed to `actor` in the
+    /// audit log, rather than to the actor the pool was connected with.
+    /// This suits services making changes on behalf of their callers.
+    pub async fn begin_as(&self, actor: &str) -> Result<Transaction<'static, Postgres>, UserDbError> {
+        let mut tx = self.begin().await?;
+        sqlx::query(SET_LOCAL_ACTOR).bind(actor).execute(&mut tx).await?;
+        Ok(tx)
+    }
+
+    /// Run `f` within a transaction, committing if it succeeds and rolling
+    /// back if it returns an error.
+    ///
+    /// ```no_run
+    /// # async fn run(db: &userdb_core::UserDb) -> Result<(), userdb_core::UserDbError> {
+    /// use userdb_core::{create, Locations, NumberString, PhoneCategory};
+    ///
+    /// let portland = Locations::load(db).await?.parse("pd")?.clone();
+    /// let ext = NumberString::new("5234")?;
+    /// let id = db.transaction(|tx| Box::pin(async move {
+    ///     let id = create::person::create(&mut *tx, "Jane", "Doe", "jdoe", "Supervisors", "Animator").await?;
+    ///     create::phone::create(&mut *tx, "jdoe", &ext, &PhoneCategory::Extension, &portland, None, true).await?;
+    ///     Ok(id)
+    /// })).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn transaction<F, T>(&self, f: F) -> Result<T, UserDbError>
+    where
+        F: for<'t> FnOnce(&'t mut Transaction<'static, Postgres>) -> BoxFuture<'t, Result<T, UserDbError>>,
+    {
+        let mut tx = self.begin().await?;
+        match f(&mut tx).await {
+            Ok(value) => {
+                tx.commit().await?;
+                Ok(value)
+            }
+            Err(e) => {
+                tx.rollback().await?;
+                Err(e)
+            }
+        }
+    }
+}
+
+// Allow a `&UserDb` to be handed to the library calls directly, by
+// delegating to the pool.
+impl<'c> Executor<'c> for &'c UserDb {
+    type Database = Postgres;
+
+    fn fetch_many<'e, 'q: 'e, E>(
+        self,
+        query: E,
+    ) -> BoxStream<'e, Result<Either<PgDone, PgRow>, sqlx::Error>>
+    where
+        'c: 'e,
+        E: 'q + Execute<'q, Postgres>,
+    {
+        self.pool.fetch_many(query)
+    }
+
+    fn fetch_optional<'e, 'q: 'e, E>(
+        self,
+        query: E,
+    ) -> BoxFuture<'e, Result<Option<PgRow>, sqlx::Error>>
+    where
+        'c: 'e,
+        E: 'q + Execute<'q, Postgres>,
+    {
+        self.pool.fetch_optional(query)
+    }
+
+    fn prepare_with<'e, 'q: 'e>(
+        self,
+        sql: &'q str,
+        parameters: &'e [PgTypeInfo],
+    ) -> BoxFuture<'e, Result<PgStatement<'q>, sqlx::Error>>
+    where
+        'c: 'e,
+    {
+        self.pool.prepare_with(sql, parameters)
+    }
+
+    fn describe<'e, 'q: 'e>(self, sql: &'q str) -> BoxFuture<'e, Result<Describe<Postgres>, sqlx::Error>>
+    where
+        'c: 'e,
+    {
+        self.pool.describe(sql)
+    }
+}
+
+// Allow a `&UserDb` to be handed to the calls which begin their own
+// transaction, such as `import::import`, by delegating to the pool.
+impl Acquire<'static> for &'_ UserDb {
+    type Database = Postgres;
+    type Connection = PoolConnection<Postgres>;
+
+    fn acquire(self) -> BoxFuture<'static, Result<Self::Connection, sqlx::Error>> {
+        Acquire::acquire(&self.pool)
+    }
+
+    fn begin(self) -> BoxFuture<'static, Result<Transaction<'static, Postgres>, sqlx::Error>> {
+        Acquire::begin(&self.pool)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_profile_given_empty_profile_uses_defaults() {
+        let options = UserDbOptions::from(&Profile::default());
+        assert_eq!(options, UserDbOptions::default());
+    }
+
+    #[test]
+    fn from_profile_converts_units() {
+        let profile = Profile {
+            connect_timeout: Some(5),
+            statement_timeout: Some(250),
+            retry_delay: Some(100),
+            ..Profile::default()
+        };
+        let options = UserDbOptions::from(&profile);
+        assert_eq!(options.connect_timeout, Duration::from_secs(5));
+        assert_eq!(options.statement_timeout, Some(Duration::from_millis(250)));
+        assert_eq!(options.retry_delay, Duration::from_millis(100));
+    }
+
+    #[test]
+    fn from_profile_carries_actor() {
+        let profile = Profile {
+            actor: Some("jdoe".into()),
+            ..Profile::default()
+        };
+        assert_eq!(UserDbOptions::from(&profile).actor.as_deref(), Some("jdoe"));
+    }
+}
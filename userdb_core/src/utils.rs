@@ -1,5 +1,9 @@
 
+use serde::de::DeserializeOwned;
 use sqlx::FromRow;
+use std::num::NonZeroU32;
+
+use crate::UserDbError;
 
 /// A little helper function to build up the WHERE clause in 
 /// a SELECT query.
@@ -19,3 +23,24 @@ pub fn where_joiner(cnt: u8) -> &'static str {
 pub struct JsonAdapter {
     pub inner: serde_json::Value
 }
+
+// the number of rows matching a query, selected AS count
+#[derive(FromRow, Debug)]
+pub(crate) struct Count {
+    pub count: i64
+}
+
+/// The LIMIT and OFFSET clauses of a query returning at most `limit`
+/// rows after skipping `offset` of them
+pub(crate) fn limit_clause(limit: Option<NonZeroU32>, offset: u32) -> String {
+    let limit = limit.map(|limit| format!("LIMIT {}", limit));
+    let offset = Some(offset).filter(|offset| *offset > 0).map(|offset| format!("OFFSET {}", offset));
+    limit.into_iter().chain(offset).collect::<Vec<_>>().join(" ")
+}
+
+/// Deserialize the json objects returned by a query into `T`s.
+pub(crate) fn from_json<T: DeserializeOwned>(values: Vec<serde_json::Value>) -> Result<Vec<T>, UserDbError> {
+    values.into_iter()
+          .map(|value| serde_json::from_value(value).map_err(UserDbError::from))
+          .collect()
+}
@@ -2,32 +2,89 @@
 //!
 //! Simple cli for finding users in the userdb.
 
+use colored::*;
+use std::num::NonZeroU32;
 use structopt::StructOpt;
 
 // internal
-use userdb_phone::process::read::process_read_simple_person;
+use userdb_core::Date;
+use userdb_core::NumberString;
+use userdb_core::Search;
+use userdb_core::UserDb;
+use userdb_phone::opt::ConnectionOpt;
+use userdb_phone::process::read::{process_read_org, process_read_person, process_read_simple_person};
 
 
 #[derive(StructOpt, Debug)]
 #[structopt(about="Search for people...")]
 pub struct Opt {
-    /// Specifcy the name, login, department, or title to search on.
-    #[structopt(name="VALUE")]
-    pub value: String,
+    /// Specifcy the name, login, department, title, email address, chat handle, building or room to search on.
+    /// Several terms may be given, eg `sam slade`, and qualified by a field: name, login, title, dept,
+    /// contact, desk, loc, cat or number, eg `dept:"vancouver soft" loc:bc`. Terms may be joined by AND
+    /// or OR, grouped with parentheses, and negated with NOT or a leading -. Terms negated with a -
+    /// must follow `--`, so that they are not taken for flags, eg `phone -- loc:bc -cat:home`
+    #[structopt(name="VALUE", required_unless_one=&["org", "tui", "number"])]
+    pub value: Vec<String>,
+    /// Display the org chart of the person with the supplied login: their
+    /// management chain and everyone reporting to them
+    #[structopt(short, long, conflicts_with="VALUE")]
+    pub org: Option<String>,
     /// Display results as json instead of as a table
     #[structopt(short,long)]
     pub json: bool,
+    /// Include people who have been terminated
+    #[structopt(long="include-inactive")]
+    pub include_inactive: bool,
+    /// Search people as they were at the end of the supplied day (YYYY-MM-DD)
+    #[structopt(long="as-of", conflicts_with="org")]
+    pub as_of: Option<Date>,
+    /// Also find people whose name or login is close to VALUE, to allow for typos
+    #[structopt(short, long, conflicts_with="org")]
+    pub fuzzy: bool,
+    /// Show at most this many people, best matches first
+    #[structopt(long, conflicts_with="org")]
+    pub limit: Option<NonZeroU32>,
+    /// Browse the directory interactively, searching as you type. VALUE,
+    /// if supplied, is the initial search
+    #[structopt(long, conflicts_with_all=&["org", "json", "as-of"])]
+    pub tui: bool,
+    /// Find the people holding a phone number, given with any punctuation,
+    /// in full or as its last digits, eg 7091
+    #[structopt(short, long, conflicts_with_all=&["VALUE", "org", "fuzzy", "limit", "tui"])]
+    pub number: Option<NumberString>,
+
+    #[structopt(flatten)]
+    pub connection: ConnectionOpt,
 }
 
 
 #[async_std::main]
-async fn main() -> Result<(), sqlx::Error> {
+async fn main() {
     // build options from structopt
     let opt = Opt::from_args();
-    match opt {
-        Opt{value, json} => {
-            process_read_simple_person(value, json ).await
-        },
+    if let Err(e) = run(opt).await {
+        eprintln!("\n\t{} {}", "Error:".bright_red(), e);
+        std::process::exit(1);
+    }
+}
+
+async fn run(opt: Opt) -> Result<(), Box<dyn std::error::Error>> {
+    let Opt{value, org, json, include_inactive, as_of, fuzzy, limit, tui, number, connection} = opt;
+    // resolve the database to connect to from the flags, env and config files
+    let value = if value.is_empty() { None } else { Some(Search::join_args(value)) };
+    let profile = connection.profile()?;
+    let db = UserDb::connect(&profile).await?;
+    if tui {
+        return Ok(userdb_phone::tui::run(&db, value, include_inactive, false).await?);
+    }
+    if number.is_some() {
+        return Ok(process_read_person(&db, None, None, None, None, None, number, include_inactive, as_of, json).await?);
+    }
+    match (org, value) {
+        (Some(login), _) => process_read_org(&db, login, json).await?,
+        (None, Some(value)) => process_read_simple_person(&db, value, include_inactive, as_of, fuzzy, limit, json ).await?,
+        // structopt requires one or the other
+        (None, None) => unreachable!(),
     }
-    
+    Ok(())
 }
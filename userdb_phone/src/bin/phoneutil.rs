@@ -7,88 +7,172 @@ use colored::*;
 use structopt::StructOpt;
 
 // internal
+use userdb_core::Locations;
 use userdb_core::QueryMode;
+use userdb_core::UserDb;
 use userdb_core::read::phone::PhoneQuery;
+use userdb_core::read::contact::ContactQuery;
+use userdb_core::read::audit::AuditQuery;
 use userdb_phone::opt::*;
 use userdb_phone::process::read::*;
 use userdb_phone::process::create::*;
 use userdb_phone::process::update::*;
 use userdb_phone::process::delete::*;
+use userdb_phone::process::import::*;
+use userdb_phone::process::export::*;
+use userdb_phone::process::snapshot::*;
 
 
 #[async_std::main]
-async fn main() -> Result<(), sqlx::Error> {
+async fn main() {
     // build options from structopt
     let opt = Opt::from_args();
+    if let Err(e) = run(opt).await {
+        eprintln!("\n\t{} {}", "Error:".bright_red(), e);
+        std::process::exit(1);
+    }
+}
+
+async fn run(opt: Opt) -> Result<(), Box<dyn std::error::Error>> {
+    // resolve the database to connect to from the flags, env and config files
+    let profile = opt.connection.profile()?;
+    let db = UserDb::connect(&profile).await?;
     match opt {
-        Opt{mut name, login, json, title, dept, fullname, cmd: None} => {
+        Opt{mut name, login, json, title, dept, contact, number, fullname, include_inactive, as_of, cmd: None, ..} => {
             if name.is_none() && fullname.is_some() {
                 name = fullname;
             }
-            process_read_person(name, login, title, dept, json ).await},
+            process_read_person(&db, name, login, title, dept, contact, number, include_inactive, as_of, json ).await},
         Opt{cmd: Some(OptSub::Read{sub}), ..} => match sub {
-            ReadOpt::Person{mut name, login, title, dept, fullname, json} => {
+            ReadOpt::Person{mut name, login, title, dept, contact, number, fullname, include_inactive, as_of, json} => {
                 if name.is_none() && fullname.is_some() {
                     name = fullname;
                 }
-                process_read_person(name, login, title, dept, json).await
+                process_read_person(&db, name, login, title, dept, contact, number, include_inactive, as_of, json).await
             },
-            ReadOpt::Title{json} => process_read_title(json).await,
-            ReadOpt::Department{json} => process_read_department(json).await,
-            ReadOpt::Phone{id, number, category, location, json} => {
-                
+            ReadOpt::Title{json} => process_read_title(&db, json).await,
+            ReadOpt::Department{json} => process_read_department(&db, json).await,
+            ReadOpt::Location{json} => process_read_location(&db, json).await,
+            ReadOpt::Contact{id, login, category, value, json} => {
+                let query = ContactQuery::new()
+                .id(id)
+                .login(login)
+                .category(category)
+                .value(value);
+                process_read_contact(&db, query, QueryMode::ILike, json).await
+            },
+            ReadOpt::DialPlan{location, json} => process_read_dial_plan(&db, location, json).await,
+            ReadOpt::Phone{id, number, category, location, as_of, json} => {
+                let location = match location {
+                    Some(location) => Some(Locations::load(&db).await?.parse(&location)?.clone()),
+                    None => None,
+                };
                 let query = PhoneQuery::new()
                 .id(id)
                 .number(number)
                 .category(category)
-                .location(location);
-                process_read_phone(query, QueryMode::ILike, json ).await}
+                .location(location)
+                .as_of(as_of);
+                process_read_phone(&db, query, QueryMode::ILike, json ).await}
         }
         Opt{cmd: Some(OptSub::Create{sub}), ..} => match sub {
-            CreateOpt::Person{first, last, login, department, title} => process_create_person(&first, &last, &login, &department, &title).await,
-            CreateOpt::Phone{login, number, category, location} => process_create_phone(&login, &number, &category, &location).await,
-            CreateOpt::Title{title} => process_create_title(&title).await,
-            CreateOpt::Department{department} => process_create_department(&department).await,
+            CreateOpt::Person{first, last, login, department, title, status, start_date} => process_create_person(&db, &first, &last, &login, &department, &title, status, start_date).await,
+            CreateOpt::Phone{login, number, category, location, label, primary} => process_create_phone(&db, &login, &number, &category, &location, label.as_deref(), primary).await,
+            CreateOpt::Contact{login, category, value, label, primary} => process_create_contact(&db, &login, &category, &value, label.as_deref(), primary).await,
+            CreateOpt::Title{title} => process_create_title(&db, &title).await,
+            CreateOpt::Department{department} => process_create_department(&db, &department).await,
+            CreateOpt::Location{name, code, aliases, address, timezone, country, calling_code, trunk_prefix} => process_create_location(&db, &name, &code, &aliases, address.as_deref(), &timezone, &country, calling_code.as_deref(), trunk_prefix.as_deref()).await,
+            CreateOpt::DialPlan{location, switchboard, did_prefix, extensions} => process_create_dial_plan(&db, &location, &switchboard, &did_prefix, &extensions).await,
         }
         Opt{cmd: Some(OptSub::Update{sub}), ..} => match sub {
-            UpdateOpt::Person{ id: Some(id), first, last, login, department, title,..} => process_update_person_by_id(id, first, last, login, department, title).await,
-            UpdateOpt::Person{ from_login: Some(from_login), first, last, login, department, title,..} => process_update_person_by_login(from_login, first, last, login, department, title).await,
+            UpdateOpt::Person{ id: Some(id), first, last, login, department, title, manager, status, desk,..} => process_update_person_by_id(&db, id, first, last, login, department, title, manager, status, desk).await,
+            UpdateOpt::Person{ from_login: Some(from_login), first, last, login, department, title, manager, status, desk,..} => process_update_person_by_login(&db, from_login, first, last, login, department, title, manager, status, desk).await,
             UpdateOpt::Person{..} => {
                 eprintln!("\n\t{} Must supply either --id or --name.", "Error:".bright_red());
                 std::process::exit(1);
             },
-            UpdateOpt::Phone{id, number, category, location} => process_update_phone_by_id(id, number, category, location).await,
-            UpdateOpt::Title{id, name} => process_update_title_by_id(id, name).await,
-            UpdateOpt::Department{id, name} => process_update_dept_by_id(id, name).await,
+            UpdateOpt::Phone{id, number, category, location, label, primary} => process_update_phone_by_id(&db, id, number, category, location, label, primary).await,
+            UpdateOpt::Contact{id, category, value, label, primary} => process_update_contact_by_id(&db, id, category, value, label, primary).await,
+            UpdateOpt::Title{id, name} => process_update_title_by_id(&db, id, name).await,
+            UpdateOpt::Department{id, name} => process_update_dept_by_id(&db, id, name).await,
+            UpdateOpt::Location{id, name, code, aliases, clear_aliases, address, timezone, country, calling_code, trunk_prefix} => {
+                let aliases = if clear_aliases || !aliases.is_empty() { Some(aliases) } else { None };
+                process_update_location_by_id(&db, id, name, code, aliases, address, timezone, country, calling_code, trunk_prefix).await
+            },
         }
         Opt{cmd: Some(OptSub::Delete{sub}), ..} => match sub {
-            DeleteOpt::Phone{id: Some(id),..} => process_delete_phone_by_id(id).await,
+            DeleteOpt::Phone{id: Some(id),..} => process_delete_phone_by_id(&db, id).await,
             DeleteOpt::Phone{
                 login: Some(login), 
                 number: Some(number), 
                 category:Some(category), 
-                location: Some(location),..} => process_delete_phone(&login, &number, &category, &location).await,
+                location: Some(location),..} => process_delete_phone(&db, &login, &number, &category, &location).await,
             DeleteOpt::Phone{..} => panic!("should not reach here"),
-            DeleteOpt::Department{name: Some(value), ..} => process_delete_dept(&value).await,
-            DeleteOpt::Department{id: Some(id),..} => process_delete_dept_by_id(id).await,
+            DeleteOpt::Contact{id: Some(id),..} => process_delete_contact_by_id(&db, id).await,
+            DeleteOpt::Contact{
+                login: Some(login),
+                category: Some(category),
+                value: Some(value),..} => process_delete_contact(&db, &login, &category, &value).await,
+            DeleteOpt::Contact{..} => {
+                eprintln!("\n\t{} Must supply either --id or --login, --category and --value.", "Error:".bright_red());
+                std::process::exit(1);
+            },
+            DeleteOpt::Department{name: Some(value), ..} => process_delete_dept(&db, &value).await,
+            DeleteOpt::Department{id: Some(id),..} => process_delete_dept_by_id(&db, id).await,
             DeleteOpt::Department{..} => {
                 eprintln!("\n\t{} Must supply either --id or --name.", "Error:".bright_red());
                 std::process::exit(1);
             },
 
-            DeleteOpt::Title{name: Some(value), ..} => process_delete_title(&value).await,
-            DeleteOpt::Title{id: Some(id),..} => process_delete_title_by_id(id).await,
+            DeleteOpt::Title{name: Some(value), ..} => process_delete_title(&db, &value).await,
+            DeleteOpt::Title{id: Some(id),..} => process_delete_title_by_id(&db, id).await,
             DeleteOpt::Title{..} => {
                 eprintln!("\n\t{} Must supply either --id or --name.", "Error:".bright_red());
                 std::process::exit(1);
             },
 
-            DeleteOpt::Person{login: Some(value), ..} => process_delete_person(&value).await,
-            DeleteOpt::Person{id: Some(id),..} => process_delete_person_by_id(id).await,
+            DeleteOpt::Person{login: Some(value), ..} => process_delete_person(&db, &value).await,
+            DeleteOpt::Person{id: Some(id),..} => process_delete_person_by_id(&db, id).await,
             DeleteOpt::Person{..} => {
                 eprintln!("\n\t{} Must supply either --id or --login.", "Error:".bright_red());
                 std::process::exit(1);
+            },
+
+            DeleteOpt::Location{name: Some(value), ..} => process_delete_location(&db, &value).await,
+            DeleteOpt::Location{id: Some(id),..} => process_delete_location_by_id(&db, id).await,
+            DeleteOpt::Location{..} => {
+                eprintln!("\n\t{} Must supply either --id or --name.", "Error:".bright_red());
+                std::process::exit(1);
             }
+            DeleteOpt::DialPlan{location} => process_delete_dial_plan(&db, &location).await,
         }
-    }
+        Opt{cmd: Some(OptSub::Phone{sub}), ..} => match sub {
+            PhoneOpt::Transfer{from, to, id} => process_transfer_phones(&db, &from, &to, id).await,
+            PhoneOpt::Share{id, login, primary} => process_share_phone(&db, id, &login, primary).await,
+            PhoneOpt::Unshare{id, login} => process_unshare_phone(&db, id, &login).await,
+        }
+        Opt{cmd: Some(OptSub::Department{sub}), ..} => match sub {
+            DepartmentOpt::Merge{source, destination} => process_merge_dept(&db, &source, &destination).await,
+        }
+        Opt{cmd: Some(OptSub::Import{sub}), ..} => match sub {
+            ImportOpt::Csv{file, dry_run} => process_import_csv(&db, &file, dry_run).await,
+        }
+        Opt{cmd: Some(OptSub::Export{format, output}), ..} => process_export(&db, format, output.as_deref()).await,
+        Opt{cmd: Some(OptSub::Dump{output}), ..} => process_dump(&db, output.as_deref()).await,
+        Opt{cmd: Some(OptSub::Restore{file, on_conflict, dry_run}), ..} => process_restore(&db, &file, on_conflict, dry_run).await,
+        Opt{cmd: Some(OptSub::Purge{retention_days, dry_run}), ..} => process_purge(&db, retention_days, dry_run).await,
+        Opt{cmd: Some(OptSub::Audit{entity, id, login, by, since, until, limit, json}), ..} => {
+            let query = AuditQuery::new()
+            .entity(entity)
+            .entity_id(id)
+            .login(login)
+            .actor(by)
+            .since(since)
+            .until(until)
+            .limit(Some(limit));
+            process_read_audit(&db, query, QueryMode::Exact, json).await
+        },
+        Opt{cmd: Some(OptSub::Tui{value, include_inactive}), ..} => userdb_phone::tui::run(&db, value, include_inactive, true).await,
+    }?;
+    Ok(())
 }
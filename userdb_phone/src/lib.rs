@@ -3,4 +3,6 @@
 pub mod opt;
 /// The process submodule houses all of the async functions which
 /// do the actual crud work.
-pub mod process;
\ No newline at end of file
+pub mod process;
+/// The tui submodule houses the interactive terminal ui.
+pub mod tui;
\ No newline at end of file
@@ -3,15 +3,30 @@ mod create;
 mod read;
 mod update;
 mod delete;
+mod import;
+mod phone;
+mod department;
+mod connection;
 // import the contents of the submodules
 // so that they can be imported from `opt`
 pub use create::*;
 pub use read::*;
 pub use update::*;
 pub use delete::*;
+pub use import::*;
+pub use phone::*;
+pub use department::*;
+pub use connection::*;
 
+use std::path::PathBuf;
 use structopt::StructOpt;
 
+use userdb_core::AuditEntity;
+use userdb_core::Date;
+use userdb_core::NumberString;
+use userdb_core::export::ExportFormat;
+use userdb_core::snapshot::ConflictPolicy;
+
 
 #[derive(StructOpt, Debug)]
 #[structopt(about="Search for people, and more...")]
@@ -33,6 +48,15 @@ pub struct Opt {
         #[structopt(short, long)]
         pub dept: Option<String>,
 
+        /// Fetch phone records for people by email address or chat handle
+        #[structopt(short, long)]
+        pub contact: Option<String>,
+
+        /// Fetch phone records for the people holding a number, given in full
+        /// or as its last digits, eg 7091
+        #[structopt(long)]
+        pub number: Option<NumberString>,
+
         /// Optionally specify the fullname (alternative to using --name flag)
         #[structopt(name="FULLNAME")]
         pub fullname: Option<String>,
@@ -41,6 +65,17 @@ pub struct Opt {
         #[structopt(short,long)]
         pub json: bool,
 
+        /// Include people who have been terminated
+        #[structopt(long="include-inactive")]
+        pub include_inactive: bool,
+
+        /// Fetch phone records for people as they were at the end of the supplied day (YYYY-MM-DD)
+        #[structopt(long="as-of")]
+        pub as_of: Option<Date>,
+
+        #[structopt(flatten)]
+        pub connection: ConnectionOpt,
+
         /// Optional subcommands
         #[structopt(subcommand)]
         pub cmd: Option<OptSub>
@@ -73,6 +108,105 @@ pub enum OptSub {
     Delete {
         #[structopt(subcommand)]
         sub: DeleteOpt,
-    }
+    },
+    /// Transfer and share phones between people
+    Phone {
+        #[structopt(subcommand)]
+        sub: PhoneOpt,
+    },
+    /// Merge departments
+    Department {
+        #[structopt(subcommand)]
+        sub: DepartmentOpt,
+    },
+    /// Import entities in bulk
+    Import {
+        #[structopt(subcommand)]
+        sub: ImportOpt,
+    },
+    /// Export every person and their phones
+    Export {
+        /// The output format: csv, jsonl, vcard or org
+        #[structopt(short, long, default_value = "csv")]
+        format: ExportFormat,
+
+        /// Write to a file instead of stdout
+        #[structopt(short, long, parse(from_os_str))]
+        output: Option<PathBuf>,
+    },
+    /// Write a snapshot of the whole database
+    Dump {
+        /// Write to a file instead of stdout
+        #[structopt(short, long, parse(from_os_str))]
+        output: Option<PathBuf>,
+    },
+    /// Load a snapshot written by dump
+    Restore {
+        /// Path to the snapshot
+        #[structopt(name = "FILE", parse(from_os_str))]
+        file: PathBuf,
+
+        /// What to do with rows which already exist: skip, update or fail
+        #[structopt(long, default_value = "skip")]
+        on_conflict: ConflictPolicy,
+
+        /// Check the snapshot against the database without restoring it
+        #[structopt(long)]
+        dry_run: bool,
+    },
+    /// Remove people terminated longer ago than the retention period
+    Purge {
+        /// The number of days to keep people after their end date
+        #[structopt(long = "retention-days", default_value = "365")]
+        retention_days: u32,
+
+        /// List the people who would be removed without removing them
+        #[structopt(long)]
+        dry_run: bool,
+    },
+    /// Query the log of changes made to the database, most recent first
+    Audit {
+        /// The kind of entity changed: person, phone, assignment, contact, desk,
+        /// title, department, location, dialplan or extension_range
+        #[structopt(short, long)]
+        entity: Option<AuditEntity>,
+
+        /// The id of the entity changed
+        #[structopt(short, long)]
+        id: Option<i32>,
+
+        /// The login of the person whose records changed
+        #[structopt(short, long)]
+        login: Option<String>,
+
+        /// Who made the changes
+        #[structopt(short, long)]
+        by: Option<String>,
+
+        /// Changes made on or after this day (YYYY-MM-DD)
+        #[structopt(long)]
+        since: Option<Date>,
+
+        /// Changes made on or before this day (YYYY-MM-DD)
+        #[structopt(long)]
+        until: Option<Date>,
+
+        /// The maximum number of changes to show
+        #[structopt(long, default_value = "50")]
+        limit: u32,
+
+        /// Display results as json instead of as a table
+        #[structopt(short, long)]
+        json: bool,
+    },
+    /// Browse and edit the directory interactively, searching as you type
+    Tui {
+        /// The initial search
+        #[structopt(name="VALUE")]
+        value: Option<String>,
+        /// Include people who have been terminated
+        #[structopt(long="include-inactive")]
+        include_inactive: bool,
+    },
 }
 
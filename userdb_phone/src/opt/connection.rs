@@ -0,0 +1,40 @@
+
+use structopt::StructOpt;
+// internal
+use userdb_core::Config;
+use userdb_core::ConfigError;
+use userdb_core::Profile;
+
+//----------------------------//
+//         CONNECTION         //
+//----------------------------//
+
+/// Flags selecting the database to connect to, and who to attribute
+/// changes to. These take precedence over `USERDB_PROFILE`,
+/// `USERDB_DATABASE_URL`, `USERDB_ACTOR` and the config files.
+#[derive(StructOpt, Debug)]
+pub struct ConnectionOpt {
+    /// Connect using the named profile from the userdb config (eg prod, staging, test)
+    #[structopt(long, global = true)]
+    pub profile: Option<String>,
+
+    /// Connect to the supplied database url, ignoring the configured profiles
+    #[structopt(long = "database-url", global = true)]
+    pub database_url: Option<String>,
+
+    /// Record changes in the audit log as made by the supplied identity rather than the OS user
+    #[structopt(long, global = true)]
+    pub actor: Option<String>,
+}
+
+impl ConnectionOpt {
+    /// Resolve the connection profile from the flags, the environment, and the
+    /// config files.
+    pub fn profile(&self) -> Result<Profile, ConfigError> {
+        let mut profile = Config::load_profile(self.profile.as_deref(), self.database_url.as_deref())?;
+        if self.actor.is_some() {
+            profile.actor = self.actor.clone();
+        }
+        Ok(profile)
+    }
+}
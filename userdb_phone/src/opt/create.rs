@@ -1,8 +1,11 @@
 
 use structopt::StructOpt;
 
+use userdb_core::ContactCategory;
+use userdb_core::Date;
+use userdb_core::ExtensionRange;
+use userdb_core::PersonStatus;
 use userdb_core::PhoneCategory;
-use userdb_core::Location;
 use userdb_core::NumberString;
 
 //-------------------//
@@ -31,7 +34,15 @@ pub enum CreateOpt {
 
         /// Provide the title
         #[structopt(name = "TITLE")]
-        title: String
+        title: String,
+
+        /// Optionally provide the status: active, onleave, contractor or terminated
+        #[structopt(short, long)]
+        status: Option<PersonStatus>,
+
+        /// Optionally provide the date the person starts, as YYYY-MM-DD
+        #[structopt(long = "start-date")]
+        start_date: Option<Date>,
     },
     Phone {
 
@@ -47,10 +58,39 @@ pub enum CreateOpt {
         #[structopt(short, long)]
         category: PhoneCategory,
 
-        /// Specify the location of your phone number
+        /// Specify the location of your phone number, by name, code or alias
+        #[structopt(short, long)]
+        location: String,
+
+        /// Optionally label the phone (eg "work cell")
+        #[structopt(long)]
+        label: Option<String>,
+
+        /// Mark the phone as the primary phone of its category
+        #[structopt(long)]
+        primary: bool,
+    },
+    /// Add an email address, chat handle or personal email to a person
+    Contact {
+        /// specify the login of the person
+        #[structopt(short="u", long)]
+        login: String,
+
+        /// Specify the category of the contact: email, chat or personal
         #[structopt(short, long)]
-        location: Location,
+        category: ContactCategory,
 
+        /// Specify the address or handle
+        #[structopt(name = "VALUE")]
+        value: String,
+
+        /// Optionally label the contact (eg "slack")
+        #[structopt(long)]
+        label: Option<String>,
+
+        /// Mark the contact as the primary contact of its category
+        #[structopt(long)]
+        primary: bool,
     },
     Title {
         /// Specify the name
@@ -61,5 +101,58 @@ pub enum CreateOpt {
         /// Specify the name
         #[structopt(name = "DEPARTMENT")]
         department: String,
+    },
+    Location {
+        /// Specify the name (eg PlayaVista)
+        #[structopt(name = "NAME")]
+        name: String,
+
+        /// Specify a short code for the location (eg pv)
+        #[structopt(name = "CODE")]
+        code: String,
+
+        /// Specify another name the location is known by. May be repeated
+        #[structopt(short, long = "alias", number_of_values = 1)]
+        aliases: Vec<String>,
+
+        /// Optionally provide the address
+        #[structopt(long)]
+        address: Option<String>,
+
+        /// Specify the timezone, as named in the tz database (eg America/Vancouver)
+        #[structopt(short, long, default_value = "UTC")]
+        timezone: String,
+
+        /// Specify the two letter ISO code of the country (eg US)
+        #[structopt(short, long)]
+        country: String,
+
+        /// Specify the calling code of the country, without the + (eg 44).
+        /// Not needed in the US, Canada or India
+        #[structopt(long)]
+        calling_code: Option<String>,
+
+        /// Specify the prefix dialed before national numbers in the
+        /// country (eg 0). Not needed in the US, Canada or India
+        #[structopt(long)]
+        trunk_prefix: Option<String>,
+    },
+    DialPlan {
+        /// Specify the location, by name, code or alias
+        #[structopt(name = "LOCATION")]
+        location: String,
+
+        /// Specify the main switchboard number
+        #[structopt(short, long)]
+        switchboard: NumberString,
+
+        /// Specify the prefix which, followed by an extension, dials it
+        /// directly from outside, with its country code (eg +1604555)
+        #[structopt(short, long)]
+        did_prefix: NumberString,
+
+        /// Specify a range of extensions, as first-last (eg 6000-6999). May be repeated
+        #[structopt(short, long = "extensions", number_of_values = 1)]
+        extensions: Vec<ExtensionRange>,
     }
 }
\ No newline at end of file
@@ -1,8 +1,8 @@
 
 use structopt::StructOpt;
 // internal
+use userdb_core::ContactCategory;
 use userdb_core::PhoneCategory;
-use userdb_core::Location;
 use userdb_core::NumberString;
 
 //------------------//
@@ -39,13 +39,41 @@ pub enum DeleteOpt {
         )]
         category: Option<PhoneCategory>,
 
-        /// Specify the location of your phone number
+        /// Specify the location of your phone number, by name, code or alias
         #[structopt(
             short, 
             long,
             requires_all = &["login", "number", "category"]
         )]
-        location: Option<Location>,
+        location: Option<String>,
+    },
+    Contact {
+        /// delete by contact id
+        #[structopt(short,long)]
+        id: Option<u32>,
+
+        /// specify the login of the person
+        #[structopt(
+            short="u", long,
+            requires_all = &["category", "value"]
+        )]
+        login: Option<String>,
+
+        /// Specify the category of the contact
+        #[structopt(
+            short,
+            long,
+            requires_all = &["login", "value"]
+        )]
+        category: Option<ContactCategory>,
+
+        /// Specify the address or handle
+        #[structopt(
+            short,
+            long,
+            requires_all = &["login", "category"]
+        )]
+        value: Option<String>,
     },
     Person {
          /// Name of the department to delete
@@ -74,5 +102,19 @@ pub enum DeleteOpt {
         /// Id of the title to delete
         #[structopt(short, long)]
         id: Option<u32>
+    },
+    Location {
+        /// Name of the location to delete
+        #[structopt(short, long)]
+        name: Option<String>,
+
+        /// Id of the location to delete
+        #[structopt(short, long)]
+        id: Option<u32>
+    },
+    DialPlan {
+        /// The location whose dial plan to delete, by name, code or alias
+        #[structopt(short, long)]
+        location: String,
     }
 }
\ No newline at end of file
@@ -0,0 +1,20 @@
+use structopt::StructOpt;
+
+//------------------------//
+//       DEPARTMENT       //
+//------------------------//
+
+/// Reorganize departments
+#[derive(StructOpt, Debug)]
+pub enum DepartmentOpt {
+    /// Move everyone in one department into another, and delete the first
+    Merge {
+        /// The department to merge, which is deleted
+        #[structopt(name = "SOURCE")]
+        source: String,
+
+        /// The department to merge into
+        #[structopt(name = "DESTINATION")]
+        destination: String,
+    },
+}
@@ -0,0 +1,21 @@
+use structopt::StructOpt;
+use std::path::PathBuf;
+
+//-------------------//
+//       IMPORT      //
+//-------------------//
+
+#[derive(StructOpt, Debug)]
+pub enum ImportOpt {
+    /// Import people and phones from a csv file with the columns
+    /// first, last, login, department, title, number, category, location
+    Csv {
+        /// Path to the csv file
+        #[structopt(name = "FILE", parse(from_os_str))]
+        file: PathBuf,
+
+        /// Validate the file against the database without importing it
+        #[structopt(long)]
+        dry_run: bool,
+    },
+}
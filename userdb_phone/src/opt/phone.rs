@@ -0,0 +1,48 @@
+use structopt::StructOpt;
+
+//-------------------//
+//       PHONE       //
+//-------------------//
+
+/// Manage who phones are assigned to
+#[derive(StructOpt, Debug)]
+pub enum PhoneOpt {
+    /// Move a person's phones to someone else
+    Transfer {
+        /// The login of the person giving up the phones
+        #[structopt(long)]
+        from: String,
+
+        /// The login of the person receiving the phones
+        #[structopt(long)]
+        to: String,
+
+        /// Only move the phone with this id, rather than all of them
+        #[structopt(short, long)]
+        id: Option<u32>,
+    },
+    /// Assign an existing phone to another person as well
+    Share {
+        /// The id of the phone
+        #[structopt(short, long)]
+        id: u32,
+
+        /// The login of the person to share the phone with
+        #[structopt(short = "u", long)]
+        login: String,
+
+        /// Mark the phone as the person's primary phone of its category
+        #[structopt(long)]
+        primary: bool,
+    },
+    /// Stop sharing a phone with a person, leaving it with everyone else
+    Unshare {
+        /// The id of the phone
+        #[structopt(short, long)]
+        id: u32,
+
+        /// The login of the person to take the phone from
+        #[structopt(short = "u", long)]
+        login: String,
+    },
+}
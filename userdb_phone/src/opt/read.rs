@@ -1,8 +1,9 @@
 
 use structopt::StructOpt;
 
+use userdb_core::ContactCategory;
+use userdb_core::Date;
 use userdb_core::PhoneCategory;
-use userdb_core::Location;
 use userdb_core::NumberString;
 
 //-----------------------//
@@ -29,10 +30,26 @@ pub enum ReadOpt {
         #[structopt(short, long)]
         dept: Option<String>,
 
+        /// specify an email address or chat handle
+        #[structopt(short, long)]
+        contact: Option<String>,
+
+        /// specify a number held by the person, in full or as its last digits
+        #[structopt(long)]
+        number: Option<NumberString>,
+
         /// Optionally specify the fullname (alternative to using --name flag)
         #[structopt(name="FULLNAME")]
         fullname: Option<String>,
 
+        /// Include people who have been terminated
+        #[structopt(long="include-inactive")]
+        include_inactive: bool,
+
+        /// Read people as they were at the end of the supplied day (YYYY-MM-DD)
+        #[structopt(long="as-of")]
+        as_of: Option<Date>,
+
         /// Display results as json instead of a table
         #[structopt(short,long)]
         json: bool,
@@ -47,6 +64,12 @@ pub enum ReadOpt {
         #[structopt(short,long)]
         json: bool,
     },
+    /// Read the locations, along with their codes, aliases, timezones and countries
+    Location {
+        /// Display results as json instead of a table
+        #[structopt(short,long)]
+        json: bool,
+    },
     Phone {
         /// specify the id
         #[structopt(short, long)]
@@ -60,9 +83,45 @@ pub enum ReadOpt {
         #[structopt(short, long)]
         category: Option<PhoneCategory>,
 
-        /// Specify the location of your phone number
+        /// Specify the location of your phone number, by name, code or alias
+        #[structopt(short, long)]
+        location: Option<String>,
+
+        /// Read phones as they were at the end of the supplied day (YYYY-MM-DD)
+        #[structopt(long="as-of")]
+        as_of: Option<Date>,
+
+        /// Display results as json instead of as a table
+        #[structopt(short,long)]
+        json: bool,
+    },
+    /// Read email addresses, chat handles and other contacts
+    Contact {
+        /// specify the id
+        #[structopt(short, long)]
+        id: Option<u32>,
+
+        /// Specify the login of the person
+        #[structopt(short="u", long)]
+        login: Option<String>,
+
+        /// Specify the category of the contact
+        #[structopt(short, long)]
+        category: Option<ContactCategory>,
+
+        /// Specify the address or handle to match
+        #[structopt(short, long)]
+        value: Option<String>,
+
+        /// Display results as json instead of as a table
+        #[structopt(short,long)]
+        json: bool,
+    },
+    /// Read the switchboard number, extension ranges and DID prefix of each location
+    DialPlan {
+        /// Only show the dial plan of the location, given by name, code or alias
         #[structopt(short, long)]
-        location: Option<Location>,
+        location: Option<String>,
 
         /// Display results as json instead of as a table
         #[structopt(short,long)]
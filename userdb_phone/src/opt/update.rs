@@ -1,14 +1,59 @@
 
 use structopt::StructOpt;
 // internal
+use userdb_core::ContactCategory;
+use userdb_core::PersonStatus;
 use userdb_core::PhoneCategory;
-use userdb_core::Location;
 use userdb_core::NumberString;
 
 //-------------------------//
 //          UPDATE         //
 //-------------------------//
 
+/// Flags updating a person's status and the dates they started and finished
+#[derive(StructOpt, Debug)]
+pub struct StatusOpt {
+    /// Optionally provide a status update: active, onleave, contractor or terminated
+    #[structopt(short, long)]
+    pub status: Option<PersonStatus>,
+
+    /// Optionally provide a start date update, as YYYY-MM-DD. 
+    /// An empty date ("") clears it
+    #[structopt(long = "start-date")]
+    pub start_date: Option<String>,
+
+    /// Optionally provide an end date update, as YYYY-MM-DD. 
+    /// An empty date ("") clears it
+    #[structopt(long = "end-date")]
+    pub end_date: Option<String>,
+}
+
+/// Flags updating where a person sits
+#[derive(StructOpt, Debug)]
+pub struct DeskOpt {
+    /// The location of the desk to update, by name, code or alias. 
+    /// Only needed by people with desks at more than one location
+    #[structopt(long="desk-location")]
+    pub desk_location: Option<String>,
+
+    /// Optionally provide a building update. An empty building ("") clears it
+    #[structopt(long)]
+    pub building: Option<String>,
+
+    /// Optionally provide a floor update. An empty floor ("") clears it
+    #[structopt(long)]
+    pub floor: Option<String>,
+
+    /// Optionally provide a room or desk update. An empty room ("") clears it
+    #[structopt(long)]
+    pub room: Option<String>,
+
+    /// Remove the desk at --desk-location, or every desk of the person 
+    /// without it
+    #[structopt(long="clear-desk", conflicts_with_all=&["building", "floor", "room"])]
+    pub clear_desk: bool,
+}
+
 #[derive(StructOpt, Debug)]
 pub enum UpdateOpt {
     Person {
@@ -19,7 +64,7 @@ pub enum UpdateOpt {
 
         /// Provide the target login to apply the updates to, 
         /// as an alternative to --id
-        #[structopt(short, long="from-login")]
+        #[structopt(short="F", long="from-login")]
         from_login: Option<String>,
 
         /// Optionally provide first name update
@@ -41,6 +86,17 @@ pub enum UpdateOpt {
         /// Optionally provide a title id update
         #[structopt(short, long="title-id" )]
         title: Option<i32>,
+
+        /// Optionally provide the login of the person's manager. 
+        /// An empty login ("") removes their manager
+        #[structopt(short, long)]
+        manager: Option<String>,
+
+        #[structopt(flatten)]
+        status: StatusOpt,
+
+        #[structopt(flatten)]
+        desk: DeskOpt,
     },
     Phone {
         /// Provide the phone's id to apply updates to. 
@@ -55,9 +111,38 @@ pub enum UpdateOpt {
         #[structopt(short, long )]
         category: Option<PhoneCategory>,
 
-        /// Optionally provide a location update
+        /// Optionally provide a location update, by name, code or alias
         #[structopt(short="u", long )]
-        location: Option<Location>,
+        location: Option<String>,
+
+        /// Optionally provide a label update. An empty label clears it
+        #[structopt(long)]
+        label: Option<String>,
+
+        /// Optionally mark (true) or unmark (false) the phone as primary
+        #[structopt(long)]
+        primary: Option<bool>,
+    },
+    Contact {
+        /// Provide the contact's id to apply updates to.
+        #[structopt(short, long)]
+        id: i32,
+
+        /// Optionally provide a category update
+        #[structopt(short, long)]
+        category: Option<ContactCategory>,
+
+        /// Optionally provide an address or handle update
+        #[structopt(short, long)]
+        value: Option<String>,
+
+        /// Optionally provide a label update. An empty label clears it
+        #[structopt(long)]
+        label: Option<String>,
+
+        /// Optionally mark (true) or unmark (false) the contact as primary
+        #[structopt(long)]
+        primary: Option<bool>,
     },
     Title {
         /// Provide the title's id to apply updates to. 
@@ -76,5 +161,46 @@ pub enum UpdateOpt {
         /// provide name update
         #[structopt(name = "NAME")]
         name: String,
+    },
+    Location {
+        /// Provide the location's id to apply updates to.
+        #[structopt(name = "ID")]
+        id: i32,
+
+        /// Optionally provide a name update
+        #[structopt(short, long)]
+        name: Option<String>,
+
+        /// Optionally provide a code update
+        #[structopt(long)]
+        code: Option<String>,
+
+        /// Optionally replace the aliases. May be repeated
+        #[structopt(short, long = "alias", number_of_values = 1)]
+        aliases: Vec<String>,
+
+        /// Remove every alias
+        #[structopt(long, conflicts_with = "aliases")]
+        clear_aliases: bool,
+
+        /// Optionally provide an address update. An empty address clears it
+        #[structopt(long)]
+        address: Option<String>,
+
+        /// Optionally provide a timezone update
+        #[structopt(short, long)]
+        timezone: Option<String>,
+
+        /// Optionally provide a country update
+        #[structopt(short, long)]
+        country: Option<String>,
+
+        /// Optionally provide a calling code update. An empty calling code clears it
+        #[structopt(long)]
+        calling_code: Option<String>,
+
+        /// Optionally provide a trunk prefix update. An empty trunk prefix clears it
+        #[structopt(long)]
+        trunk_prefix: Option<String>,
     }
 }
\ No newline at end of file
@@ -5,4 +5,10 @@ pub mod read;
 /// All async functions for updating data in the phonedb live here.
 pub mod update;
 /// All async functions for deleting data in the phonedb live here.
-pub mod delete;
\ No newline at end of file
+pub mod delete;
+/// All async functions for importing data into the phonedb live here.
+pub mod import;
+/// All async functions for exporting data from the phonedb live here.
+pub mod export;
+/// All async functions for dumping and restoring snapshots of the phonedb live here.
+pub mod snapshot;
\ No newline at end of file
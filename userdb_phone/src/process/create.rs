@@ -1,13 +1,20 @@
 
 use colored::*;
-use sqlx::postgres::PgPoolOptions;
 
 // internal
 use userdb_core::create;
-use userdb_core::DB_URL;
+use userdb_core::update;
+use userdb_core::update::person::id::PersonUpdate as PersonUpdateById;
+use userdb_core::prelude::*;
+use userdb_core::Date;
+use userdb_core::PersonStatus;
+use userdb_core::ContactCategory;
+use userdb_core::ExtensionRange;
 use userdb_core::PhoneCategory;
-use userdb_core::Location;
+use userdb_core::Locations;
 use userdb_core::NumberString;
+use userdb_core::UserDb;
+use userdb_core::UserDbError;
 
 
 //
@@ -15,35 +22,44 @@ use userdb_core::NumberString;
 //
 
 /// Process the request to create a person, given required fields
+#[allow(clippy::too_many_arguments)]
 pub async fn process_create_person(
+    db: &UserDb,
     first: &str, 
     last: &str, 
     login: &str, 
     department: &str, 
-    title: &str
-) -> Result<(),sqlx::Error> {
-    let  pool = PgPoolOptions::new()
-        .max_connections(1)
-        .connect(DB_URL).await?;
-        
-    let result = create::person::create(&pool, first, last, login, department, title).await?;
+    title: &str,
+    status: Option<PersonStatus>,
+    start_date: Option<Date>,
+) -> Result<(), UserDbError> {
+    // the status and start date are set along with the person, or not at all
+    let mut tx = db.begin().await?;
+    let result = create::person::create(&mut tx, first, last, login, department, title).await?;
+    let person_update = PersonUpdateById::new(result)
+        .status(status)
+        .start_date(start_date.map(|date| date.to_string()));
+    if !person_update.is_empty() {
+        update::person::id::update(&mut tx, person_update).await?;
+    }
+    tx.commit().await?;
     println!("{} {}","ID:".bright_green(), result);
     Ok(())
 }
 
 /// Process request to create a phone entry for an individual, specified via the login
 pub async fn process_create_phone(
+    db: &UserDb,
     login: &str, 
     number:&NumberString, 
     category: &PhoneCategory, 
-    location: &Location, 
-    
-) -> Result<(),sqlx::Error> {
-    let  pool = PgPoolOptions::new()
-        .max_connections(1)
-        .connect(DB_URL).await?;
-        
-    let result = create::phone::create(&pool, login, number, category, location).await?;
+    location: &str, 
+    label: Option<&str>,
+    primary: bool,
+) -> Result<(), UserDbError> {
+    let locations = Locations::load(db).await?;
+    let location = locations.parse(location)?;
+    let result = create::phone::create(db, login, number, category, location, label, primary).await?;
     match result {
         Some(val) => println!("Created Phone with id: {}", val),
         None => eprintln!("\n\t{} Phone number already exists", "Warning:".bright_green())
@@ -51,20 +67,26 @@ pub async fn process_create_phone(
     Ok(())
 }
 
+/// Process request to add a contact (eg an email address) to the person with `login`
+pub async fn process_create_contact(
+    db: &UserDb,
+    login: &str,
+    category: &ContactCategory,
+    value: &str,
+    label: Option<&str>,
+    primary: bool,
+) -> Result<(), UserDbError> {
+    let result = create::contact::create(db, login, category, value, label, primary).await?;
+    println!("Created Contact with id: {}", result);
+    Ok(())
+}
+
 /// Process the request to create a new title
 pub async fn process_create_title(
+    db: &UserDb,
     title: &str,     
-) -> Result<(),sqlx::Error> {
-    let  pool = PgPoolOptions::new()
-        .max_connections(1)
-        .connect(DB_URL).await?;
-        
-    let result = create::title::create(&pool, &title).await;
-    let result = match result {
-        Ok(r) => r,
-        Err(sqlx::Error::RowNotFound) =>  None,
-        Err(e) => return Err(e)
-    };
+) -> Result<(), UserDbError> {
+    let result = create::title::create(db, &title).await?;
     match result {
         Some(val) => println!("Created Title with id: {}", val),
         None => eprintln!("\n\t{} Title '{}' already exists", "Warning:".bright_cyan(), title)
@@ -74,21 +96,72 @@ pub async fn process_create_title(
 
 /// Process the request to create a new department
 pub async fn process_create_department(
+    db: &UserDb,
     department: &str,     
-) -> Result<(),sqlx::Error> {
-    let  pool = PgPoolOptions::new()
-        .max_connections(1)
-        .connect(DB_URL).await?;
-        
-    let result = create::department::create(&pool, &department).await;
-    let result = match result {
-        Ok(r) => r,
-        Err(sqlx::Error::RowNotFound) =>  None,
-        Err(e) => return Err(e)
-    };
+) -> Result<(), UserDbError> {
+    let result = create::department::create(db, &department).await?;
     match result {
         Some(val) => println!("Created Department with id: {}", val),
         None => eprintln!("\n\t{} Department '{}' already exists", "Warning:".bright_green(), department)
     };
     Ok(())
 }
+
+/// Process the request to create a new location
+#[allow(clippy::too_many_arguments)]
+pub async fn process_create_location(
+    db: &UserDb,
+    name: &str,
+    code: &str,
+    aliases: &[String],
+    address: Option<&str>,
+    timezone: &str,
+    country: &str,
+    calling_code: Option<&str>,
+    trunk_prefix: Option<&str>,
+) -> Result<(), UserDbError> {
+    let result = create::location::create(db, name, code, aliases, address, timezone, country, calling_code, trunk_prefix).await?;
+    match result {
+        Some(val) => println!("Created Location with id: {}", val),
+        None => eprintln!("\n\t{} Location '{}' or code '{}' already exists", "Warning:".bright_green(), name, code)
+    };
+    Ok(())
+}
+
+/// Process the request to create the dial plan of a location, along with
+/// the ranges its extensions are drawn from
+pub async fn process_create_dial_plan(
+    db: &UserDb,
+    location: &str,
+    switchboard: &NumberString,
+    did_prefix: &NumberString,
+    extensions: &[ExtensionRange],
+) -> Result<(), UserDbError> {
+    let locations = Locations::load(db).await?;
+    let location = locations.parse(location)?;
+    let mut tx = db.begin().await?;
+    match create::dial_plan::create(&mut tx, location, switchboard, did_prefix).await? {
+        Some(name) => {
+            for range in extensions {
+                create::dial_plan::add_range(&mut tx, location, range).await?;
+            }
+            tx.commit().await?;
+            println!("Created dial plan for {}", name);
+        }
+        None => eprintln!("\n\t{} {} already has a dial plan", "Warning:".bright_green(), location),
+    };
+    Ok(())
+}
+
+/// Process a request to share the phone with `phone_id` with the person
+/// with `login`, in addition to those who already have it.
+pub async fn process_share_phone(
+    db: &UserDb,
+    phone_id: u32,
+    login: &str,
+    primary: bool,
+) -> Result<(), UserDbError> {
+    let id = create::person_phone::share(db, login, phone_id, primary).await?;
+    println!("Shared Phone with id: {} with {}", id, login);
+    Ok(())
+}
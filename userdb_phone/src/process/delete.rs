@@ -1,16 +1,18 @@
-use sqlx::postgres::PgPoolOptions;
 //use std::collections::HashMap;
-use colored::*;
 
 //type RowMap = HashMap<Location, PhoneRow>;
 
+use colored::*;
+
 // internal
 use userdb_core::delete;
-use userdb_core::DB_URL;
 //use userdb_core::PhoneRow;
+use userdb_core::ContactCategory;
 use userdb_core::PhoneCategory;
-use userdb_core::Location;
+use userdb_core::Locations;
 use userdb_core::NumberString;
+use userdb_core::UserDb;
+use userdb_core::UserDbError;
 
 /// Process the request to delete a phone, identified by a user's login along iwth 
 /// the number, category, and location. This function will do the following things:
@@ -19,125 +21,143 @@ use userdb_core::NumberString;
 /// - Delete the phone record, as long as no additional releationships exist between the phone
 ///   and another user. (Users may share extentions for instance)
 pub async fn process_delete_phone(
+    db: &UserDb,
     login: &str, 
     number:&NumberString, 
     category: &PhoneCategory, 
-    location: &Location, 
+    location: &str, 
     
-) -> Result<(),sqlx::Error> {
-    let  pool = PgPoolOptions::new()
-        .max_connections(1)
-        .connect(DB_URL).await?;
-        
-    let result = delete::person_phone::delete(&pool, login, number, category, location).await?;
-    match result {
-        Some(val) => println!("Deleted Phone?: {}", val>0),
-        None => eprintln!("\n\t{} Phone number not associated with {}", "Warning:".bright_green(), login)
-    };
+) -> Result<(), UserDbError> {
+    let locations = Locations::load(db).await?;
+    let location = locations.parse(location)?;
+    delete::person_phone::delete(db, login, number, category, location).await?;
+    println!("Deleted {} phone {} at {} from {}", category, number, location, login);
+    Ok(())
+}
+
+/// Process a request to stop sharing the phone with `phone_id` with the person
+/// with `login`. Everyone else keeps the phone.
+pub async fn process_unshare_phone(db: &UserDb, phone_id: u32, login: &str) -> Result<(), UserDbError> {
+    let id = delete::person_phone::unshare(db, login, phone_id).await?;
+    println!("Unshared Phone with id: {} from {}", id, login);
     Ok(())
 }
 
 /// Process a request to delete a phone entry provided its `id` in the phone table.
-pub async fn process_delete_phone_by_id(id: u32) -> Result<(), sqlx::Error> {
-    let  pool = PgPoolOptions::new()
-        .max_connections(1)
-        .connect(DB_URL).await?;
-        
-    let result = delete::phone::delete_by_id(&pool, id).await?;
-    match result {
-        Some(val) => println!("Deleted Phone with id: {}", val),
-        None => eprintln!("\n\t{} Id {} does not exist","Warning:".bright_green(), id)
-    };
+pub async fn process_delete_phone_by_id(db: &UserDb, id: u32) -> Result<(), UserDbError> {
+    let val = delete::phone::delete_by_id(db, id).await?;
+    println!("Deleted Phone with id: {}", val);
+    Ok(())
+}
+
+/// Process the request to delete the contact of the person with `login`
+/// matching the `category` and `value`.
+pub async fn process_delete_contact(
+    db: &UserDb,
+    login: &str,
+    category: &ContactCategory,
+    value: &str,
+) -> Result<(), UserDbError> {
+    delete::contact::delete(db, login, category, value).await?;
+    println!("Deleted {} contact {} from {}", category, value, login);
+    Ok(())
+}
+
+/// Process a request to delete a contact provided its `id` in the contact table.
+pub async fn process_delete_contact_by_id(db: &UserDb, id: u32) -> Result<(), UserDbError> {
+    let val = delete::contact::delete_by_id(db, id).await?;
+    println!("Deleted Contact with id: {}", val);
     Ok(())
 }
 
 /// Process the request to delete a department by name. This method will not 
 /// delete the department, should it be associated with one or more `person`s.
-pub async fn process_delete_dept(name: &str) -> Result<(), sqlx::Error> {
-    let  pool = PgPoolOptions::new()
-        .max_connections(1)
-        .connect(DB_URL).await?;
-        
-    let result = delete::department::delete(&pool, name).await?;
-    match result {
-        Some(val) => println!("Deleted Dept with id: {}", val),
-        None => eprintln!("\n\t{} Dept '{}' does not exist","Warning:".bright_cyan(), name)
-    };
+pub async fn process_delete_dept(db: &UserDb, name: &str) -> Result<(), UserDbError> {
+    let val = delete::department::delete(db, name).await?;
+    println!("Deleted Dept with id: {}", val);
     Ok(())
 }
 
 /// Process request to delete a department given its `id` in the `department` table. This
 /// function will not attempt to delete the department if the department is associated with
 /// one or more `person`s.
-pub async fn process_delete_dept_by_id(id: u32) -> Result<(), sqlx::Error> {
-    let  pool = PgPoolOptions::new()
-        .max_connections(1)
-        .connect(DB_URL).await?;
-        
-    let result = delete::department::delete_by_id(&pool, id).await?;
-    match result {
-        Some(val) => println!("Deleted Department with id: {}", val),
-        None => eprintln!("\n\t{} Department Id '{}' does not exist", "Warning:".bright_green(), id)
-    };
+pub async fn process_delete_dept_by_id(db: &UserDb, id: u32) -> Result<(), UserDbError> {
+    let val = delete::department::delete_by_id(db, id).await?;
+    println!("Deleted Department with id: {}", val);
     Ok(())
 }
 
 /// Process request to delete the supplied title. This will not succeed if the 
 /// `title` is in use by one or more `person`s.
-pub async fn process_delete_title(name: &str) -> Result<(), sqlx::Error> {
-    let  pool = PgPoolOptions::new()
-        .max_connections(1)
-        .connect(DB_URL).await?;
-        
-    let result = delete::title::delete(&pool, name).await?;
-    match result {
-        Some(val) => println!("Deleted Title with id: {}", val),
-        None => eprintln!("\n\t{} Title '{}' does not exist", "Warning:".bright_cyan(),name)
-    };
+pub async fn process_delete_title(db: &UserDb, name: &str) -> Result<(), UserDbError> {
+    let val = delete::title::delete(db, name).await?;
+    println!("Deleted Title with id: {}", val);
     Ok(())
 }
 
 /// Process the request to delete a `title` by its `id` in the `title` table. This 
 /// will not succeed if the `title` is associated with one or more `person`s.
-pub async fn process_delete_title_by_id(id: u32) -> Result<(), sqlx::Error> {
-    let  pool = PgPoolOptions::new()
-        .max_connections(1)
-        .connect(DB_URL).await?;
-        
-    let result = delete::title::delete_by_id(&pool, id).await?;
-    match result {
-        Some(val) => println!("Deleted Department with id: {}", val),
-        None => eprintln!("\n\t{} Title Id '{}' does not exist","Warning:".bright_green(), id)
-    };
+pub async fn process_delete_title_by_id(db: &UserDb, id: u32) -> Result<(), UserDbError> {
+    let val = delete::title::delete_by_id(db, id).await?;
+    println!("Deleted Title with id: {}", val);
     Ok(())
 }
 
 /// Process request to delete a `person` by `login`.
-pub async fn process_delete_person(login: &str) -> Result<(), sqlx::Error> {
-    let  pool = PgPoolOptions::new()
-        .max_connections(1)
-        .connect(DB_URL).await?;
-        
-    let result = delete::person::delete(&pool, login).await?;
-    match result {
-        Some(val) => println!("Deleted Person with id: {}", val),
-        None => eprintln!("\n\t{} Person '{}' does not exist", "Warning:".bright_green(), login)
-    };
+pub async fn process_delete_person(db: &UserDb, login: &str) -> Result<(), UserDbError> {
+    let val = delete::person::delete(db, login).await?;
+    println!("Terminated Person with id: {}", val);
     Ok(())
 }
 
 /// Process the request to delete a `person` by their `id` in the `person` table. 
-pub async fn process_delete_person_by_id(id: u32) -> Result<(), sqlx::Error> {
-    let  pool = PgPoolOptions::new()
-        .max_connections(1)
-        .connect(DB_URL).await?;
-        
-    let result = delete::person::delete_by_id(&pool, id).await?;
-    match result {
-        Some(val) => println!("Deleted Person with id: {}", val),
-        None => eprintln!("\n\tPerson Id '{}' does not exist", id)
-    };
+pub async fn process_delete_person_by_id(db: &UserDb, id: u32) -> Result<(), UserDbError> {
+    let val = delete::person::delete_by_id(db, id).await?;
+    println!("Terminated Person with id: {}", val);
+    Ok(())
+}
+
+/// Process the request to remove everyone terminated at least `retention_days`
+/// ago. When `dry_run` is set, those who would be removed are listed, and the
+/// database is left untouched.
+pub async fn process_purge(db: &UserDb, retention_days: u32, dry_run: bool) -> Result<(), UserDbError> {
+    let mut tx = db.begin().await?;
+    let logins = delete::person::purge(&mut tx, retention_days).await?;
+    if dry_run {
+        tx.rollback().await?;
+        eprintln!("\n\t{} Dry run. Nothing was purged.", "Warning:".bright_green());
+    } else {
+        tx.commit().await?;
+    }
+    for login in &logins {
+        println!("{} {}", "Purged:".bright_green(), login);
+    }
+    println!("{} {}", "People Purged:".bright_green(), logins.len());
     Ok(())
 }
 
+/// Process the request to delete a location by name. This will not succeed
+/// while the location has one or more phones.
+pub async fn process_delete_location(db: &UserDb, name: &str) -> Result<(), UserDbError> {
+    let val = delete::location::delete(db, name).await?;
+    println!("Deleted Location with id: {}", val);
+    Ok(())
+}
 
+/// Process the request to delete the dial plan of a location, along with
+/// its extension ranges
+pub async fn process_delete_dial_plan(db: &UserDb, location: &str) -> Result<(), UserDbError> {
+    let locations = Locations::load(db).await?;
+    let location = locations.parse(location)?;
+    let val = delete::dial_plan::delete(db, location).await?;
+    println!("Deleted dial plan for {}", val);
+    Ok(())
+}
+
+/// Process the request to delete a location given its `id` in the `location` table.
+/// This will not succeed while the location has one or more phones.
+pub async fn process_delete_location_by_id(db: &UserDb, id: u32) -> Result<(), UserDbError> {
+    let val = delete::location::delete_by_id(db, id as i32).await?;
+    println!("Deleted Location with id: {}", val);
+    Ok(())
+}
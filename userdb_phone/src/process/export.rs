@@ -0,0 +1,28 @@
+use colored::*;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+// internal
+use userdb_core::export::{self, ExportFormat};
+use userdb_core::UserDb;
+use userdb_core::UserDbError;
+
+/// Process the request to export every person in the database, writing
+/// to `output` if supplied, and stdout otherwise.
+pub async fn process_export(
+    db: &UserDb,
+    format: ExportFormat,
+    output: Option<&Path>,
+) -> Result<(), UserDbError> {
+    let writer: Box<dyn Write> = match output {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(io::stdout()),
+    };
+    let count = export::export(db, format, BufWriter::new(writer)).await?;
+    // keep stdout clean for the exported data
+    if let Some(path) = output {
+        eprintln!("{} {} people to {}", "Exported".bright_green(), count, path.display());
+    }
+    Ok(())
+}
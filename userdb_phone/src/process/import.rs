@@ -0,0 +1,55 @@
+use colored::*;
+use std::path::Path;
+
+// internal
+use userdb_core::import;
+use userdb_core::ImportError;
+use userdb_core::Locations;
+use userdb_core::UserDb;
+use userdb_core::UserDbError;
+
+/// Process the request to import people and phones from a csv file.
+/// Every problem found in the file is reported, and nothing is imported
+/// unless the whole file is valid.
+pub async fn process_import_csv(
+    db: &UserDb,
+    file: &Path,
+    dry_run: bool,
+) -> Result<(), UserDbError> {
+    let locations = Locations::load(db).await?;
+    let result = match import::parse_file(file, &locations) {
+        Ok(rows) => import::import(db, &rows, dry_run).await,
+        Err(errors) => Err(errors),
+    };
+    match result {
+        Ok(summary) => {
+            if dry_run {
+                eprintln!("\n\t{} Dry run. Nothing was imported.", "Warning:".bright_green());
+            }
+            println!("{} {}", "People Created:".bright_green(), summary.people_created);
+            println!("{} {}", "People Updated:".bright_green(), summary.people_updated);
+            println!("{} {}", "Phones Added:".bright_green(), summary.phones_added);
+            Ok(())
+        }
+        Err(errors) => report(errors),
+    }
+}
+
+// print every row error. errors which are not tied to a row are
+// reported on their own
+fn report(errors: Vec<ImportError>) -> Result<(), UserDbError> {
+    let count = errors.len();
+    eprintln!();
+    for error in errors {
+        match error {
+            ImportError::UserDb(e) => return Err(e),
+            e @ ImportError::Io(..) => {
+                eprintln!("\t{} {}", "Error:".bright_red(), e);
+                std::process::exit(1);
+            }
+            e => eprintln!("\t{} {}", "Error:".bright_red(), e),
+        }
+    }
+    eprintln!("\n\t{} problem(s) found in the file. Nothing was imported.", count);
+    std::process::exit(1);
+}
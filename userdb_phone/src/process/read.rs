@@ -1,221 +1,401 @@
 use colored::*;
-use prettytable::{Table, format, row, cell};
-use sqlx::postgres::PgPoolOptions;
-use std::collections::HashMap;
-
-type RowMap = HashMap<Location, PhoneRow>;
+use prettytable::{Table, format, row};
+use std::num::NonZeroU32;
+use std::str::FromStr;
 
 // internal
 use userdb_core::read;
 
 use userdb_core::prelude::*;
-use userdb_core::DB_URL;
-use userdb_core::Phone;
+use userdb_core::AuditAction;
+use userdb_core::AuditEntry;
+use userdb_core::Contact;
+use userdb_core::Date;
+use userdb_core::ContactCategory;
+use userdb_core::Locations;
+use userdb_core::NumberString;
+use userdb_core::OrgChart;
+use userdb_core::PersonStatus;
 use userdb_core::PersonView;
+use userdb_core::PhoneNumber;
 use userdb_core::PhoneRow;
 use userdb_core::read::person::PersonQuery;
 use userdb_core::read::person_simple::SimplePersonQuery;
-use userdb_core::PhoneCategory;
 use userdb_core::QueryMode;
 use userdb_core::read::phone::PhoneQuery;
-use userdb_core::Location;
+use userdb_core::read::contact::ContactQuery;
+use userdb_core::read::audit::AuditQuery;
+use userdb_core::UserDb;
+use userdb_core::UserDbError;
 
 
 /// Process a request to read data from the database and present it to the user 
 /// via stdout/stderr, as either a table (default) or json (if `json` is true)
 pub async fn process_read_simple_person(
+    db: &UserDb,
     value: String, 
+    include_inactive: bool,
+    as_of: Option<Date>,
+    fuzzy: bool,
+    limit: Option<NonZeroU32>,
     json: bool
-) -> Result<(),sqlx::Error> {
-   
-
-    // construct a connection pool to the db
-    let  pool = PgPoolOptions::new()
-        .max_connections(1)
-        .connect(DB_URL).await?;
-
+) -> Result<(), UserDbError> {
     // PersonQuery is just a simple ol' pod
-    let personquery = SimplePersonQuery::new(value);
-    // query the database
-    let results = read::person_simple::query(&pool, personquery, QueryMode::ILike ).await?;
+    let personquery = SimplePersonQuery::new(value)
+                                .include_inactive(include_inactive)
+                                .as_of(as_of)
+                                .fuzzy(fuzzy)
+                                .limit(limit);
 
     // present the results - either in a table or as raw json, depending upon
     // whether the user has requested json via the --json flag or not
     if json {
+        let results = read::person_simple::query_json(db, personquery, QueryMode::ILike ).await?;
         // convert to json and print it out. simple as can be
-        let people = serde_json::to_string_pretty(&results).unwrap();
+        let people = serde_json::to_string_pretty(&results)?;
         println!("{}", people);
     } else {
-        for result in results {
-            let person: PersonView = serde_json::from_value(result).unwrap();
-            let mut table = Table::new();
-            table.set_format(*format::consts::FORMAT_CLEAN);
-            // we nest two tables for phones in order to achieve a better
-            // aesthetic balance in formatting. To pull off this trick
-            // prettytable allows us to embed one table within another.
-            table.add_row(row![format!(" {} {}", "User:".bright_cyan(), person.login), format!(" {} {}", "Full Name:".bright_cyan(), person.fullname)]);
-            table.add_row(row![format!(" {} {}","Dept:".bright_cyan(),  person.department), format!(" {} {}","Title:".bright_cyan(), person.title)]);
-            match person.phones {
-                None => {
-                    // Empty Row Handling
-                    table.add_row(row![" Ext:      H:             ".bright_cyan(),"P:       C:       Loc:       ".bright_cyan()]);
-                },
-                // currently, we assume that we will only have at most one of each type of phone per location
-                Some(phones) => {
-                    // rowmap maps location to phonerow
-                    let mut rowmap = RowMap::new();
-                    for phone in phones {
-                        if !rowmap.contains_key(&phone.location) {
-                            let mut phonerow = PhoneRow::new();
-                            phonerow.location = Some(phone.location.clone());
-                            rowmap.insert(phone.location.clone(), phonerow);
-                        }
-                        match phone.category {
-                            PhoneCategory::Home =>      rowmap.get_mut(&phone.location).unwrap().home = Some(phone.number.clone()),
-                            PhoneCategory::Extension => rowmap.get_mut(&phone.location).unwrap().ext  = Some(phone.number.clone()),
-                            PhoneCategory::Cell =>      rowmap.get_mut(&phone.location).unwrap().cell = Some(phone.number.clone()),
-                        }
-                    }
-                    // construct a new table per side. We are embedding the first table
-                    // in the left column, and the second table in the right column
-                    for (_loc, phonerow) in rowmap {
-                        let mut table_l = Table::new();
-                        let mut table_r = Table::new();
-                        table_l.set_format(*format::consts::FORMAT_CLEAN);
-                        table_r.set_format(*format::consts::FORMAT_CLEAN);
-                        table_l.add_row(phonerow.row_left());
-                        table_r.add_row(phonerow.row_right());
-                        table.add_row(row![table_l.to_string(), table_r.to_string()]);
-                    }
-                }
+        let people = read::person_simple::query(db, personquery, QueryMode::ILike ).await?;
+        people.iter().for_each(print_person);
+    }
+    
+    Ok(())
+}
+
+/// Process a request to read the org chart of the person with the supplied login,
+/// printing their management chain and their reports as a tree, or as json
+/// (if `json` is true)
+pub async fn process_read_org(
+    db: &UserDb,
+    login: String,
+    json: bool
+) -> Result<(), UserDbError> {
+    let chart = OrgChart::load(db).await?;
+    let missing = || UserDbError::NotFound(format!("Person '{}'", login));
+    if json {
+        let view = chart.view(&login).ok_or_else(missing)?;
+        println!("{}", serde_json::to_string_pretty(&view)?);
+    } else {
+        println!();
+        for line in chart.lines(&login).ok_or_else(missing)? {
+            let person = line.person.to_string();
+            if line.subject {
+                println!(" {}{}", line.prefix, person.bright_cyan().bold());
+            } else {
+                println!(" {}{}", line.prefix, person);
             }
-            table.printstd();
-            println!();
         }
+        println!();
     }
-    
     Ok(())
 }
 
 /// Process a request to read data from the database and present it to the user 
 /// via stdout/stderr, as either a table (default) or json (if `json` is true)
+#[allow(clippy::too_many_arguments)]
 pub async fn process_read_person(
+    db: &UserDb,
     name: Option<String>, 
     login: Option<String>, 
     title: Option<String>,
     dept: Option<String>,
+    contact: Option<String>,
+    number: Option<NumberString>,
+    include_inactive: bool,
+    as_of: Option<Date>,
     json: bool
-) -> Result<(),sqlx::Error> {
+) -> Result<(), UserDbError> {
     // verify that either name or login is set
     if name.is_none()  && 
        login.is_none() && 
        title.is_none() &&
-       dept.is_none()
+       dept.is_none() &&
+       contact.is_none() &&
+       number.is_none()
     {
-        eprintln!("\n{}: Must provide --name or --login or --title or --dept", "Error".bright_red());
+        eprintln!("\n{}: Must provide --name or --login or --title or --dept or --contact or --number", "Error".bright_red());
         std::process::exit(1);
     }
 
-    // construct a connection pool to the db
-    let  pool = PgPoolOptions::new()
-        .max_connections(1)
-        .connect(DB_URL).await?;
-
     // PersonQuery is just a simple ol' pod
     let personquery = PersonQuery::new()
                                 .name(name)
                                 .login(login)
                                 .title(title)
-                                .dept(dept);
-    // query the database
-    let results = read::person::query(&pool, personquery, QueryMode::ILike ).await?;
+                                .dept(dept)
+                                .contact(contact)
+                                .number(number)
+                                .include_inactive(include_inactive)
+                                .as_of(as_of);
 
     // present the results - either in a table or as raw json, depending upon
     // whether the user has requested json via the --json flag or not
     if json {
+        let results = read::person::query_json(db, personquery, QueryMode::ILike ).await?;
         // convert to json and print it out. simple as can be
-        let people = serde_json::to_string_pretty(&results).unwrap();
+        let people = serde_json::to_string_pretty(&results)?;
         println!("{}", people);
     } else {
-        for result in results {
-            let person: PersonView = serde_json::from_value(result).unwrap();
-            let mut table = Table::new();
-            table.set_format(*format::consts::FORMAT_CLEAN);
-            // we nest two tables for phones in order to achieve a better
-            // aesthetic balance in formatting. To pull off this trick
-            // prettytable allows us to embed one table within another.
-            table.add_row(row![format!(" {} {}", "User:".bright_cyan(), person.login), format!(" {} {}", "Full Name:".bright_cyan(), person.fullname)]);
-            table.add_row(row![format!(" {} {}","Dept:".bright_cyan(),  person.department), format!(" {} {}","Title:".bright_cyan(), person.title)]);
-            match person.phones {
-                None => {
-                    // Empty Row Handling
-                    table.add_row(row![" Ext:      H:             ".bright_cyan(),"P:       C:       Loc:       ".bright_cyan()]);
-                },
-                // currently, we assume that we will only have at most one of each type of phone per location
-                Some(phones) => {
-                    // rowmap maps location to phonerow
-                    let mut rowmap = RowMap::new();
-                    for phone in phones {
-                        if !rowmap.contains_key(&phone.location) {
-                            let mut phonerow = PhoneRow::new();
-                            phonerow.location = Some(phone.location.clone());
-                            rowmap.insert(phone.location.clone(), phonerow);
-                        }
-                        match phone.category {
-                            PhoneCategory::Home =>      rowmap.get_mut(&phone.location).unwrap().home = Some(phone.number.clone()),
-                            PhoneCategory::Extension => rowmap.get_mut(&phone.location).unwrap().ext  = Some(phone.number.clone()),
-                            PhoneCategory::Cell =>      rowmap.get_mut(&phone.location).unwrap().cell = Some(phone.number.clone()),
-                        }
-                    }
-                    // construct a new table per side. We are embedding the first table
-                    // in the left column, and the second table in the right column
-                    for (_loc, phonerow) in rowmap {
-                        let mut table_l = Table::new();
-                        let mut table_r = Table::new();
-                        table_l.set_format(*format::consts::FORMAT_CLEAN);
-                        table_r.set_format(*format::consts::FORMAT_CLEAN);
-                        table_l.add_row(phonerow.row_left());
-                        table_r.add_row(phonerow.row_right());
-                        table.add_row(row![table_l.to_string(), table_r.to_string()]);
-                    }
-                }
-            }
-            table.printstd();
-            println!();
-        }
+        let people = read::person::query(db, personquery, QueryMode::ILike ).await?;
+        people.iter().for_each(print_person);
     }
     
     Ok(())
 }
 
+// Print a table presenting the person's details and phones to stdout
+fn print_person(person: &PersonView) {
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_CLEAN);
+    // we nest two tables for phones in order to achieve a better
+    // aesthetic balance in formatting. To pull off this trick
+    // prettytable allows us to embed one table within another.
+    table.add_row(row![format!(" {} {}", "User:".bright_cyan(), person.login), format!(" {} {}", "Full Name:".bright_cyan(), person.fullname)]);
+    table.add_row(row![format!(" {} {}","Dept:".bright_cyan(),  person.department), format!(" {} {}","Title:".bright_cyan(), person.title)]);
+    if let Some(manager) = &person.manager {
+        table.add_row(row![format!(" {} {}", "Manager:".bright_cyan(), manager), ""]);
+    }
+    // the status is only worth mentioning when it is out of the ordinary
+    if person.status != PersonStatus::Active || person.start_date.is_some() || person.end_date.is_some() {
+        let dates = match (&person.start_date, &person.end_date) {
+            (Some(start), Some(end)) => format!("{} to {}", start, end),
+            (Some(start), None) => format!("from {}", start),
+            (None, Some(end)) => format!("until {}", end),
+            (None, None) => String::new(),
+        };
+        let dates = if dates.is_empty() { dates } else { format!(" {} {}", "Dates:".bright_cyan(), dates) };
+        table.add_row(row![format!(" {} {}", "Status:".bright_cyan(), person.status), dates]);
+    }
+    for contact in person.contacts.iter().flatten() {
+        table.add_row(row![format!(" {} {}", format!("{}:", contact_label(&contact.category)).bright_cyan(), format_contact(contact)), ""]);
+    }
+    for desk in person.desks.iter().flatten() {
+        table.add_row(row![format!(" {} {}", "Desk:".bright_cyan(), desk), ""]);
+    }
+    match &person.phones {
+        None => {
+            // Empty Row Handling
+            add_phonerow(&mut table, &PhoneRow::new());
+        },
+        Some(phones) => {
+            for phonerow in PhoneRow::from_phones(phones) {
+                add_phonerow(&mut table, &phonerow);
+            }
+        }
+    }
+    table.printstd();
+    println!();
+}
+
+// the heading of each category of contact in the person table
+pub(crate) fn contact_label(category: &ContactCategory) -> &'static str {
+    match category {
+        ContactCategory::Email => "Email",
+        ContactCategory::Chat => "Chat",
+        ContactCategory::PersonalEmail => "Personal",
+    }
+}
+
+// a contact, with the primary marked by a `*` and its label in parentheses
+pub(crate) fn format_contact(contact: &Contact) -> String {
+    let primary = if contact.primary { "*" } else { "" };
+    match &contact.label {
+        Some(label) => format!("{}{} ({})", contact.value, primary, label),
+        None => format!("{}{}", contact.value, primary),
+    }
+}
+
+// construct a new table per side. We are embedding the first table
+// in the left column, and the second table in the right column
+fn add_phonerow(table: &mut Table, phonerow: &PhoneRow) {
+    let mut table_l = Table::new();
+    let mut table_r = Table::new();
+    table_l.set_format(*format::consts::FORMAT_CLEAN);
+    table_r.set_format(*format::consts::FORMAT_CLEAN);
+    table_l.add_row(phonerow.row_left());
+    table_r.add_row(phonerow.row_right());
+    table.add_row(row![table_l.to_string(), table_r.to_string()]);
+}
+
 /// Process the request to read phone data from the database, and print it to stderr/stdout
 /// in either a table (default) or json
 pub async fn process_read_phone(
+    db: &UserDb,
     query: PhoneQuery,
     mode: QueryMode,
     json: bool,
-) -> Result<(), sqlx::Error> {
-    // construct a connection pool to the db
-    let  pool = PgPoolOptions::new()
-        .max_connections(1)
-        .connect(DB_URL).await?;
-
-    let results = read::phone::query(&pool, query, mode).await?;
+) -> Result<(), UserDbError> {
     if json {
-        let phones = serde_json::to_string_pretty(&results).unwrap();
+        let results = read::phone::query_json(db, query, mode).await?;
+        let phones = serde_json::to_string_pretty(&results)?;
         println!("{}", phones);
     } else {
+        let phones = read::phone::query(db, query, mode).await?;
         let mut table = Table::new();
         table.set_format(*format::consts::FORMAT_CLEAN);
-        table.add_row(row![bFC->"ID", bFC->"NUMBER", bFC->"CATEGORY", bFC->"LOCATION"]);
-        for result in results {
-            let phone: Phone = serde_json::from_value(result).unwrap();
-
+        table.add_row(row![bFC->"ID", bFC->"NUMBER", bFC->"CATEGORY", bFC->"LOCATION", bFC->"LABEL", bFC->"DID"]);
+        for phone in phones {
+            let did = phone.did.as_deref().and_then(|did| PhoneNumber::from_str(did).ok());
             table.add_row(row![
                 phone.phone_id, 
                 phone.number, 
                 phone.category.to_static_str(), 
-                phone.location.to_static_str()
+                phone.location,
+                phone.label.as_deref().unwrap_or(""),
+                did.map(|did| did.to_string()).unwrap_or_default()
+            ]);
+        }
+        table.printstd();
+    }
+    Ok(())
+}
+
+/// Process the request to read contacts from the database, and print them to stdout
+/// in either a table (default) or json
+pub async fn process_read_contact(
+    db: &UserDb,
+    query: ContactQuery,
+    mode: QueryMode,
+    json: bool,
+) -> Result<(), UserDbError> {
+    if json {
+        let results = read::contact::query_json(db, query, mode).await?;
+        let contacts = serde_json::to_string_pretty(&results)?;
+        println!("{}", contacts);
+    } else {
+        let contacts = read::contact::query(db, query, mode).await?;
+        let mut table = Table::new();
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.add_row(row![bFC->"ID", bFC->"LOGIN", bFC->"CATEGORY", bFC->"VALUE", bFC->"LABEL", bFC->"PRIMARY"]);
+        for contact in contacts {
+            table.add_row(row![
+                contact.contact_id,
+                contact.login.as_deref().unwrap_or(""),
+                contact.category.to_static_str(),
+                contact.value,
+                contact.label.as_deref().unwrap_or(""),
+                if contact.primary { "*" } else { "" }
+            ]);
+        }
+        table.printstd();
+    }
+    Ok(())
+}
+
+/// Process the request to read the audit log and print the matching changes
+/// to stdout as a table (default) or json.
+pub async fn process_read_audit(
+    db: &UserDb,
+    query: AuditQuery,
+    mode: QueryMode,
+    json: bool,
+) -> Result<(), UserDbError> {
+    if json {
+        let results = read::audit::query_json(db, query, mode).await?;
+        let entries = serde_json::to_string_pretty(&results)?;
+        println!("{}", entries);
+    } else {
+        let entries = read::audit::query(db, query, mode).await?;
+        let mut table = Table::new();
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.add_row(row![bFC->"AT", bFC->"ACTOR", bFC->"ACTION", bFC->"ENTITY", bFC->"ID", bFC->"LOGIN", bFC->"CHANGES"]);
+        for entry in entries {
+            table.add_row(row![
+                entry.at,
+                entry.actor,
+                entry.action,
+                entry.entity,
+                entry.entity_id.map(|id| id.to_string()).unwrap_or_default(),
+                entry.login.as_deref().unwrap_or(""),
+                audit_changes(&entry)
+            ]);
+        }
+        table.printstd();
+    }
+    Ok(())
+}
+
+// describe what changed: the fields altered by an update, or the name of
+// whatever was created or deleted
+fn audit_changes(entry: &AuditEntry) -> String {
+    let show = |value: Option<&serde_json::Value>| match value {
+        Some(serde_json::Value::String(value)) => value.clone(),
+        Some(serde_json::Value::Null) | None => "-".to_string(),
+        Some(value) => value.to_string(),
+    };
+    match entry.action {
+        AuditAction::Update => entry.changed()
+            .iter()
+            .map(|field| format!(
+                "{}: {} -> {}",
+                field,
+                show(entry.before.as_ref().and_then(|row| row.get(field))),
+                show(entry.after.as_ref().and_then(|row| row.get(field)))
+            ))
+            .collect::<Vec<_>>()
+            .join(", "),
+        AuditAction::Create | AuditAction::Delete => {
+            let row = entry.after.as_ref().or(entry.before.as_ref());
+            ["login", "name", "number", "value", "location", "room", "phone_id"].iter()
+                .find_map(|field| row.and_then(|row| row.get(field)).filter(|value| !value.is_null()))
+                .map(|value| show(Some(value)))
+                .unwrap_or_default()
+        }
+    }
+}
+
+/// Process the request to read the dial plans of each location, or of `location`
+/// alone, and print them to stdout as a table (default) or json.
+pub async fn process_read_dial_plan(
+    db: &UserDb,
+    location: Option<String>,
+    json: bool,
+) -> Result<(), UserDbError> {
+    let locations = Locations::load(db).await?;
+    let location = location.map(|l| locations.parse(&l)).transpose()?;
+    if json {
+        let results = read::dial_plan::query_json(db, location).await?;
+        let plans = serde_json::to_string_pretty(&results)?;
+        println!("{}", plans);
+    } else {
+        let plans = read::dial_plan::query(db, location).await?;
+        let mut table = Table::new();
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.add_row(row![bFC->"LOCATION", bFC->"SWITCHBOARD", bFC->"EXTENSIONS", bFC->"DID PREFIX"]);
+        for plan in plans {
+            let switchboard = PhoneNumber::from_str(&plan.switchboard)
+                .map(|number| number.to_string())
+                .unwrap_or(plan.switchboard);
+            let extensions = plan.extensions.iter().map(|range| range.to_string()).collect::<Vec<_>>();
+            table.add_row(row![b->plan.location, switchboard, extensions.join(", "), plan.did_prefix]);
+        }
+        table.printstd();
+    }
+    Ok(())
+}
+
+/// Process the request to read the locations from the database and print them
+/// to stdout as a table (default) or json.
+pub async fn process_read_location(db: &UserDb, json: bool) -> Result<(), UserDbError> {
+    if json {
+        let results = read::location::query_json(db).await?;
+        let locations = serde_json::to_string_pretty(&results)?;
+        println!("{}", locations);
+    } else {
+        let locations = read::location::query(db).await?;
+        let mut table = Table::new();
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.add_row(row![bFC->"NAME", bFC->"CODE", bFC->"ALIASES", bFC->"TIMEZONE", bFC->"COUNTRY", bFC->"CALLING CODE", bFC->"ADDRESS", bFC->"ID"]);
+        for location in locations {
+            table.add_row(row![
+                b->location.name,
+                location.code,
+                location.aliases.join(", "),
+                location.timezone,
+                location.country,
+                location.country().map(|c| format!("+{}", c.calling_code())).unwrap_or_default(),
+                location.address.as_deref().unwrap_or(""),
+                location.id
             ]);
         }
         table.printstd();
@@ -225,23 +405,18 @@ pub async fn process_read_phone(
 
 /// Process the request to read titles from the database and print them to 
 /// stderr/stdout as a table (default) or json.
-pub async fn process_read_title(json: bool)  -> Result<(), sqlx::Error> 
+pub async fn process_read_title(db: &UserDb, json: bool)  -> Result<(), UserDbError> 
 {
-    // construct a connection pool to the db
-    let  pool = PgPoolOptions::new()
-        .max_connections(1)
-        .connect(DB_URL).await?;
-    
-    let results = read::title::query(&pool).await?;
     if json {
-        let titles = serde_json::to_string_pretty(&results).unwrap();
+        let results = read::title::query_json(db).await?;
+        let titles = serde_json::to_string_pretty(&results)?;
         println!("{}", titles);
     } else {
+        let titles = read::title::query(db).await?;
         let mut table = Table::new();
         table.set_format(*format::consts::FORMAT_CLEAN);
         table.add_row(row![bFC->"NAME", bFC->"ID"]);
-        for result in results {
-            let title: read::title::TitleView = serde_json::from_value(result).unwrap();
+        for title in titles {
             table.add_row(row![b->title.name, title.id]);
         }
         table.printstd();
@@ -251,23 +426,18 @@ pub async fn process_read_title(json: bool)  -> Result<(), sqlx::Error>
 
 /// Process the request to print departments to stderr/stdout as a table (default)
 /// or json (via the `json` argument)
-pub async fn process_read_department(json: bool)  -> Result<(), sqlx::Error> 
+pub async fn process_read_department(db: &UserDb, json: bool)  -> Result<(), UserDbError> 
 {
-    // construct a connection pool to the db
-    let  pool = PgPoolOptions::new()
-        .max_connections(1)
-        .connect(DB_URL).await?;
-    
-    let results = read::department::departmentview(&pool).await?;
     if json {
-        let depts = serde_json::to_string_pretty(&results).unwrap();
+        let results = read::department::query_json(db).await?;
+        let depts = serde_json::to_string_pretty(&results)?;
         println!("{}", depts);
     } else {
+        let depts = read::department::query(db).await?;
         let mut table = Table::new();
         table.set_format(*format::consts::FORMAT_CLEAN);
         table.add_row(row![bFC->"NAME", bFC->"ID"]);
-        for result in results {
-            let dept: read::department::DepartmentView = serde_json::from_value(result).unwrap();
+        for dept in depts {
             table.add_row(row![b->dept.name, dept.id]);
         }
         table.printstd();
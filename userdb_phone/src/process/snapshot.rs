@@ -0,0 +1,60 @@
+use colored::*;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::Path;
+
+// internal
+use userdb_core::snapshot::{self, ConflictPolicy, Snapshot};
+use userdb_core::UserDb;
+use userdb_core::UserDbError;
+
+/// Process the request to dump a snapshot of the database, writing
+/// to `output` if supplied, and stdout otherwise.
+pub async fn process_dump(
+    db: &UserDb,
+    output: Option<&Path>,
+) -> Result<(), UserDbError> {
+    let snapshot = snapshot::dump(db).await?;
+    let writer: Box<dyn Write> = match output {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(io::stdout()),
+    };
+    let mut writer = BufWriter::new(writer);
+    snapshot.to_writer(&mut writer)?;
+    writeln!(writer)?;
+    writer.flush()?;
+    // keep stdout clean for the snapshot
+    if let Some(path) = output {
+        eprintln!("{} {} people to {}", "Dumped".bright_green(), snapshot.people.len(), path.display());
+    }
+    Ok(())
+}
+
+/// Process the request to restore the snapshot in `file`
+pub async fn process_restore(
+    db: &UserDb,
+    file: &Path,
+    policy: ConflictPolicy,
+    dry_run: bool,
+) -> Result<(), UserDbError> {
+    let snapshot = Snapshot::from_reader(BufReader::new(File::open(file)?))?;
+    let summary = snapshot::restore(db, &snapshot, policy, dry_run).await?;
+    if dry_run {
+        eprintln!("\n\t{} Dry run. Nothing was restored.", "Warning:".bright_green());
+    }
+    println!("{} {}", "Departments Added:".bright_green(), summary.departments);
+    println!("{} {}", "Titles Added:".bright_green(), summary.titles);
+    // existing locations, people, phones, contacts and desks are counted too when they are updated
+    let (added, assigned) = match policy {
+        ConflictPolicy::Update => ("Added or Updated:", "Assigned or Updated:"),
+        _ => ("Added:", "Assigned:"),
+    };
+    println!("{} {}", format!("Locations {}", added).bright_green(), summary.locations);
+    println!("{} {}", format!("People {}", added).bright_green(), summary.people);
+    println!("{} {}", format!("Phones {}", added).bright_green(), summary.phones);
+    println!("{} {}", format!("Phones {}", assigned).bright_green(), summary.people_phones);
+    println!("{} {}", format!("Contacts {}", added).bright_green(), summary.contacts);
+    println!("{} {}", "Managers Set:".bright_green(), summary.managers);
+    println!("{} {}", format!("Desks {}", added).bright_green(), summary.desks);
+    Ok(())
+}
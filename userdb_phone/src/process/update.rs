@@ -1,184 +1,301 @@
 
 use colored::*;
-use sqlx::postgres::PgPoolOptions;
 
 // internal
+use crate::opt::{DeskOpt, StatusOpt};
+use userdb_core::delete;
+use userdb_core::read;
 use userdb_core::update;
-use userdb_core::DB_URL;
+use userdb_core::read::phone::PhoneQuery;
+use userdb_core::ContactCategory;
+use userdb_core::DeskOwner;
 use userdb_core::PhoneCategory;
-use userdb_core::Location;
+use userdb_core::Locations;
+use userdb_core::QueryMode;
 use userdb_core::prelude::*;
 use userdb_core::NumberString;
 use userdb_core::update::person::id::PersonUpdate as PersonUpdateById;
 use userdb_core::update::person::login::PersonUpdate as PersonUpdateByLogin;
 use userdb_core::update::phone::id::PhoneUpdate as PhoneUpdateById;
+use userdb_core::update::contact::id::ContactUpdate as ContactUpdateById;
 use userdb_core::update::title::id::TitleUpdate as TitleUpdateById;
 use userdb_core::update::department::id::DepartmentUpdate as DepartmentUpdateById;
+use userdb_core::update::location::id::LocationUpdate as LocationUpdateById;
+use userdb_core::update::desk::DeskUpdate;
+use userdb_core::UserDb;
+use userdb_core::UserDbError;
 
 
 /// Process the request to update a `person` identified by their `id` in the 
 /// person table. 
+#[allow(clippy::too_many_arguments)]
 pub async fn process_update_person_by_id(
+    db: &UserDb,
     id: i32, 
     first: Option<String>, 
     last: Option<String>, 
     login: Option<String>, 
     department: Option<i32>, 
-    title: Option<i32>) -> Result<(), sqlx::Error> 
+    title: Option<i32>,
+    manager: Option<String>,
+    status: StatusOpt,
+    desk: DeskOpt) -> Result<(), UserDbError> 
     {
+        let StatusOpt{status, start_date, end_date} = status;
         let person_update = PersonUpdateById::new(id)
         .first(first)
         .last(last)
         .login(login)
         .department(department)
-        .title(title);
+        .title(title)
+        .manager(manager)
+        .status(status)
+        .start_date(start_date)
+        .end_date(end_date);
 
+        let desk_changed = update_desk(db, DeskOwner::Id(id), desk).await?;
         if person_update.is_empty() {
-            eprintln!("\n\t{} Nothing to do updating person. No changes supplied", "Warning:".bright_green());
+            if !desk_changed {
+                eprintln!("\n\t{} Nothing to do updating person. No changes supplied", "Warning:".bright_green());
+            }
             return Ok(());
         }
 
-        let pool = PgPoolOptions::new()
-            .max_connections(1)
-            .connect(DB_URL).await?;
-
-        let result = update::person::id::update(&pool, person_update).await;
-        let result = match result {
-            Ok(r) => r,
-            Err(sqlx::Error::RowNotFound) =>  None,
-            Err(e) => return Err(e)
-        };
-        match result {
-            Some(val) => println!("Updated person with id: {}", val),
-            None => eprintln!("\n\tNothing to do updating person"),
-        };
+        let val = update::person::id::update(db, person_update).await?;
+        println!("Updated person with id: {}", val);
         Ok(())
     }
 
     
 /// Process the request to update a `person` identified by their `login`.
+#[allow(clippy::too_many_arguments)]
 pub async fn process_update_person_by_login(
+    db: &UserDb,
     from_login: String, 
     first: Option<String>, 
     last: Option<String>, 
     login: Option<String>, 
     department: Option<i32>, 
-    title: Option<i32>) 
--> Result<(), sqlx::Error> {
+    title: Option<i32>,
+    manager: Option<String>,
+    status: StatusOpt,
+    desk: DeskOpt) 
+-> Result<(), UserDbError> {
+    // the desk is updated first, while the person still has their current login
+    let desk_changed = update_desk(db, DeskOwner::Login(from_login.clone()), desk).await?;
+    let StatusOpt{status, start_date, end_date} = status;
     let person_update = PersonUpdateByLogin::new(from_login)
     .first(first)
     .last(last)
     .login(login)
     .department(department)
-    .title(title);
+    .title(title)
+    .manager(manager)
+    .status(status)
+    .start_date(start_date)
+    .end_date(end_date);
     
     if person_update.is_empty() {
-        eprintln!("\n\t{} Nothing to do updating person. No changes supplied", "Warning:".bright_green());
+        if !desk_changed {
+            eprintln!("\n\t{} Nothing to do updating person. No changes supplied", "Warning:".bright_green());
+        }
         return Ok(());
     }
 
-    let pool = PgPoolOptions::new()
-        .max_connections(1)
-        .connect(DB_URL).await?;
+    let val = update::person::login::update(db, person_update).await?;
+    println!("Updated person with id: {}", val);
+    Ok(())
+}
 
-    let result = update::person::login::update(&pool, person_update).await;
-    let result = match result {
-        Ok(r) => r,
-        Err(sqlx::Error::RowNotFound) =>  None,
-        Err(e) => return Err(e)
-    };
-    match result {
-        Some(val) => println!("Updated person with id: {}", val),
-        None => eprintln!("\n\tNothing to do updating person"),
+// update or clear the desk of a person, returning whether any change was requested
+async fn update_desk(db: &UserDb, owner: DeskOwner, desk: DeskOpt) -> Result<bool, UserDbError> {
+    let DeskOpt{desk_location, building, floor, room, clear_desk} = desk;
+    let location = match desk_location {
+        Some(location) => Some(Locations::load(db).await?.parse(&location)?.clone()),
+        None => None,
     };
-    Ok(())
+    if clear_desk {
+        let count = delete::desk::delete(db, &owner, location.as_ref()).await?;
+        println!("Cleared desks: {}", count);
+        return Ok(true);
+    }
+    let desk_update = DeskUpdate::new(owner)
+        .location(location)
+        .building(building)
+        .floor(floor)
+        .room(room);
+    if desk_update.is_empty() {
+        return Ok(false);
+    }
+    let val = update::desk::update(db, desk_update).await?;
+    println!("Updated desk with id: {}", val);
+    Ok(true)
 }
 
 /// Process a request to update a phone, identified by its `id` in the `phone` table.
 pub async fn process_update_phone_by_id(
+    db: &UserDb,
     // the phone's id
     id: i32, 
     number: Option<NumberString>, 
     category: Option<PhoneCategory>, 
-    location: Option<Location>, 
-) -> Result<(), sqlx::Error> 
+    location: Option<String>, 
+    label: Option<String>,
+    primary: Option<bool>,
+) -> Result<(), UserDbError> 
 {
+    let locations = Locations::load(db).await?;
+    let location = location.map(|l| locations.parse(&l).cloned()).transpose()?;
+    // a number is read according to the phone's category, and a national
+    // one as belonging to its country, so when either is not changing we
+    // look up the current phone
+    let current = match (&number, &category, &location) {
+        (Some(_), None, _) | (Some(_), _, None) => {
+            let query = PhoneQuery::new().id(Some(id as u32));
+            let phones = read::phone::query(db, query, QueryMode::Exact).await?;
+            let phone = phones.into_iter().next().ok_or_else(|| UserDbError::NotFound(format!("Phone with id {}", id)))?;
+            Some(phone)
+        }
+        _ => None,
+    };
+    let country = match (&current, &location) {
+        (Some(phone), None) => locations.parse(&phone.location)?.country(),
+        _ => None,
+    };
+    let current_category = current.map(|phone| phone.category);
     let phone_update = PhoneUpdateById::new(id)
                         .number(number)
                         .category(category)
-                        .location(location);
+                        .location(location)
+                        .label(label)
+                        .primary(primary)
+                        .country(country)
+                        .current_category(current_category);
 
     if phone_update.is_empty() {
         eprintln!("\n\t{} Nothing to do updating phone. No changes supplied", "Warning:".bright_green());
         return Ok(());
     }
 
-    let pool = PgPoolOptions::new()
-        .max_connections(1)
-        .connect(DB_URL).await?;
+    let val = update::phone::id::update(db, phone_update).await?;
+    println!("Updated phone with id: {}", val);
+    Ok(())
+}
 
-    let result = update::phone::id::update(&pool, phone_update).await;
-    let result = match result {
-        Ok(r) => r,
-        Err(sqlx::Error::RowNotFound) =>  None,
-        Err(e) => return Err(e)
-    };
-    match result {
-        Some(val) => println!("Updated phone with id: {}", val),
-        None => eprintln!("\n\tNothing to do updating phone"),
-    };
+/// Process a request to update a contact, identified by its `id` in the `contact` table.
+pub async fn process_update_contact_by_id(
+    db: &UserDb,
+    id: i32,
+    category: Option<ContactCategory>,
+    value: Option<String>,
+    label: Option<String>,
+    primary: Option<bool>,
+) -> Result<(), UserDbError>
+{
+    let contact_update = ContactUpdateById::new(id)
+                        .category(category)
+                        .value(value)
+                        .label(label)
+                        .primary(primary);
+
+    if contact_update.is_empty() {
+        eprintln!("\n\t{} Nothing to do updating contact. No changes supplied", "Warning:".bright_green());
+        return Ok(());
+    }
+
+    let val = update::contact::id::update(db, contact_update).await?;
+    println!("Updated contact with id: {}", val);
     Ok(())
 }
 
 /// Process a reqeust to update a title identified by its `id` in the `title` table.
 pub async fn process_update_title_by_id(
+    db: &UserDb,
     // the phone's id
     id: i32, 
     name: String, 
-) -> Result<(), sqlx::Error> 
+) -> Result<(), UserDbError> 
 {
     let title_update = TitleUpdateById::new(id, name);
    
-    let pool = PgPoolOptions::new()
-        .max_connections(1)
-        .connect(DB_URL).await?;
-
-    let result = update::title::id::update(&pool, title_update).await;
-    let result = match result {
-        Ok(r) => r,
-        Err(sqlx::Error::RowNotFound) =>  None,
-        Err(e) => return Err(e)
-    };
-    match result {
-        Some(val) => println!("Updated title with id: {}", val),
-        None => eprintln!("\n\tNothing to do updating title"),
-    };
+    let val = update::title::id::update(db, title_update).await?;
+    println!("Updated title with id: {}", val);
+    Ok(())
+}
+
+/// Process a request to move the phones of the person with login `from`, or
+/// only the phone with `phone_id`, to the person with login `to`.
+pub async fn process_transfer_phones(
+    db: &UserDb,
+    from: &str,
+    to: &str,
+    phone_id: Option<u32>,
+) -> Result<(), UserDbError> {
+    let moved = update::person_phone::transfer(db, from, to, phone_id).await?;
+    for id in &moved {
+        println!("Transferred Phone with id: {} from {} to {}", id, from, to);
+    }
+    println!("{} {}", "Phones Transferred:".bright_cyan(), moved.len());
+    Ok(())
+}
+
+/// Process a request to merge the department named `source` into the one
+/// named `destination`, moving everyone across before deleting `source`.
+pub async fn process_merge_dept(db: &UserDb, source: &str, destination: &str) -> Result<(), UserDbError> {
+    let moved = update::department::merge(db, source, destination).await?;
+    println!("Merged Department {} into {}", source, destination);
+    println!("{} {}", "People Moved:".bright_cyan(), moved);
     Ok(())
 }
 
 /// Process a request to update a department, identified by its `id` in 
 /// the `department` table.
 pub async fn process_update_dept_by_id(
+    db: &UserDb,
     // the phone's id
     id: i32, 
     name: String, 
-) -> Result<(), sqlx::Error> 
+) -> Result<(), UserDbError> 
 {
     let department_update = DepartmentUpdateById::new(id, name);
    
-    let pool = PgPoolOptions::new()
-        .max_connections(1)
-        .connect(DB_URL).await?;
-
-    let result = update::department::id::update(&pool, department_update).await;
-    let result = match result {
-        Ok(r) => r,
-        Err(sqlx::Error::RowNotFound) =>  None,
-        Err(e) => return Err(e)
-    };
-    match result {
-        Some(val) => println!("Updated department with id: {}", val),
-        None => eprintln!("\n\tNothing to do updating department"),
-    };
+    let val = update::department::id::update(db, department_update).await?;
+    println!("Updated department with id: {}", val);
+    Ok(())
+}
+
+/// Process a request to update a location, identified by its `id` in
+/// the `location` table.
+#[allow(clippy::too_many_arguments)]
+pub async fn process_update_location_by_id(
+    db: &UserDb,
+    id: i32,
+    name: Option<String>,
+    code: Option<String>,
+    aliases: Option<Vec<String>>,
+    address: Option<String>,
+    timezone: Option<String>,
+    country: Option<String>,
+    calling_code: Option<String>,
+    trunk_prefix: Option<String>,
+) -> Result<(), UserDbError>
+{
+    let location_update = LocationUpdateById::new(id)
+                        .name(name)
+                        .code(code)
+                        .aliases(aliases)
+                        .address(address)
+                        .timezone(timezone)
+                        .country(country)
+                        .calling_code(calling_code)
+                        .trunk_prefix(trunk_prefix);
+
+    if location_update.is_empty() {
+        eprintln!("\n\t{} Nothing to do updating location. No changes supplied", "Warning:".bright_green());
+        return Ok(());
+    }
+
+    let val = update::location::id::update(db, location_update).await?;
+    println!("Updated location with id: {}", val);
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,191 @@
+//! An interactive terminal ui for browsing the directory, searching as you
+//! type. When editing is enabled, the selected person and their phones may
+//! be changed through forms backed by the update modules.
+use crossterm::cursor::Show;
+use crossterm::event::{self, Event};
+use crossterm::execute;
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
+use std::io;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tui::backend::CrosstermBackend;
+use tui::Terminal;
+
+// internal
+use userdb_core::read;
+use userdb_core::read::person_simple::SimplePersonQuery;
+use userdb_core::update;
+use userdb_core::update::person::login::PersonUpdate as PersonUpdateByLogin;
+use userdb_core::update::phone::id::PhoneUpdate;
+use userdb_core::Locations;
+use userdb_core::NumberString;
+use userdb_core::PersonStatus;
+use userdb_core::PersonView;
+use userdb_core::PhoneCategory;
+use userdb_core::QueryMode;
+use userdb_core::UserDb;
+use userdb_core::UserDbError;
+
+mod app;
+mod form;
+mod ui;
+
+use app::{Action, App};
+use form::{Form, Target};
+
+// how long to wait after the last key before searching
+const DEBOUNCE: Duration = Duration::from_millis(150);
+// how long to wait for a key before checking whether to search
+const TICK: Duration = Duration::from_millis(50);
+
+type Term = Terminal<CrosstermBackend<io::Stdout>>;
+
+/// Run the terminal ui until the user quits, starting with a search for
+/// `query`. People and phones may only be changed when `editable` is set.
+pub async fn run(db: &UserDb, query: Option<String>, include_inactive: bool, editable: bool) -> Result<(), UserDbError> {
+    let _screen = Screen::enter()?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+    let app = App::new(query.unwrap_or_default(), editable);
+    event_loop(&mut terminal, db, app, include_inactive).await
+}
+
+// Raw mode on the alternate screen, for as long as it lives. The terminal
+// is restored when it is dropped, whether the ui returns, fails or panics.
+struct Screen;
+
+impl Screen {
+    fn enter() -> Result<Self, io::Error> {
+        terminal_io(enable_raw_mode())?;
+        let screen = Self;
+        terminal_io(execute!(io::stdout(), EnterAlternateScreen))?;
+        Ok(screen)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        // there is nowhere left to report a failure to restore the terminal
+        let _ = disable_raw_mode();
+        let _ = execute!(io::stdout(), LeaveAlternateScreen, Show);
+    }
+}
+
+async fn event_loop(terminal: &mut Term, db: &UserDb, mut app: App, include_inactive: bool) -> Result<(), UserDbError> {
+    // search straight away for the initial query
+    let mut search_at = Some(Instant::now());
+    loop {
+        // searching replaces the people, so waits while a form is open
+        if app.form.is_none() && search_at.is_some_and(|at| at <= Instant::now()) {
+            search_at = None;
+            match search(db, &app.query, include_inactive).await {
+                Ok(people) => app.set_people(people),
+                Err(e) => app.message = Some(e.to_string()),
+            }
+        }
+        terminal.draw(|f| ui::draw(f, &mut app))?;
+        if !terminal_io(event::poll(TICK))? {
+            continue;
+        }
+        let key = match terminal_io(event::read())? {
+            Event::Key(key) => key,
+            _ => continue,
+        };
+        app.message = None;
+        match app.handle_key(key) {
+            Action::None => (),
+            Action::Search => search_at = Some(Instant::now() + DEBOUNCE),
+            Action::Quit => return Ok(()),
+            Action::Save => {
+                let form = match &app.form {
+                    Some(form) if form.is_changed() => form,
+                    _ => {
+                        app.form = None;
+                        app.message = Some("Nothing to save".into());
+                        continue;
+                    }
+                };
+                match save(db, form).await {
+                    Ok(message) => {
+                        app.form = None;
+                        app.message = Some(message);
+                        search_at = Some(Instant::now());
+                    }
+                    Err(e) => app.message = Some(e.to_string()),
+                }
+            }
+        }
+    }
+}
+
+// crossterm reports its own errors, which are io errors as far as we are concerned
+fn terminal_io<T>(result: crossterm::Result<T>) -> Result<T, io::Error> {
+    result.map_err(|e| match e {
+        crossterm::ErrorKind::IoError(e) => e,
+        e => io::Error::other(e.to_string()),
+    })
+}
+
+// the people matching the query, as the phone command finds them
+async fn search(db: &UserDb, query: &str, include_inactive: bool) -> Result<Vec<PersonView>, UserDbError> {
+    let query = SimplePersonQuery::new(query).include_inactive(include_inactive);
+    read::person_simple::query(db, query, QueryMode::ILike).await
+}
+
+// save the changed fields of the form, returning a message describing the change
+async fn save(db: &UserDb, form: &Form) -> Result<String, UserDbError> {
+    match &form.target {
+        Target::Person(login) => {
+            // departments and titles are edited by name, but updated by id
+            let department = match form.changed("Department") {
+                Some(name) => Some(read::department::query(db).await?.into_iter()
+                    .find(|dept| dept.name == name)
+                    .map(|dept| dept.id)
+                    .ok_or(UserDbError::UnknownDepartment(name))?),
+                None => None,
+            };
+            let title = match form.changed("Title") {
+                Some(name) => Some(read::title::query(db).await?.into_iter()
+                    .find(|title| title.name == name)
+                    .map(|title| title.id)
+                    .ok_or(UserDbError::UnknownTitle(name))?),
+                None => None,
+            };
+            let status = form.changed("Status").map(|status| PersonStatus::from_str(&status)).transpose()?;
+            let person_update = PersonUpdateByLogin::new(login.as_str())
+                .first(form.changed("First"))
+                .last(form.changed("Last"))
+                .login(form.changed("Login"))
+                .department(department)
+                .title(title)
+                .manager(form.changed("Manager"))
+                .status(status)
+                .start_date(form.changed("Start date"))
+                .end_date(form.changed("End date"));
+            let id = update::person::login::update(db, person_update).await?;
+            Ok(format!("Updated person with id: {}", id))
+        }
+        Target::Phone(id) => {
+            let number = form.changed("Number").map(|number| NumberString::new(&number)).transpose()?;
+            let category = form.changed("Category").map(|category| PhoneCategory::from_str(&category)).transpose()?;
+            let locations = Locations::load(db).await?;
+            let location = form.changed("Location").map(|location| locations.parse(&location).cloned()).transpose()?;
+            // the number is read according to the phone as it was, for
+            // whichever of its category and location are not changing
+            let current_category = form.original("Category").map(PhoneCategory::from_str).transpose()?;
+            let country = match form.original("Location") {
+                Some(current) if location.is_none() => locations.parse(current)?.country(),
+                _ => None,
+            };
+            let phone_update = PhoneUpdate::new(*id)
+                .number(number)
+                .category(category)
+                .location(location)
+                .label(form.changed("Label"))
+                .primary(form.changed("Primary").map(|primary| primary == "yes"))
+                .country(country)
+                .current_category(current_category);
+            let id = update::phone::id::update(db, phone_update).await?;
+            Ok(format!("Updated phone with id: {}", id))
+        }
+    }
+}
@@ -0,0 +1,292 @@
+//! The state of the terminal ui, and how it responds to keys
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use tui::widgets::ListState;
+
+use userdb_core::Phone;
+use userdb_core::PersonView;
+
+use super::form::{Form, Target};
+
+// the number of people skipped by page up and page down
+const PAGE: usize = 10;
+
+/// Where typing goes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Focus {
+    /// The search box, which is searched as it is typed in
+    Search,
+    /// The list of people found
+    List,
+}
+
+/// What the event loop should do in response to a key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    None,
+    /// The search changed, and should be run again
+    Search,
+    /// Save the open form
+    Save,
+    Quit,
+}
+
+/// The state of the terminal ui
+#[derive(Debug)]
+pub struct App {
+    pub query: String,
+    pub people: Vec<PersonView>,
+    /// The selected person
+    pub list: ListState,
+    pub focus: Focus,
+    /// The edit form, if one is open
+    pub form: Option<Form>,
+    /// Whether people and phones may be edited
+    pub editable: bool,
+    /// A message for the status line, eg the outcome of a save
+    pub message: Option<String>,
+    // the index of the phone being edited, among the selected person's phones
+    phone: usize,
+}
+
+impl App {
+    pub fn new<I: Into<String>>(query: I, editable: bool) -> Self {
+        Self {
+            query: query.into(),
+            people: Vec::new(),
+            list: ListState::default(),
+            focus: Focus::Search,
+            form: None,
+            editable,
+            message: None,
+            phone: 0,
+        }
+    }
+
+    /// The person currently selected, if any
+    pub fn selected(&self) -> Option<&PersonView> {
+        self.list.selected().and_then(|idx| self.people.get(idx))
+    }
+
+    /// Replace the people found, keeping the same person selected if they
+    /// are still among them
+    pub fn set_people(&mut self, people: Vec<PersonView>) {
+        let current = self.selected().map(|person| person.person_id);
+        let idx = current
+            .and_then(|id| people.iter().position(|person| person.person_id == id))
+            .unwrap_or(0);
+        self.list.select(if people.is_empty() { None } else { Some(idx) });
+        self.people = people;
+    }
+
+    /// Respond to a key, returning what the event loop should do next
+    pub fn handle_key(&mut self, key: KeyEvent) -> Action {
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            return Action::Quit;
+        }
+        if self.form.is_some() {
+            return self.handle_form_key(key);
+        }
+        match self.focus {
+            Focus::Search => self.handle_search_key(key),
+            Focus::List => self.handle_list_key(key),
+        }
+    }
+
+    fn handle_search_key(&mut self, key: KeyEvent) -> Action {
+        match key.code {
+            KeyCode::Char(c) => {
+                self.query.push(c);
+                Action::Search
+            }
+            KeyCode::Backspace => {
+                self.query.pop();
+                Action::Search
+            }
+            KeyCode::Enter | KeyCode::Tab | KeyCode::Down | KeyCode::Esc => {
+                self.focus = Focus::List;
+                Action::None
+            }
+            _ => Action::None,
+        }
+    }
+
+    fn handle_list_key(&mut self, key: KeyEvent) -> Action {
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Action::Quit,
+            KeyCode::Char('/') | KeyCode::Tab => self.focus = Focus::Search,
+            KeyCode::Down | KeyCode::Char('j') => self.move_by(1),
+            KeyCode::Up | KeyCode::Char('k') => self.move_by(-1),
+            KeyCode::PageDown => self.move_by(PAGE as isize),
+            KeyCode::PageUp => self.move_by(-(PAGE as isize)),
+            KeyCode::Home | KeyCode::Char('g') => self.move_by(isize::MIN),
+            KeyCode::End | KeyCode::Char('G') => self.move_by(isize::MAX),
+            KeyCode::Char('e') if self.editable => {
+                self.form = self.selected().map(Form::person);
+            }
+            KeyCode::Char('p') if self.editable => {
+                self.phone = 0;
+                self.form = self.phones().first().map(Form::phone);
+                if self.form.is_none() {
+                    self.message = Some("No phones to edit".into());
+                }
+            }
+            _ => (),
+        }
+        Action::None
+    }
+
+    fn handle_form_key(&mut self, key: KeyEvent) -> Action {
+        let form = match self.form.as_mut() {
+            Some(form) => form,
+            None => return Action::None,
+        };
+        match key.code {
+            KeyCode::Esc => {
+                self.form = None;
+                self.message = Some("Edit cancelled".into());
+            }
+            KeyCode::Enter => return Action::Save,
+            KeyCode::Tab | KeyCode::Down => form.next(),
+            KeyCode::BackTab | KeyCode::Up => form.previous(),
+            KeyCode::Backspace => form.pop(),
+            KeyCode::Char(c) => form.push(c),
+            // switch between the phones of the person, abandoning any edits.
+            // The person may have lost their phones since the form opened
+            KeyCode::PageDown | KeyCode::PageUp if matches!(form.target, Target::Phone(_)) => {
+                let count = self.phones().len();
+                if count == 0 {
+                    self.form = None;
+                    self.message = Some("No phones to edit".into());
+                    return Action::None;
+                }
+                self.phone = if key.code == KeyCode::PageDown {
+                    (self.phone + 1) % count
+                } else {
+                    (self.phone + count - 1) % count
+                };
+                self.form = self.phones().get(self.phone).map(Form::phone);
+            }
+            _ => (),
+        }
+        Action::None
+    }
+
+    // the phones of the selected person
+    fn phones(&self) -> &[Phone] {
+        self.selected().and_then(|person| person.phones.as_deref()).unwrap_or(&[])
+    }
+
+    // move the selection, stopping at either end of the list
+    fn move_by(&mut self, delta: isize) {
+        if self.people.is_empty() {
+            return;
+        }
+        let last = self.people.len() as isize - 1;
+        let current = self.list.selected().unwrap_or(0) as isize;
+        let idx = current.saturating_add(delta).clamp(0, last);
+        self.list.select(Some(idx as usize));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use userdb_core::PersonStatus;
+
+    fn person(person_id: i32, login: &str) -> PersonView {
+        PersonView {
+            person_id,
+            first: login.into(),
+            last: login.into(),
+            fullname: login.into(),
+            login: login.into(),
+            department: "Playa Software".into(),
+            title: "Engineer, Software".into(),
+            manager: None,
+            status: PersonStatus::Active,
+            start_date: None,
+            end_date: None,
+            phones: None,
+            contacts: None,
+            desks: None,
+            score: None,
+        }
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn handle_key_given_typing_searches() {
+        let mut app = App::new("", false);
+        assert_eq!(app.handle_key(key(KeyCode::Char('s'))), Action::Search);
+        assert_eq!(app.handle_key(key(KeyCode::Char('a'))), Action::Search);
+        assert_eq!(app.handle_key(key(KeyCode::Backspace)), Action::Search);
+        assert_eq!(app.query, "s");
+        assert_eq!(app.handle_key(key(KeyCode::Enter)), Action::None);
+        assert_eq!(app.focus, Focus::List);
+        assert_eq!(app.handle_key(key(KeyCode::Char('q'))), Action::Quit);
+    }
+
+    #[test]
+    fn handle_key_given_navigation_stops_at_ends() {
+        let mut app = App::new("", false);
+        app.set_people(vec![person(1, "a"), person(2, "b"), person(3, "c")]);
+        app.focus = Focus::List;
+        app.handle_key(key(KeyCode::Up));
+        assert_eq!(app.list.selected(), Some(0));
+        app.handle_key(key(KeyCode::PageDown));
+        assert_eq!(app.selected().map(|p| p.person_id), Some(3));
+        app.handle_key(key(KeyCode::Char('k')));
+        assert_eq!(app.list.selected(), Some(1));
+    }
+
+    #[test]
+    fn set_people_keeps_selected_person() {
+        let mut app = App::new("", false);
+        app.set_people(vec![person(1, "a"), person(2, "b")]);
+        app.list.select(Some(1));
+        app.set_people(vec![person(0, "z"), person(1, "a"), person(2, "b")]);
+        assert_eq!(app.selected().map(|p| p.person_id), Some(2));
+        app.set_people(Vec::new());
+        assert!(app.selected().is_none());
+    }
+
+    #[test]
+    fn handle_key_given_read_only_does_not_edit() {
+        let mut app = App::new("", false);
+        app.set_people(vec![person(1, "a")]);
+        app.focus = Focus::List;
+        app.handle_key(key(KeyCode::Char('e')));
+        assert!(app.form.is_none());
+        app.editable = true;
+        app.handle_key(key(KeyCode::Char('e')));
+        assert!(app.form.is_some());
+        assert_eq!(app.handle_key(key(KeyCode::Enter)), Action::Save);
+        app.handle_key(key(KeyCode::Esc));
+        assert!(app.form.is_none());
+    }
+
+    #[test]
+    fn handle_key_given_phone_form_without_phones_closes_it() {
+        let phone = Phone {
+            phone_id: 7,
+            number: "4321".parse().unwrap(),
+            category: userdb_core::PhoneCategory::Extension,
+            location: "Portland".into(),
+            label: None,
+            primary: true,
+            did: None,
+        };
+        let mut app = App::new("", true);
+        app.set_people(vec![PersonView { phones: Some(vec![phone]), ..person(1, "a") }]);
+        app.focus = Focus::List;
+        app.handle_key(key(KeyCode::Char('p')));
+        assert!(app.form.is_some());
+        // the person is found again, without their phone
+        app.set_people(vec![person(1, "a")]);
+        app.handle_key(key(KeyCode::PageDown));
+        assert!(app.form.is_none());
+    }
+}
@@ -0,0 +1,205 @@
+//! Inline edit forms for a person or one of their phones
+use userdb_core::prelude::*;
+use userdb_core::Phone;
+use userdb_core::PersonView;
+
+/// What a form edits
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    /// The person with the login
+    Person(String),
+    /// The phone with the id
+    Phone(i32),
+}
+
+/// A single field of a form, along with the value it started with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    pub label: &'static str,
+    pub value: String,
+    original: String,
+    /// Whether the field is a yes or no toggle rather than free text
+    pub toggle: bool,
+}
+
+impl Field {
+    fn text<I: Into<String>>(label: &'static str, value: I) -> Self {
+        let value = value.into();
+        Self { label, original: value.clone(), value, toggle: false }
+    }
+
+    fn toggle(label: &'static str, value: bool) -> Self {
+        let value = if value { "yes" } else { "no" }.to_string();
+        Self { label, original: value.clone(), value, toggle: true }
+    }
+
+    /// Whether the value differs from the one the field started with
+    pub fn is_changed(&self) -> bool {
+        self.value != self.original
+    }
+}
+
+/// A form editing the fields of a person or phone. Only the fields which
+/// are changed are saved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Form {
+    pub target: Target,
+    pub fields: Vec<Field>,
+    /// The index of the field being edited
+    pub focused: usize,
+}
+
+impl Form {
+    /// A form for the name, login, department, title, manager, status and
+    /// dates of a person. Clearing the manager or a date removes it.
+    pub fn person(person: &PersonView) -> Self {
+        let fields = vec![
+            Field::text("First", person.first.as_str()),
+            Field::text("Last", person.last.as_str()),
+            Field::text("Login", person.login.as_str()),
+            Field::text("Department", person.department.as_str()),
+            Field::text("Title", person.title.as_str()),
+            Field::text("Manager", person.manager.clone().unwrap_or_default()),
+            Field::text("Status", person.status.to_static_str()),
+            Field::text("Start date", person.start_date.clone().unwrap_or_default()),
+            Field::text("End date", person.end_date.clone().unwrap_or_default()),
+        ];
+        Self { target: Target::Person(person.login.clone()), fields, focused: 0 }
+    }
+
+    /// A form for the number, category, location, label and primary flag
+    /// of a phone. Clearing the label removes it.
+    pub fn phone(phone: &Phone) -> Self {
+        let fields = vec![
+            Field::text("Number", phone.number.to_string()),
+            Field::text("Category", phone.category.to_static_str()),
+            Field::text("Location", phone.location.as_str()),
+            Field::text("Label", phone.label.clone().unwrap_or_default()),
+            Field::toggle("Primary", phone.primary),
+        ];
+        Self { target: Target::Phone(phone.phone_id), fields, focused: 0 }
+    }
+
+    /// The heading of the form
+    pub fn title(&self) -> String {
+        match &self.target {
+            Target::Person(login) => format!("Edit {}", login),
+            Target::Phone(id) => format!("Edit phone {}", id),
+        }
+    }
+
+    /// Move to the next field, wrapping around
+    pub fn next(&mut self) {
+        self.focused = (self.focused + 1) % self.fields.len();
+    }
+
+    /// Move to the previous field, wrapping around
+    pub fn previous(&mut self) {
+        self.focused = (self.focused + self.fields.len() - 1) % self.fields.len();
+    }
+
+    /// Type a character into the focused field. Any character flips a toggle.
+    pub fn push(&mut self, c: char) {
+        let field = &mut self.fields[self.focused];
+        if field.toggle {
+            field.value = if field.value == "yes" { "no" } else { "yes" }.to_string();
+        } else {
+            field.value.push(c);
+        }
+    }
+
+    /// Delete the last character of the focused field
+    pub fn pop(&mut self) {
+        let field = &mut self.fields[self.focused];
+        if !field.toggle {
+            field.value.pop();
+        }
+    }
+
+    /// The new value of the field with `label`, if it was changed
+    pub fn changed(&self, label: &str) -> Option<String> {
+        self.fields.iter()
+            .find(|field| field.label == label && field.is_changed())
+            .map(|field| field.value.trim().to_string())
+    }
+
+    /// The value the field with `label` started with
+    pub fn original(&self, label: &str) -> Option<&str> {
+        self.fields.iter()
+            .find(|field| field.label == label)
+            .map(|field| field.original.as_str())
+    }
+
+    /// Whether any of the fields were changed
+    pub fn is_changed(&self) -> bool {
+        self.fields.iter().any(Field::is_changed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use userdb_core::PersonStatus;
+    use userdb_core::PhoneCategory;
+    use userdb_core::PhoneNumber;
+
+    fn person() -> PersonView {
+        PersonView {
+            person_id: 1,
+            first: "Sam".into(),
+            last: "Slade".into(),
+            fullname: "Sam Slade".into(),
+            login: "sslade".into(),
+            department: "Playa Software".into(),
+            title: "Engineer, Software".into(),
+            manager: None,
+            status: PersonStatus::Active,
+            start_date: Some("2020-01-06".into()),
+            end_date: None,
+            phones: None,
+            contacts: None,
+            desks: None,
+            score: None,
+        }
+    }
+
+    #[test]
+    fn changed_given_edits_reports_only_changed_fields() {
+        let mut form = Form::person(&person());
+        assert!(!form.is_changed());
+        form.push('s');
+        form.pop();
+        form.pop();
+        assert_eq!(form.changed("First"), Some("Sa".into()));
+        assert_eq!(form.changed("Last"), None);
+        form.previous();
+        form.pop();
+        assert_eq!(form.changed("End date"), None);
+        form.previous();
+        for _ in 0..10 {
+            form.pop();
+        }
+        assert_eq!(form.changed("Start date"), Some("".into()));
+    }
+
+    #[test]
+    fn push_given_toggle_flips_it() {
+        let phone = Phone {
+            phone_id: 7,
+            number: PhoneNumber::Extension("1234".into()),
+            category: PhoneCategory::Extension,
+            location: "Portland".into(),
+            label: None,
+            primary: false,
+            did: None,
+        };
+        let mut form = Form::phone(&phone);
+        assert_eq!(form.target, Target::Phone(7));
+        form.focused = 4;
+        form.push(' ');
+        assert_eq!(form.changed("Primary"), Some("yes".into()));
+        form.pop();
+        form.push('x');
+        assert_eq!(form.changed("Primary"), None);
+    }
+}
@@ -0,0 +1,146 @@
+//! Drawing the terminal ui: a search box above a list of people and the
+//! details of the selected person, with the edit form over the top
+use tui::backend::Backend;
+use tui::layout::{Constraint, Direction, Layout, Rect};
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap};
+use tui::Frame;
+
+use userdb_core::PersonStatus;
+use userdb_core::PersonView;
+use userdb_core::PhoneRow;
+
+use crate::process::read::{contact_label, format_contact};
+use super::app::{App, Focus};
+use super::form::Form;
+
+// the width of the labels in the form
+const LABEL_WIDTH: usize = 12;
+
+/// Draw the whole ui
+pub fn draw<B: Backend>(f: &mut Frame<B>, app: &mut App) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(0), Constraint::Length(1)].as_ref())
+        .split(f.size());
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(35), Constraint::Percentage(65)].as_ref())
+        .split(rows[1]);
+
+    let search = Paragraph::new(app.query.as_str())
+        .block(bordered("Search", app.focus == Focus::Search && app.form.is_none()));
+    f.render_widget(search, rows[0]);
+
+    let items = app.people.iter()
+        .map(|person| ListItem::new(format!("{} ({})", person.fullname, person.login)))
+        .collect::<Vec<_>>();
+    let list = List::new(items)
+        .block(bordered(&format!("People ({})", app.people.len()), app.focus == Focus::List && app.form.is_none()))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .highlight_symbol("> ");
+    f.render_stateful_widget(list, columns[0], &mut app.list);
+
+    let details = app.selected().map(details).unwrap_or_default();
+    let details = Paragraph::new(details)
+        .block(bordered("Details", false))
+        .wrap(Wrap { trim: false });
+    f.render_widget(details, columns[1]);
+
+    let status = Paragraph::new(app.message.clone().unwrap_or_else(|| help(app)))
+        .style(Style::default().fg(Color::DarkGray));
+    f.render_widget(status, rows[2]);
+
+    match &app.form {
+        Some(form) => draw_form(f, form),
+        None if app.focus == Focus::Search => {
+            f.set_cursor(rows[0].x + 1 + app.query.chars().count() as u16, rows[0].y + 1);
+        }
+        None => (),
+    }
+}
+
+// the keys which may be pressed, given where the focus is
+fn help(app: &App) -> String {
+    match (&app.form, app.focus) {
+        (Some(_), _) => "Tab/↑↓ field  Enter save  Esc cancel  PgUp/PgDn other phone".into(),
+        (None, Focus::Search) => "Type to search  Enter/Tab/↓ results  Ctrl-C quit".into(),
+        (None, Focus::List) if app.editable => "↑↓/jk move  / search  e edit person  p edit phones  q quit".into(),
+        (None, Focus::List) => "↑↓/jk move  / search  q quit".into(),
+    }
+}
+
+fn bordered(title: &str, focused: bool) -> Block<'static> {
+    let style = if focused { Style::default().fg(Color::Cyan) } else { Style::default() };
+    Block::default().borders(Borders::ALL).border_style(style).title(title.to_string())
+}
+
+fn label(text: &str) -> Span<'static> {
+    Span::styled(format!("{} ", text), Style::default().fg(Color::Cyan))
+}
+
+// the details of a person, laid out as the phone command prints them
+fn details(person: &PersonView) -> Vec<Spans<'static>> {
+    let mut lines = vec![
+        Spans::from(vec![label("User:"), Span::raw(person.login.clone())]),
+        Spans::from(vec![label("Full Name:"), Span::raw(person.fullname.clone())]),
+        Spans::from(vec![label("Dept:"), Span::raw(person.department.clone())]),
+        Spans::from(vec![label("Title:"), Span::raw(person.title.clone())]),
+    ];
+    if let Some(manager) = &person.manager {
+        lines.push(Spans::from(vec![label("Manager:"), Span::raw(manager.clone())]));
+    }
+    // the status is only worth mentioning when it is out of the ordinary
+    if person.status != PersonStatus::Active || person.start_date.is_some() || person.end_date.is_some() {
+        let dates = match (&person.start_date, &person.end_date) {
+            (Some(start), Some(end)) => format!(" ({} to {})", start, end),
+            (Some(start), None) => format!(" (from {})", start),
+            (None, Some(end)) => format!(" (until {})", end),
+            (None, None) => String::new(),
+        };
+        lines.push(Spans::from(vec![label("Status:"), Span::raw(format!("{}{}", person.status, dates))]));
+    }
+    for contact in person.contacts.iter().flatten() {
+        lines.push(Spans::from(vec![
+            label(&format!("{}:", contact_label(&contact.category))),
+            Span::raw(format_contact(contact)),
+        ]));
+    }
+    for desk in person.desks.iter().flatten() {
+        lines.push(Spans::from(vec![label("Desk:"), Span::raw(desk.to_string())]));
+    }
+    for row in PhoneRow::from_phones(person.phones.as_deref().unwrap_or(&[])) {
+        lines.push(Spans::default());
+        lines.extend(row.lines().into_iter().map(Spans::from));
+    }
+    lines
+}
+
+// the form, centered over the rest of the ui, with the cursor at the end
+// of the field being edited
+fn draw_form<B: Backend>(f: &mut Frame<B>, form: &Form) {
+    let area = centered(f.size(), 60, form.fields.len() as u16 + 2);
+    let lines = form.fields.iter().enumerate().map(|(idx, field)| {
+        let style = if idx == form.focused { Style::default().add_modifier(Modifier::BOLD) } else { Style::default() };
+        let marker = if field.is_changed() { "*" } else { " " };
+        Spans::from(vec![
+            Span::styled(format!("{:width$}{}", field.label, marker, width = LABEL_WIDTH), Style::default().fg(Color::Cyan)),
+            Span::styled(field.value.clone(), style),
+        ])
+    }).collect::<Vec<_>>();
+    f.render_widget(Clear, area);
+    f.render_widget(Paragraph::new(lines).block(bordered(&form.title(), true)), area);
+    let field = &form.fields[form.focused];
+    f.set_cursor(
+        area.x + 2 + LABEL_WIDTH as u16 + field.value.chars().count() as u16,
+        area.y + 1 + form.focused as u16,
+    );
+}
+
+// a rectangle of the supplied size in the middle of `area`, shrunk to fit
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect::new(area.x + (area.width - width) / 2, area.y + (area.height - height) / 2, width, height)
+}
@@ -0,0 +1,80 @@
+use percent_encoding::percent_decode_str;
+use serde::Serialize;
+use std::future::Future;
+use tide::{Body, Request, Response, Server, StatusCode};
+
+use crate::catalog;
+use crate::error::ApiError;
+use crate::people;
+use crate::phones;
+use crate::State;
+
+/// The result of an endpoint. Errors are turned into responses by `respond`
+pub type ApiResult = Result<Response, ApiError>;
+
+/// Route every endpoint
+pub fn build(state: State) -> Server<State> {
+    let mut app = tide::with_state(state);
+    app.at("/people")
+        .get(|req| respond(people::search(req)))
+        .post(|req| respond(people::create(req)));
+    app.at("/people/:login")
+        .get(|req| respond(people::get(req)))
+        .patch(|req| respond(people::update(req)))
+        .delete(|req| respond(people::delete(req)));
+    app.at("/people/:login/phones")
+        .post(|req| respond(phones::create(req)));
+    app.at("/phones")
+        .get(|req| respond(phones::search(req)));
+    app.at("/phones/:id")
+        .delete(|req| respond(phones::delete(req)));
+    app.at("/titles")
+        .get(|req| respond(catalog::titles(req)))
+        .post(|req| respond(catalog::create_title(req)));
+    app.at("/titles/:name")
+        .delete(|req| respond(catalog::delete_title(req)));
+    app.at("/departments")
+        .get(|req| respond(catalog::departments(req)))
+        .post(|req| respond(catalog::create_department(req)));
+    app.at("/departments/:name")
+        .delete(|req| respond(catalog::delete_department(req)));
+    app.at("/locations")
+        .get(|req| respond(catalog::locations(req)));
+    app
+}
+
+/// Await an endpoint, turning its error, if any, into a response
+async fn respond<F>(endpoint: F) -> tide::Result
+where
+    F: Future<Output = ApiResult>,
+{
+    Ok(endpoint.await.unwrap_or_else(Response::from))
+}
+
+/// The value of the route parameter `name`, eg the login in /people/:login
+pub fn param(req: &Request<State>, name: &str) -> Result<String, ApiError> {
+    percent_decode_str(req.param(name)?)
+        .decode_utf8()
+        .map(String::from)
+        .map_err(|_| ApiError::BadRequest(format!("The {} must be valid utf-8", name)))
+}
+
+/// A response with `status` and `value` as its json body
+pub fn json<T: Serialize>(status: StatusCode, value: &T) -> ApiResult {
+    let mut response = Response::new(status);
+    response.set_body(Body::from_json(value)?);
+    Ok(response)
+}
+
+/// A response with a page of `results` as its body. The total number
+/// of results, across every page, is returned in the X-Total-Count header.
+pub fn paginate<T: Serialize>(total: i64, results: Vec<T>) -> ApiResult {
+    let mut response = json(StatusCode::Ok, &results)?;
+    response.insert_header("X-Total-Count", total.to_string());
+    Ok(response)
+}
+
+/// A response reporting the id of the row created or changed
+pub fn id(status: StatusCode, id: i32) -> ApiResult {
+    json(status, &serde_json::json!({ "id": id }))
+}
@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use tide::Request;
+
+use crate::error::ApiError;
+use crate::State;
+
+/// The bearer tokens permitted to change the database, along with the
+/// actor each is recorded as in the audit log. A tokens file holds one
+/// actor and token per line, separated by whitespace:
+///
+/// ```text
+/// # actor    token
+/// intranet   5c1b6d...
+/// slackbot   9f00a2...
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Tokens {
+    // actors by token
+    actors: HashMap<String, String>,
+}
+
+impl FromStr for Tokens {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut actors = HashMap::new();
+        for (idx, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            match fields.as_slice() {
+                [actor, token] => { actors.insert(token.to_string(), actor.to_string()); }
+                _ => return Err(format!("line {}: expected an actor and a token", idx + 1)),
+            }
+        }
+        Ok(Self { actors })
+    }
+}
+
+impl Tokens {
+    /// Read the tokens from a file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Unable to read tokens file '{}': {}", path.display(), e))?;
+        Self::from_str(&contents).map_err(|e| format!("Invalid tokens file '{}': {}", path.display(), e))
+    }
+
+    /// The actor using the supplied token, if it is permitted
+    pub fn actor(&self, token: &str) -> Option<&str> {
+        self.actors.get(token).map(String::as_str)
+    }
+}
+
+/// The actor making the request, as identified by its bearer token.
+/// Fails unless the request carries a permitted token.
+pub fn authorize(req: &Request<State>) -> Result<String, ApiError> {
+    req.header("Authorization")
+        .map(|values| values.last().as_str())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .and_then(|token| req.state().tokens.actor(token.trim()))
+        .map(String::from)
+        .ok_or(ApiError::Unauthorized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_str_given_tokens_maps_them_to_actors() {
+        let tokens = Tokens::from_str("# actor token\nintranet abc\n\n  slackbot   xyz  \n").unwrap();
+        assert_eq!(tokens.actor("abc"), Some("intranet"));
+        assert_eq!(tokens.actor("xyz"), Some("slackbot"));
+        assert_eq!(tokens.actor("intranet"), None);
+    }
+
+    #[test]
+    fn from_str_given_bad_line_fails() {
+        let result = Tokens::from_str("intranet abc\nslackbot\n").unwrap_err();
+        assert_eq!(result, "line 2: expected an actor and a token");
+    }
+}
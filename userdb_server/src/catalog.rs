@@ -0,0 +1,93 @@
+//! Endpoints for titles, departments and locations
+use serde::Deserialize;
+use tide::{Request, StatusCode};
+
+// internal
+use userdb_core::create;
+use userdb_core::delete;
+use userdb_core::read;
+use userdb_core::UserDbError;
+
+use crate::app::{self, ApiResult};
+use crate::auth::authorize;
+use crate::page::Page;
+use crate::State;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NewName {
+    name: String,
+}
+
+/// GET /titles
+pub async fn titles(req: Request<State>) -> ApiResult {
+    let page: Page = req.query()?;
+    let db = &req.state().db;
+    let total = read::title::count(db).await?;
+    let results = read::title::page_json(db, page.limit(), page.offset()).await?;
+    app::paginate(total, results)
+}
+
+/// POST /titles
+pub async fn create_title(mut req: Request<State>) -> ApiResult {
+    let actor = authorize(&req)?;
+    let NewName{name} = req.body_json().await?;
+    let mut tx = req.state().db.begin_as(&actor).await?;
+    let id = create::title::create(&mut tx, &name).await?
+        .ok_or(UserDbError::DuplicateTitle(name))?;
+    tx.commit().await.map_err(UserDbError::from)?;
+    app::id(StatusCode::Created, id)
+}
+
+/// DELETE /titles/:name
+///
+/// Delete a title, as long as no one holds it
+pub async fn delete_title(req: Request<State>) -> ApiResult {
+    let actor = authorize(&req)?;
+    let name = app::param(&req, "name")?;
+    let mut tx = req.state().db.begin_as(&actor).await?;
+    let id = delete::title::delete(&mut tx, &name).await?;
+    tx.commit().await.map_err(UserDbError::from)?;
+    app::id(StatusCode::Ok, id)
+}
+
+/// GET /departments
+pub async fn departments(req: Request<State>) -> ApiResult {
+    let page: Page = req.query()?;
+    let db = &req.state().db;
+    let total = read::department::count(db).await?;
+    let results = read::department::page_json(db, page.limit(), page.offset()).await?;
+    app::paginate(total, results)
+}
+
+/// POST /departments
+pub async fn create_department(mut req: Request<State>) -> ApiResult {
+    let actor = authorize(&req)?;
+    let NewName{name} = req.body_json().await?;
+    let mut tx = req.state().db.begin_as(&actor).await?;
+    let id = create::department::create(&mut tx, &name).await?
+        .ok_or(UserDbError::DuplicateDepartment(name))?;
+    tx.commit().await.map_err(UserDbError::from)?;
+    app::id(StatusCode::Created, id)
+}
+
+/// DELETE /departments/:name
+///
+/// Delete a department, as long as no one is in it
+pub async fn delete_department(req: Request<State>) -> ApiResult {
+    let actor = authorize(&req)?;
+    let name = app::param(&req, "name")?;
+    let mut tx = req.state().db.begin_as(&actor).await?;
+    let id = delete::department::delete(&mut tx, &name).await?;
+    tx.commit().await.map_err(UserDbError::from)?;
+    app::id(StatusCode::Ok, id)
+}
+
+/// GET /locations
+pub async fn locations(req: Request<State>) -> ApiResult {
+    let page: Page = req.query()?;
+    let db = &req.state().db;
+    let total = read::location::count(db).await?;
+    let results = read::location::page_json(db, page.limit(), page.offset()).await?;
+    app::paginate(total, results)
+}
@@ -0,0 +1,125 @@
+use serde_json::json;
+use tide::{Body, Response, StatusCode};
+
+// internal
+use userdb_core::UserDbError;
+use userdb_core::UserDbErrorKind;
+
+/// Errors returned by the endpoints. Each is turned into a response with
+/// a json body of the form `{"error": "...", "kind": "..."}`.
+#[derive(Debug)]
+pub enum ApiError {
+    /// The library refused the request
+    UserDb(UserDbError),
+    /// A mutation was attempted without a valid token
+    Unauthorized,
+    /// The request was malformed
+    BadRequest(String),
+    /// The request could not be read, eg a bad query string or body
+    Http(tide::Error),
+}
+
+impl From<UserDbError> for ApiError {
+    fn from(error: UserDbError) -> Self {
+        Self::UserDb(error)
+    }
+}
+
+impl From<tide::Error> for ApiError {
+    fn from(error: tide::Error) -> Self {
+        Self::Http(error)
+    }
+}
+
+impl From<ApiError> for Response {
+    fn from(error: ApiError) -> Self {
+        let (status, kind, message) = match error {
+            ApiError::UserDb(e) => match status(e.kind()) {
+                // the detail of a failure of the server is logged rather
+                // than handed to the client, as it may describe the database
+                StatusCode::InternalServerError => {
+                    tide::log::error!("{}", e);
+                    (StatusCode::InternalServerError, format!("{:?}", e.kind()), "Internal server error".to_string())
+                }
+                status => (status, format!("{:?}", e.kind()), e.to_string()),
+            },
+            ApiError::Unauthorized => (StatusCode::Unauthorized, "Unauthorized".to_string(), "A valid bearer token is required".to_string()),
+            ApiError::BadRequest(message) => (StatusCode::BadRequest, "BadRequest".to_string(), message),
+            ApiError::Http(e) => (e.status(), "BadRequest".to_string(), e.to_string()),
+        };
+        let mut response = Response::new(status);
+        response.set_body(Body::from_json(&json!({"error": message, "kind": kind})).unwrap_or_else(|_| Body::from(message)));
+        response
+    }
+}
+
+/// The status code to respond with when the library fails with `kind`
+pub fn status(kind: UserDbErrorKind) -> StatusCode {
+    use UserDbErrorKind::*;
+    match kind {
+        NotFound => StatusCode::NotFound,
+        DuplicateLogin
+        | DuplicateFullName
+        | DuplicateDepartment
+        | DuplicateTitle
+        | DuplicatePhone
+        | DuplicateLocation
+        | DuplicateContact
+        | ManagerCycle
+        | DepartmentInUse
+        | SelfMerge
+        | PhoneAlreadyAssigned
+        | PhoneNotShared
+        | TitleInUse
+        | LocationInUse => StatusCode::Conflict,
+        UnknownDepartment
+        | UnknownTitle
+        | UnknownLocation
+        | UnknownManager
+        | InvalidTimezone
+        | InvalidCountryCode
+        | InvalidCallingCode
+        | InvalidTrunkPrefix
+        | InvalidNumber
+        | InvalidEmail
+        | EmptyContact
+        | EndBeforeStart
+        | ExtensionOutsideDialPlan
+        | Phone
+        | InvalidFormat
+        | InvalidConflictPolicy
+        | InvalidSnapshot => StatusCode::UnprocessableEntity,
+        Json | Io | Sqlx => StatusCode::InternalServerError,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_given_kinds_maps_to_codes() {
+        assert_eq!(status(UserDbErrorKind::NotFound), StatusCode::NotFound);
+        assert_eq!(status(UserDbErrorKind::DuplicateLogin), StatusCode::Conflict);
+        assert_eq!(status(UserDbErrorKind::UnknownTitle), StatusCode::UnprocessableEntity);
+        assert_eq!(status(UserDbErrorKind::Sqlx), StatusCode::InternalServerError);
+    }
+
+    #[test]
+    fn response_given_not_found_has_json_body() {
+        let error = ApiError::from(UserDbError::NotFound("Person 'jdoe'".into()));
+        let response = Response::from(error);
+        assert_eq!(response.status(), StatusCode::NotFound);
+        assert_eq!(response.content_type(), Some(tide::http::mime::JSON));
+    }
+
+    #[async_std::test]
+    async fn response_given_internal_error_hides_detail() {
+        let error = ApiError::from(UserDbError::from(std::io::Error::other("/srv/secret unreadable")));
+        let mut response = Response::from(error);
+        assert_eq!(response.status(), StatusCode::InternalServerError);
+        let body = response.take_body().into_string().await.unwrap();
+        assert!(!body.contains("secret"), "{}", body);
+        assert!(body.contains("Internal server error"), "{}", body);
+    }
+}
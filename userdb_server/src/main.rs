@@ -0,0 +1,71 @@
+//! userdb-server
+//!
+//! Serve the userdb over http. People, phones, titles and departments may
+//! be read by anyone; changing them requires a bearer token.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use structopt::StructOpt;
+
+// internal
+use userdb_core::Config;
+use userdb_core::UserDb;
+
+mod app;
+mod auth;
+mod catalog;
+mod error;
+mod page;
+mod people;
+mod phones;
+
+use auth::Tokens;
+
+/// The state shared by every request
+#[derive(Clone)]
+pub struct State {
+    pub db: UserDb,
+    pub tokens: Arc<Tokens>,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(about="Serve the userdb over http")]
+pub struct Opt {
+    /// The address to listen on
+    #[structopt(short, long, default_value="127.0.0.1:8080")]
+    pub listen: String,
+    /// A file of the tokens permitted to make changes, one actor and token
+    /// per line. Without it, the database may only be read
+    #[structopt(short, long, parse(from_os_str))]
+    pub tokens: Option<PathBuf>,
+    /// The profile in the config file to connect with
+    #[structopt(short, long)]
+    pub profile: Option<String>,
+    /// The url of the database to connect to, overriding the profile
+    #[structopt(long="database-url")]
+    pub database_url: Option<String>,
+}
+
+#[async_std::main]
+async fn main() {
+    let opt = Opt::from_args();
+    // warnings and errors only, so that failures hidden from clients are recorded
+    tide::log::with_level(tide::log::LevelFilter::Warn);
+    if let Err(e) = run(opt).await {
+        eprintln!("\n\tError: {}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run(opt: Opt) -> Result<(), Box<dyn std::error::Error>> {
+    let Opt{listen, tokens, profile, database_url} = opt;
+    let tokens = match tokens {
+        Some(path) => Tokens::from_file(path)?,
+        None => Tokens::default(),
+    };
+    let profile = Config::load_profile(profile.as_deref(), database_url.as_deref())?;
+    let db = UserDb::connect(&profile).await?;
+    let app = app::build(State{db, tokens: Arc::new(tokens)});
+    app.listen(listen).await?;
+    Ok(())
+}
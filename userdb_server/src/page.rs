@@ -0,0 +1,71 @@
+use serde::Deserialize;
+use std::convert::TryFrom;
+use std::num::NonZeroU32;
+
+const DEFAULT_PER_PAGE: usize = 50;
+const MAX_PER_PAGE: usize = 500;
+// the most results per page when listing without a search, so that
+// an unfiltered request cannot fetch everyone at once
+const MAX_UNFILTERED_PER_PAGE: usize = 100;
+
+/// The page of results requested via the `page` and `per_page` query
+/// parameters. Pages are numbered from 1.
+#[derive(Debug, Default, PartialEq, Eq, Deserialize)]
+pub struct Page {
+    pub page: Option<usize>,
+    pub per_page: Option<usize>,
+}
+
+impl Page {
+    /// The number of results per page, within limits
+    pub fn per_page(&self) -> usize {
+        self.per_page.unwrap_or(DEFAULT_PER_PAGE).clamp(1, MAX_PER_PAGE)
+    }
+
+    /// The page, with at most MAX_UNFILTERED_PER_PAGE results, for
+    /// listings which are not narrowed by a search
+    pub fn unfiltered(self) -> Self {
+        let per_page = self.per_page().min(MAX_UNFILTERED_PER_PAGE);
+        Self { page: self.page, per_page: Some(per_page) }
+    }
+
+    /// The most results to fetch for the page
+    pub fn limit(&self) -> Option<NonZeroU32> {
+        NonZeroU32::new(u32::try_from(self.per_page()).unwrap_or(u32::MAX))
+    }
+
+    /// The number of results preceding the page
+    pub fn offset(&self) -> u32 {
+        let offset = self.page.unwrap_or(1).saturating_sub(1).saturating_mul(self.per_page());
+        u32::try_from(offset).unwrap_or(u32::MAX)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offset_given_default_page_is_zero() {
+        let page = Page::default();
+        assert_eq!(page.offset(), 0);
+        assert_eq!(page.limit(), NonZeroU32::new(DEFAULT_PER_PAGE as u32));
+    }
+
+    #[test]
+    fn offset_given_page_skips_earlier_pages() {
+        let page = Page { page: Some(3), per_page: Some(4) };
+        assert_eq!(page.offset(), 8);
+        assert_eq!(page.limit(), NonZeroU32::new(4));
+        let page = Page { page: Some(usize::MAX), per_page: Some(4) };
+        assert_eq!(page.offset(), u32::MAX);
+    }
+
+    #[test]
+    fn per_page_is_clamped() {
+        assert_eq!(Page { page: None, per_page: Some(0) }.per_page(), 1);
+        assert_eq!(Page { page: None, per_page: Some(10_000) }.per_page(), MAX_PER_PAGE);
+        assert_eq!(Page { page: None, per_page: Some(10_000) }.unfiltered().per_page(), MAX_UNFILTERED_PER_PAGE);
+        assert_eq!(Page { page: None, per_page: Some(20) }.unfiltered().per_page(), 20);
+    }
+}
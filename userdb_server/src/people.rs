@@ -0,0 +1,174 @@
+//! Endpoints for people
+use serde::Deserialize;
+use std::str::FromStr;
+use tide::{Request, StatusCode};
+
+// internal
+use userdb_core::create;
+use userdb_core::delete;
+use userdb_core::read;
+use userdb_core::read::person::PersonQuery;
+use userdb_core::read::person_simple::SimplePersonQuery;
+use userdb_core::update;
+use userdb_core::update::person::id::PersonUpdate as PersonUpdateById;
+use userdb_core::update::person::login::PersonUpdate as PersonUpdateByLogin;
+use userdb_core::prelude::*;
+use userdb_core::Date;
+use userdb_core::PersonStatus;
+use userdb_core::QueryMode;
+use userdb_core::UserDbError;
+
+use crate::app::{self, ApiResult};
+use crate::auth::authorize;
+use crate::error::ApiError;
+use crate::page::Page;
+use crate::State;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct SearchParams {
+    /// The name, login, department, title, contact, building or room to search on
+    q: Option<String>,
+    include_inactive: bool,
+    as_of: Option<String>,
+    /// Also find people whose name or login is close to `q`
+    fuzzy: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct GetParams {
+    as_of: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NewPerson {
+    first: String,
+    last: String,
+    login: String,
+    department: String,
+    title: String,
+    status: Option<String>,
+    start_date: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PersonPatch {
+    first: Option<String>,
+    last: Option<String>,
+    login: Option<String>,
+    department_id: Option<i32>,
+    title_id: Option<i32>,
+    manager: Option<String>,
+    status: Option<String>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+}
+
+/// Parse the optional `as_of` query parameter
+pub(crate) fn as_of(value: Option<&str>) -> Result<Option<Date>, UserDbError> {
+    Ok(value.map(Date::from_str).transpose()?)
+}
+
+/// GET /people?q=
+///
+/// The people matching `q`, best matches first, as the phone command finds them
+pub async fn search(req: Request<State>) -> ApiResult {
+    let SearchParams{q, include_inactive, as_of: day, fuzzy} = req.query()?;
+    let q = q.unwrap_or_default();
+    let mut page: Page = req.query()?;
+    if q.trim().is_empty() {
+        page = page.unfiltered();
+    }
+    let db = &req.state().db;
+    let query = SimplePersonQuery::new(q)
+        .include_inactive(include_inactive)
+        .as_of(as_of(day.as_deref())?)
+        .fuzzy(fuzzy)
+        .limit(page.limit())
+        .offset(page.offset());
+    let total = read::person_simple::count(db, &query, QueryMode::ILike).await?;
+    let results = read::person_simple::query_json(db, query, QueryMode::ILike).await?;
+    app::paginate(total, results)
+}
+
+/// GET /people/:login
+///
+/// The person with the supplied login, whether or not they are active
+pub async fn get(req: Request<State>) -> ApiResult {
+    let GetParams{as_of: day} = req.query()?;
+    let login = app::param(&req, "login")?;
+    let query = PersonQuery::new()
+        .login(Some(login.clone()))
+        .include_inactive(true)
+        .as_of(as_of(day.as_deref())?);
+    let results = read::person::query_json(&req.state().db, query, QueryMode::Exact).await?;
+    match results.first() {
+        Some(person) => app::json(StatusCode::Ok, person),
+        None => Err(UserDbError::NotFound(format!("Person '{}'", login)).into()),
+    }
+}
+
+/// POST /people
+///
+/// Create a person, along with their status and start date if supplied
+pub async fn create(mut req: Request<State>) -> ApiResult {
+    let actor = authorize(&req)?;
+    let NewPerson{first, last, login, department, title, status, start_date} = req.body_json().await?;
+    let status = status.as_deref().map(PersonStatus::from_str).transpose().map_err(UserDbError::from)?;
+    let start_date = start_date.as_deref().map(Date::from_str).transpose().map_err(UserDbError::from)?;
+    let mut tx = req.state().db.begin_as(&actor).await?;
+    let id = create::person::create(&mut tx, first, last, &login, department, title).await?;
+    let person_update = PersonUpdateById::new(id)
+        .status(status)
+        .start_date(start_date.map(|date| date.to_string()));
+    if !person_update.is_empty() {
+        update::person::id::update(&mut tx, person_update).await?;
+    }
+    tx.commit().await.map_err(UserDbError::from)?;
+    let mut response = app::id(StatusCode::Created, id)?;
+    response.insert_header("Location", format!("/people/{}", login));
+    Ok(response)
+}
+
+/// PATCH /people/:login
+///
+/// Change the supplied fields of a person. An empty manager, start date
+/// or end date clears it.
+pub async fn update(mut req: Request<State>) -> ApiResult {
+    let actor = authorize(&req)?;
+    let login = app::param(&req, "login")?;
+    let PersonPatch{first, last, login: new_login, department_id, title_id, manager, status, start_date, end_date} = req.body_json().await?;
+    let status = status.as_deref().map(PersonStatus::from_str).transpose().map_err(UserDbError::from)?;
+    let person_update = PersonUpdateByLogin::new(login)
+        .first(first)
+        .last(last)
+        .login(new_login)
+        .department(department_id)
+        .title(title_id)
+        .manager(manager)
+        .status(status)
+        .start_date(start_date)
+        .end_date(end_date);
+    if person_update.is_empty() {
+        return Err(ApiError::BadRequest("No changes supplied".into()));
+    }
+    let mut tx = req.state().db.begin_as(&actor).await?;
+    let id = update::person::login::update(&mut tx, person_update).await?;
+    tx.commit().await.map_err(UserDbError::from)?;
+    app::id(StatusCode::Ok, id)
+}
+
+/// DELETE /people/:login
+///
+/// Mark a person as terminated
+pub async fn delete(req: Request<State>) -> ApiResult {
+    let actor = authorize(&req)?;
+    let login = app::param(&req, "login")?;
+    let mut tx = req.state().db.begin_as(&actor).await?;
+    let id = delete::person::delete(&mut tx, &login).await?;
+    tx.commit().await.map_err(UserDbError::from)?;
+    app::id(StatusCode::Ok, id)
+}
@@ -0,0 +1,108 @@
+//! Endpoints for phones
+use serde::Deserialize;
+use std::str::FromStr;
+use tide::{Request, StatusCode};
+
+// internal
+use userdb_core::create;
+use userdb_core::delete;
+use userdb_core::read;
+use userdb_core::read::person::PersonQuery;
+use userdb_core::read::phone::PhoneQuery;
+use userdb_core::Locations;
+use userdb_core::NumberString;
+use userdb_core::PhoneCategory;
+use userdb_core::QueryMode;
+use userdb_core::UserDbError;
+
+use crate::app::{self, ApiResult};
+use crate::auth::authorize;
+use crate::error::ApiError;
+use crate::page::Page;
+use crate::people::as_of;
+use crate::State;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct SearchParams {
+    number: Option<String>,
+    category: Option<String>,
+    location: Option<String>,
+    as_of: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NewPhone {
+    number: String,
+    category: String,
+    location: String,
+    label: Option<String>,
+    #[serde(default)]
+    primary: bool,
+}
+
+/// GET /phones
+///
+/// The phones matching the number, category and location supplied
+pub async fn search(req: Request<State>) -> ApiResult {
+    let SearchParams{number, category, location, as_of: day} = req.query()?;
+    let mut page: Page = req.query()?;
+    if number.is_none() && category.is_none() && location.is_none() {
+        page = page.unfiltered();
+    }
+    let db = &req.state().db;
+    let number = number.map(|number| NumberString::new(&number)).transpose().map_err(UserDbError::from)?;
+    let category = category.as_deref().map(PhoneCategory::from_str).transpose().map_err(UserDbError::from)?;
+    let location = match location {
+        Some(location) => Some(Locations::load(db).await?.parse(&location).map_err(UserDbError::from)?.clone()),
+        None => None,
+    };
+    let query = PhoneQuery::new()
+        .number(number)
+        .category(category)
+        .location(location)
+        .as_of(as_of(day.as_deref())?)
+        .limit(page.limit())
+        .offset(page.offset());
+    let total = read::phone::count(db, &query, QueryMode::ILike).await?;
+    let results = read::phone::query_json(db, query, QueryMode::ILike).await?;
+    app::paginate(total, results)
+}
+
+/// POST /people/:login/phones
+///
+/// Create a phone and assign it to the person with the supplied login
+pub async fn create(mut req: Request<State>) -> ApiResult {
+    let actor = authorize(&req)?;
+    let login = app::param(&req, "login")?;
+    let NewPhone{number, category, location, label, primary} = req.body_json().await?;
+    let number = NumberString::new(&number).map_err(UserDbError::from)?;
+    let category = PhoneCategory::from_str(&category).map_err(UserDbError::from)?;
+    let mut tx = req.state().db.begin_as(&actor).await?;
+    // addPhone reports an unknown person and an existing phone alike, so
+    // the person is looked up first
+    let person = PersonQuery::new().login(Some(login.clone())).include_inactive(true);
+    if read::person::query_json(&mut tx, person, QueryMode::Exact).await?.is_empty() {
+        return Err(UserDbError::NotFound(format!("Person '{}'", login)).into());
+    }
+    let locations = Locations::load(&mut tx).await?;
+    let location = locations.parse(&location).map_err(UserDbError::from)?;
+    let id = create::phone::create(&mut tx, &login, &number, &category, location, label.as_deref(), primary).await?
+        .ok_or_else(|| ApiError::from(UserDbError::DuplicatePhone(number.to_string())))?;
+    tx.commit().await.map_err(UserDbError::from)?;
+    app::id(StatusCode::Created, id)
+}
+
+/// DELETE /phones/:id
+///
+/// Delete a phone, along with its assignments to people
+pub async fn delete(req: Request<State>) -> ApiResult {
+    let actor = authorize(&req)?;
+    let id: u32 = app::param(&req, "id")?.parse()
+        .map_err(|_| ApiError::BadRequest("The phone id must be a positive integer".into()))?;
+    let mut tx = req.state().db.begin_as(&actor).await?;
+    let id = delete::phone::delete_by_id(&mut tx, id).await?;
+    tx.commit().await.map_err(UserDbError::from)?;
+    app::id(StatusCode::Ok, id)
+}